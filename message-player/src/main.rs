@@ -1,5 +1,9 @@
 use anyhow::Result;
-use rabbitmq_config::{get_password, load_config_file, RabbitMQClient, RabbitMQConfig, RabbitMQMessage, MessageProperties};
+use clap::Parser;
+use rabbitmq_config::{
+    ConfigArgs, MessageProperties, PeekOptions, PeekedMessage, QueueConfig, RabbitMQClient,
+    RabbitMQFullConfig, RabbitMQMessage, StreamOffset,
+};
 use serde::Deserialize;
 use serde_json::json;
 use std::fs;
@@ -16,6 +20,7 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, List, ListItem, ListState},
     Terminal,
 };
@@ -41,6 +46,9 @@ struct MessageType {
     durable: bool,
 }
 
+/// Number of messages shown for the selected queue.
+const PEEK_LIMIT: usize = 10;
+
 // --- Application State ---
 
 struct App<'a> {
@@ -49,13 +57,20 @@ struct App<'a> {
     categories: Vec<Category>,
     template_list_state: ListState,
     client: RabbitMQClient,
+    queues: Vec<QueueConfig>,
+    queue_list_state: ListState,
+    peeked: Vec<PeekedMessage>,
     logs: Vec<String>,
 }
 
 impl<'a> App<'a> {
-    async fn new(categories: Vec<Category>) -> Result<Self> {
+    async fn new(categories: Vec<Category>, file_config: RabbitMQFullConfig) -> Result<Self> {
         let mut editor = TextArea::default();
-        editor.set_block(Block::default().borders(Borders::ALL).title("Message Editor"));
+        editor.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Message Editor"),
+        );
 
         let mut template_list_state = ListState::default();
         if !categories.is_empty() {
@@ -63,18 +78,12 @@ impl<'a> App<'a> {
         }
 
         // --- Connect to RabbitMQ ---
-        let file_config = load_config_file()?;
-        let conn_info = file_config.connection;
-        let password = get_password()?;
-        let config = RabbitMQConfig {
-            host: conn_info.host,
-            amqp_port: conn_info.amqp_port,
-            management_port: conn_info.management_port,
-            username: conn_info.username,
-            password,
-            vhost: conn_info.vhost,
-        };
-        let client = RabbitMQClient::new(config).await?;
+        let client = RabbitMQClient::from_config(&file_config).await?;
+
+        let mut queue_list_state = ListState::default();
+        if !file_config.queues.is_empty() {
+            queue_list_state.select(Some(0));
+        }
 
         let mut app = App {
             should_quit: false,
@@ -82,6 +91,9 @@ impl<'a> App<'a> {
             categories,
             template_list_state,
             client,
+            queues: file_config.queues,
+            queue_list_state,
+            peeked: Vec::new(),
             logs: Vec::new(),
         };
 
@@ -100,7 +112,11 @@ impl<'a> App<'a> {
         let flat_templates = self.get_flat_templates();
         let i = match self.template_list_state.selected() {
             Some(i) => {
-                if i >= flat_templates.len() - 1 { 0 } else { i + 1 }
+                if i >= flat_templates.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
             }
             None => 0,
         };
@@ -112,7 +128,11 @@ impl<'a> App<'a> {
         let flat_templates = self.get_flat_templates();
         let i = match self.template_list_state.selected() {
             Some(i) => {
-                if i == 0 { flat_templates.len() - 1 } else { i - 1 }
+                if i == 0 {
+                    flat_templates.len() - 1
+                } else {
+                    i - 1
+                }
             }
             None => 0,
         };
@@ -132,6 +152,45 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Selects the next queue. Its messages are only read on F5, as peeking marks them redelivered.
+    fn next_queue(&mut self) {
+        if self.queues.is_empty() {
+            return;
+        }
+        let i = match self.queue_list_state.selected() {
+            Some(i) => (i + 1) % self.queues.len(),
+            None => 0,
+        };
+        self.queue_list_state.select(Some(i));
+        self.peeked.clear();
+    }
+
+    /// Shows the first messages of the selected queue, leaving them in the queue.
+    async fn peek_selected_queue(&mut self) {
+        let Some(queue) = self
+            .queue_list_state
+            .selected()
+            .and_then(|i| self.queues.get(i))
+        else {
+            return;
+        };
+        let is_stream =
+            queue.arguments.get("x-queue-type").and_then(|t| t.as_str()) == Some("stream");
+        let options = if is_stream {
+            PeekOptions::stream(PEEK_LIMIT, StreamOffset::First)
+        } else {
+            PeekOptions::new(PEEK_LIMIT)
+        };
+        match self.client.peek_messages(&queue.name, &options).await {
+            Ok(messages) => self.peeked = messages,
+            Err(e) => {
+                self.peeked.clear();
+                self.logs
+                    .push(format!("Error peeking into '{}': {}", queue.name, e));
+            }
+        }
+    }
+
     async fn publish_current_message(&mut self) {
         if let Some(selected_index) = self.template_list_state.selected() {
             let flat_templates = self.get_flat_templates();
@@ -143,13 +202,20 @@ impl<'a> App<'a> {
                     routing_key: template.name.clone(),
                     payload: payload.into_bytes(),
                     properties: Some(MessageProperties {
-                        priority: if template.priority > 0 { Some(template.priority) } else { None },
+                        priority: if template.priority > 0 {
+                            Some(template.priority)
+                        } else {
+                            None
+                        },
                         ..Default::default()
                     }),
                 };
 
                 match self.client.publish_message(&message).await {
-                    Ok(_) => self.logs.push(format!("Published message to '{}'", template.name)),
+                    Ok(_) => {
+                        self.logs
+                            .push(format!("Published message to '{}'", template.name));
+                    }
                     Err(e) => self.logs.push(format!("Error publishing: {}", e)),
                 }
             }
@@ -159,10 +225,20 @@ impl<'a> App<'a> {
 
 // --- Main Application Logic ---
 
+/// Publishes messages built from `artifacts/message_types.json` and peeks into the configured queues.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let file_config = cli.config.load()?.config;
     let categories = load_message_categories()?;
-    let mut app = App::new(categories).await?;
+    let mut app = App::new(categories, file_config).await?;
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -197,8 +273,12 @@ fn generate_sample_payload(message_name: &str) -> String {
         "user.create" => json!({ "email": "user@example.com", "name": "Test User" }),
         "user.update" => json!({ "id": "user-123", "name": "Updated Name" }),
         "user.delete" => json!({ "id": "user-123" }),
-        "order.placed" => json!({ "order_id": "ord-456", "amount": 99.99, "items": [ "item-1", "item-2" ] }),
-        "payment.processed" => json!({ "order_id": "ord-456", "status": "success", "transaction_id": "txn-789" }),
+        "order.placed" => {
+            json!({ "order_id": "ord-456", "amount": 99.99, "items": [ "item-1", "item-2" ] })
+        }
+        "payment.processed" => {
+            json!({ "order_id": "ord-456", "status": "success", "transaction_id": "txn-789" })
+        }
         "inventory.updated" => json!({ "item_id": "item-1", "quantity": 50 }),
         "user.lookup" => json!({ "email": "user@example.com" }),
         "order.status" => json!({ "order_id": "ord-456" }),
@@ -207,7 +287,10 @@ fn generate_sample_payload(message_name: &str) -> String {
     serde_json::to_string_pretty(&payload).unwrap_or_default()
 }
 
-async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App<'_>) -> io::Result<()> {
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App<'_>,
+) -> io::Result<()> {
     loop {
         if app.should_quit {
             return Ok(());
@@ -221,6 +304,8 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
                 KeyCode::Char('p') => app.publish_current_message().await,
                 KeyCode::Up => app.previous_template(),
                 KeyCode::Down => app.next_template(),
+                KeyCode::Tab => app.next_queue(),
+                KeyCode::F(5) => app.peek_selected_queue().await,
                 _ => {
                     app.editor.input(key);
                 }
@@ -241,10 +326,7 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
 
     let top_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(30),
-            Constraint::Percentage(70),
-        ])
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
         .split(main_chunks[0]);
 
     // --- Producers Pane ---
@@ -255,8 +337,16 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
         .collect();
 
     let producers_list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Producers (Templates)"))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Producers (Templates)"),
+        )
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Cyan),
+        )
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(producers_list, top_chunks[0], &mut app.template_list_state);
@@ -265,12 +355,81 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
     f.render_widget(app.editor.widget(), top_chunks[1]);
 
     // --- Consumers Pane ---
-    let consumers_pane = Block::default().title("Consumers (Queues)").borders(Borders::ALL);
-    f.render_widget(consumers_pane, main_chunks[1]);
+    let consumer_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(main_chunks[1]);
+
+    let queue_items: Vec<ListItem> = if app.queues.is_empty() {
+        vec![ListItem::new("No queues configured")]
+    } else {
+        app.queues
+            .iter()
+            .map(|q| ListItem::new(q.name.as_str()))
+            .collect()
+    };
+    let queue_list = List::new(queue_items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Consumers (Queues) [Tab]"),
+        )
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Cyan),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(queue_list, consumer_chunks[0], &mut app.queue_list_state);
+
+    let message_items: Vec<ListItem> = app
+        .peeked
+        .iter()
+        .flat_map(peeked_message_lines)
+        .map(ListItem::new)
+        .collect();
+    let messages_list = List::new(message_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Messages (F5 to peek)"),
+    );
+    f.render_widget(messages_list, consumer_chunks[1]);
 
     // --- Logs Pane ---
-    let log_items: Vec<ListItem> = app.logs.iter().rev().map(|l| ListItem::new(l.as_str())).collect();
-    let logs_list = List::new(log_items)
-        .block(Block::default().borders(Borders::ALL).title("Logs"));
+    let log_items: Vec<ListItem> = app
+        .logs
+        .iter()
+        .rev()
+        .map(|l| ListItem::new(l.as_str()))
+        .collect();
+    let logs_list =
+        List::new(log_items).block(Block::default().borders(Borders::ALL).title("Logs"));
     f.render_widget(logs_list, main_chunks[2]);
 }
+
+/// A summary line for a peeked message, then its properties and headers, then its payload.
+fn peeked_message_lines(peeked: &PeekedMessage) -> Vec<Line<'static>> {
+    let message = &peeked.message;
+    let mut summary = format!(
+        "#{} '{}' {} bytes",
+        peeked.position,
+        message.routing_key,
+        message.payload.len()
+    );
+    if peeked.redelivered {
+        summary.push_str(" (redelivered)");
+    }
+    let properties = peeked
+        .properties()
+        .display_fields()
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let payload = String::from_utf8_lossy(&message.payload).replace(['\n', '\r'], " ");
+    vec![
+        Line::from(summary),
+        Line::from(format!("  {}", properties)),
+        Line::from(format!("  {}", payload)),
+    ]
+}
@@ -0,0 +1,471 @@
+use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
+use lapin::{
+    acker::Acker, message, options::*, publisher_confirm::PublisherConfirm, types::FieldTable,
+    BasicProperties, Channel, Consumer,
+};
+use log::{debug, info, warn};
+use rabbitmq_config::{
+    await_confirm, publish_confirmed_batch, ChannelConfig, ConnectionConfig, Endpoint,
+    ExchangeInfo, MessageProperties, PublisherConfig, QueueInfo, RabbitMQConfig, RabbitMQError,
+    RabbitMQFullConfig, RabbitMQMessage, RetryConfig, ReturnHandler, ReturnedMessage, TlsOptions,
+};
+use std::sync::Arc;
+
+use crate::error::MessagingError;
+use crate::subscription::{Delivery, DeliveryAcker, DeliveryStream, SubscribeOptions};
+use crate::traits::MessagingClient;
+
+mod retry;
+mod rpc;
+mod session;
+mod worker;
+
+pub use retry::{
+    retry_count, RetryPolicy, RetryStep, LAST_ERROR_HEADER, ORIGINAL_EXCHANGE_HEADER,
+    ORIGINAL_ROUTING_KEY_HEADER, RETRY_COUNT_HEADER,
+};
+pub use rpc::{RpcClient, RpcRequest, RpcServer};
+use session::{SessionManager, TopologyItem};
+pub use worker::{shutdown_signal, ConsumerWorker, HandlerError, MessageHandler, WorkerStats};
+
+/// AMQP-specific options for a single publish.
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Ask the broker to return the message if it cannot be routed to any queue.
+    pub mandatory: bool,
+    /// Basic properties and headers sent with the message.
+    pub properties: MessageProperties,
+}
+
+/// Fails with a configuration error when `immediate` is set, as RabbitMQ does not support it.
+impl TryFrom<&PublisherConfig> for PublishOptions {
+    type Error = MessagingError;
+
+    fn try_from(publisher: &PublisherConfig) -> Result<Self, Self::Error> {
+        if publisher.immediate {
+            return Err(RabbitMQError::ConfigError(
+                "The immediate flag is not supported by RabbitMQ".to_string(),
+            )
+            .into());
+        }
+        Ok(Self {
+            mandatory: publisher.mandatory,
+            properties: MessageProperties::from(publisher),
+        })
+    }
+}
+
+pub struct AmqpClient {
+    session: SessionManager,
+    return_handler: Option<ReturnHandler>,
+}
+
+impl AmqpClient {
+    /// Creates a new `AmqpClient` with the given configuration.
+    pub fn new(config: RabbitMQConfig) -> Self {
+        Self {
+            session: SessionManager::new(config),
+            return_handler: None,
+        }
+    }
+
+    /// Creates a client from a configuration file: connection settings, channel
+    /// settings and, when `retry` is set, automatic recovery.
+    ///
+    /// The password is resolved as in `ConnectionConfig::to_rabbitmq_config`.
+    /// Call `connect` to open the connection.
+    pub fn from_config(full_config: &RabbitMQFullConfig) -> Result<Self, MessagingError> {
+        let config = full_config.connection.to_rabbitmq_config()?;
+        let mut client = Self::new(config)
+            .with_connection_settings(full_config.connection.clone())
+            .with_channel_config(full_config.channel.clone());
+        if let Some(retry) = &full_config.retry {
+            client = client.with_recovery(retry.clone());
+        }
+        Ok(client)
+    }
+
+    /// Registers a handler for unroutable mandatory messages.
+    ///
+    /// Without a handler, they are reported as `MessagingError::Unroutable`.
+    pub fn on_return<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ReturnedMessage) + Send + Sync + 'static,
+    {
+        self.return_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Enables automatic recovery of the connection and channel.
+    ///
+    /// When the connection is lost, the next operation reconnects with backoff
+    /// driven by `retry`, redeclares the topology declared through this client
+    /// and resubscribes active subscriptions. `connect` also retries on failure.
+    pub fn with_recovery(mut self, retry: RetryConfig) -> Self {
+        self.session.set_recovery(retry);
+        self
+    }
+
+    /// Applies the connection timeout, heartbeat, connection name, client properties
+    /// and TLS settings of `settings`. Host, credentials and vhost are not taken from it.
+    pub fn with_connection_settings(mut self, settings: ConnectionConfig) -> Self {
+        self.session.set_connection_settings(settings);
+        self
+    }
+
+    /// Connects over AMQPS (TLS), optionally with a client certificate for mutual TLS.
+    ///
+    /// See `rabbitmq_config::connect_amqps` for how `tls_options` are applied; `None`
+    /// trusts the system roots and verifies the hostname.
+    pub fn with_tls(mut self, tls_options: Option<TlsOptions>) -> Self {
+        self.session.set_tls(tls_options);
+        self
+    }
+
+    /// Applies channel settings such as prefetch and publisher confirms.
+    ///
+    /// With `confirm_deliveries` enabled, `publish` only resolves once the broker
+    /// has acked the message and fails with `MessagingError::PublishNacked` on a nack.
+    pub fn with_channel_config(mut self, channel_config: ChannelConfig) -> Self {
+        self.session.set_channel_config(channel_config);
+        self
+    }
+
+    /// The cluster node the client is connected to. A node whose connection
+    /// dropped counts as failed until a reconnect to it succeeds.
+    pub fn connected_endpoint(&self) -> Option<Endpoint> {
+        self.session.connected_endpoint()
+    }
+
+    /// Nodes that could not be reached, which are tried last on reconnect.
+    pub fn failed_endpoints(&self) -> Vec<Endpoint> {
+        self.session.failed_endpoints()
+    }
+
+    /// Returns whether the client currently holds an open channel.
+    pub async fn is_connected(&self) -> bool {
+        self.session.is_connected().await
+    }
+
+    /// Publishes a batch of messages and waits for all of their confirms.
+    ///
+    /// The channel is switched to confirm mode if it is not in it already. At most
+    /// `max_outstanding_confirms` publishes from the channel configuration are left
+    /// unconfirmed at any time. Nacked messages are reported by index once the whole
+    /// batch has been confirmed.
+    pub async fn publish_batch(&self, messages: &[RabbitMQMessage]) -> Result<(), MessagingError> {
+        debug!("Publishing a batch of {} messages", messages.len());
+        self.ensure_confirm_mode().await?;
+        let channel = self.session.channel().await?;
+        publish_confirmed_batch(
+            &channel,
+            messages,
+            self.session.channel_config().max_outstanding_confirms,
+            self.return_handler.as_ref(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Publishes a message with AMQP-specific options.
+    ///
+    /// Mandatory messages that cannot be routed are passed to the handler registered
+    /// with `on_return`, or reported as `MessagingError::Unroutable`. Returns can only
+    /// be matched to their publish in confirm mode, so the channel is switched to it
+    /// when needed.
+    pub async fn publish_with_options(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+        options: &PublishOptions,
+    ) -> Result<(), MessagingError> {
+        debug!(
+            "Publishing message to exchange '{}' with routing key '{}' (mandatory: {})",
+            exchange, routing_key, options.mandatory
+        );
+        if options.mandatory {
+            self.ensure_confirm_mode().await?;
+        }
+
+        let publish_options = BasicPublishOptions {
+            mandatory: options.mandatory,
+            ..Default::default()
+        };
+        let confirm = self
+            .start_publish(
+                exchange,
+                routing_key,
+                payload,
+                publish_options,
+                options.properties.to_basic_properties(),
+            )
+            .await?;
+        self.await_confirm(exchange, routing_key, confirm).await?;
+
+        debug!("Message published successfully.");
+        Ok(())
+    }
+
+    async fn ensure_confirm_mode(&self) -> Result<(), MessagingError> {
+        let channel = self.session.channel().await?;
+        if !channel.status().confirm() {
+            debug!("Enabling publisher confirms.");
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn start_publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+        options: BasicPublishOptions,
+        properties: BasicProperties,
+    ) -> Result<PublisherConfirm, MessagingError> {
+        let channel = self.session.channel().await?;
+        let confirm = channel
+            .basic_publish(exchange, routing_key, options, payload, properties)
+            .await?;
+        Ok(confirm)
+    }
+
+    async fn await_confirm(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        confirm: PublisherConfirm,
+    ) -> Result<(), MessagingError> {
+        await_confirm(exchange, routing_key, confirm, self.return_handler.as_ref()).await?;
+        Ok(())
+    }
+
+    /// Declares an exchange. It is redeclared automatically after recovery.
+    pub async fn declare_exchange(&self, exchange: &ExchangeInfo) -> Result<(), MessagingError> {
+        debug!("Declaring exchange '{}'", exchange.name);
+        self.session
+            .declare(TopologyItem::Exchange(exchange.clone()))
+            .await
+    }
+
+    /// Declares a queue. It is redeclared automatically after recovery.
+    pub async fn declare_queue(&self, queue: &QueueInfo) -> Result<(), MessagingError> {
+        debug!("Declaring queue '{}'", queue.name);
+        self.session
+            .declare(TopologyItem::Queue(queue.clone()))
+            .await
+    }
+
+    /// Binds a queue to an exchange. The binding is redeclared automatically after recovery.
+    pub async fn bind_queue(
+        &self,
+        queue: &str,
+        exchange: &str,
+        routing_key: &str,
+    ) -> Result<(), MessagingError> {
+        debug!(
+            "Binding queue '{}' to exchange '{}' with routing key '{}'",
+            queue, exchange, routing_key
+        );
+        self.session
+            .declare(TopologyItem::Binding {
+                queue: queue.to_string(),
+                exchange: exchange.to_string(),
+                routing_key: routing_key.to_string(),
+            })
+            .await
+    }
+}
+
+/// Settles AMQP deliveries through the lapin `Acker`.
+///
+/// With `no_ack` subscriptions the broker has already considered the delivery
+/// settled, so every operation is a no-op.
+struct AmqpAcker {
+    acker: Acker,
+    no_ack: bool,
+}
+
+#[async_trait]
+impl DeliveryAcker for AmqpAcker {
+    async fn ack(&self) -> Result<(), MessagingError> {
+        if !self.no_ack {
+            self.acker.ack(BasicAckOptions::default()).await?;
+        }
+        Ok(())
+    }
+
+    async fn nack(&self, requeue: bool) -> Result<(), MessagingError> {
+        if !self.no_ack {
+            self.acker
+                .nack(BasicNackOptions {
+                    requeue,
+                    ..Default::default()
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn reject(&self, requeue: bool) -> Result<(), MessagingError> {
+        if !self.no_ack {
+            self.acker.reject(BasicRejectOptions { requeue }).await?;
+        }
+        Ok(())
+    }
+}
+
+fn into_delivery(delivery: message::Delivery, no_ack: bool) -> Delivery {
+    Delivery::new(
+        delivery.delivery_tag,
+        delivery.exchange.to_string(),
+        delivery.routing_key.to_string(),
+        delivery.redelivered,
+        MessageProperties::from(&delivery.properties),
+        delivery.data,
+        Box::new(AmqpAcker {
+            acker: delivery.acker,
+            no_ack,
+        }),
+    )
+}
+
+/// Starts a consumer, returning it with the channel it consumes on.
+async fn start_consumer(
+    session: &SessionManager,
+    queue: &str,
+    options: &SubscribeOptions,
+) -> Result<(Channel, Consumer), MessagingError> {
+    let channel = session.channel().await?;
+
+    if let Some(prefetch_count) = options.prefetch_count {
+        channel
+            .basic_qos(prefetch_count, BasicQosOptions::default())
+            .await?;
+    }
+
+    let consumer = channel
+        .basic_consume(
+            queue,
+            &options.consumer_tag,
+            BasicConsumeOptions {
+                no_ack: options.no_ack,
+                exclusive: options.exclusive,
+                ..Default::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+
+    info!("Subscribed to queue '{}' as '{}'.", queue, consumer.tag());
+    Ok((channel, consumer))
+}
+
+/// State carried by a subscription stream between deliveries.
+struct Subscription {
+    session: SessionManager,
+    queue: String,
+    options: SubscribeOptions,
+    /// The consumer and the channel it was started on.
+    consumer: Option<(Channel, Consumer)>,
+}
+
+impl Subscription {
+    /// Returns the next delivery, resubscribing after a connection loss when
+    /// recovery is enabled. `None` ends the stream.
+    async fn next(mut self) -> Option<(Result<Delivery, MessagingError>, Option<Self>)> {
+        loop {
+            let (channel, consumer) = match self.consumer.as_mut() {
+                Some((channel, consumer)) => (channel.clone(), consumer),
+                None => match start_consumer(&self.session, &self.queue, &self.options).await {
+                    Ok(started) => {
+                        let (channel, consumer) = self.consumer.insert(started);
+                        (channel.clone(), consumer)
+                    }
+                    // Recovery gave up, so there is nothing left to resume.
+                    Err(e) => return Some((Err(e), None)),
+                },
+            };
+
+            match consumer.next().await {
+                Some(Ok(delivery)) => {
+                    let no_ack = self.options.no_ack;
+                    return Some((Ok(into_delivery(delivery, no_ack)), Some(self)));
+                }
+                Some(Err(e)) if self.session.recovery_enabled() => {
+                    warn!(
+                        "Consumer on queue '{}' failed: {}. Resubscribing.",
+                        self.queue, e
+                    );
+                    self.consumer = None;
+                }
+                Some(Err(e)) => return Some((Err(e.into()), Some(self))),
+                // The consumer's channel was lost, whether or not another task has
+                // already recovered the session since.
+                None if self.session.recovery_enabled()
+                    && !self.session.is_current_channel(&channel).await =>
+                {
+                    self.consumer = None;
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+async fn subscribe_stream(
+    session: &SessionManager,
+    queue: &str,
+    options: SubscribeOptions,
+) -> Result<DeliveryStream, MessagingError> {
+    let consumer = start_consumer(session, queue, &options).await?;
+
+    let subscription = Subscription {
+        session: session.clone(),
+        queue: queue.to_string(),
+        options,
+        consumer: Some(consumer),
+    };
+    let stream = stream::unfold(Some(subscription), |subscription| async move {
+        subscription?.next().await
+    });
+
+    Ok(Box::pin(stream))
+}
+
+#[async_trait]
+impl MessagingClient for AmqpClient {
+    async fn connect(&mut self) -> Result<(), MessagingError> {
+        self.session.connect().await
+    }
+
+    async fn publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+    ) -> Result<(), MessagingError> {
+        self.publish_with_options(exchange, routing_key, payload, &PublishOptions::default())
+            .await
+    }
+
+    async fn subscribe(
+        &self,
+        queue: &str,
+        options: SubscribeOptions,
+    ) -> Result<DeliveryStream, MessagingError> {
+        debug!(
+            "Subscribing to queue '{}' with consumer tag '{}'",
+            queue, options.consumer_tag
+        );
+        subscribe_stream(&self.session, queue, options).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), MessagingError> {
+        self.session.disconnect().await
+    }
+}
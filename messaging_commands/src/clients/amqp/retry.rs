@@ -0,0 +1,460 @@
+//! Consumer-side retries through delay queues, with dead-lettering once they run out.
+
+use std::time::Duration;
+
+use lapin::{
+    options::BasicPublishOptions,
+    types::{AMQPValue, FieldTable, LongString, ShortString},
+    Channel,
+};
+use log::{info, warn};
+use rabbitmq_config::{
+    amqp_integer, confirmation_result, ExchangeInfo, MessageProperties, QueueInfo, RabbitMQError,
+    RetryConfig,
+};
+
+use super::TopologyItem;
+use crate::error::MessagingError;
+use crate::subscription::Delivery;
+
+pub use rabbitmq_config::{
+    LAST_ERROR_HEADER, ORIGINAL_EXCHANGE_HEADER, ORIGINAL_ROUTING_KEY_HEADER, RETRY_COUNT_HEADER,
+};
+
+/// Where a failed delivery goes next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryStep {
+    /// Republish to the delay queue `queue`, from which the message returns to the
+    /// work queue after `delay`, or later if a message ahead of it in `queue` has a
+    /// longer delay. `attempt` counts from 1.
+    Retry {
+        attempt: u32,
+        queue: String,
+        delay: Duration,
+    },
+    /// Retries are exhausted: publish to the dead letter exchange.
+    DeadLetter,
+}
+
+/// Retry and dead-letter topology of a work queue, driven by a `RetryConfig`.
+///
+/// For a queue `orders` it consists of:
+///
+/// - delay queues `orders.retry.<delay>ms`, one per distinct backoff interval. A
+///   retried message is published there with its backoff as per-message TTL and is
+///   dead-lettered back to `orders` through the default exchange when it expires.
+///   Attempts whose backoff is capped by `max_interval_ms` share a queue, and a
+///   growing backoff must have that cap.
+///
+///   The delay queue is picked by the backoff without jitter, while the TTL carries
+///   the jitter of `randomization_factor`. RabbitMQ only expires messages at the head
+///   of a queue, so a message with a shorter TTL than the one ahead of it waits for
+///   that one to expire first. Jitter therefore only spreads retries within this
+///   head-of-line limit: a retry never waits longer than the longest jittered delay
+///   of its queue, but may return later than its own delay.
+/// - the fanout exchange `orders.dlx` and the queue `orders.dlq` bound to it, which
+///   receive messages once `max_retries` retries have failed.
+///
+/// The attempt count travels in the `x-retry-count` header, and the first exchange
+/// and routing key of the message in `x-original-exchange` and `x-original-routing-key`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    queue: String,
+    retry: RetryConfig,
+}
+
+impl RetryPolicy {
+    pub fn new(queue: impl Into<String>, retry: RetryConfig) -> Self {
+        Self {
+            queue: queue.into(),
+            retry,
+        }
+    }
+
+    /// The work queue the policy retries messages for.
+    pub fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    pub fn dead_letter_exchange(&self) -> String {
+        format!("{}.dlx", self.queue)
+    }
+
+    pub fn dead_letter_queue(&self) -> String {
+        format!("{}.dlq", self.queue)
+    }
+
+    /// The delay queue holding messages for `delay`.
+    pub fn retry_queue(&self, delay: Duration) -> String {
+        format!("{}.retry.{}ms", self.queue, delay.as_millis())
+    }
+
+    /// The delay queues of the policy, shortest delay first.
+    pub fn retry_queues(&self) -> Vec<String> {
+        let mut delays: Vec<Duration> = (0..self.retry.max_retries)
+            .map(|attempt| self.retry.interval(attempt))
+            .collect();
+        delays.dedup();
+        delays
+            .into_iter()
+            .map(|delay| self.retry_queue(delay))
+            .collect()
+    }
+
+    /// Checks that the backoff is capped when it grows. Without `max_interval_ms`
+    /// every attempt would get its own durable delay queue, with ever larger TTLs.
+    pub fn validate(&self) -> Result<(), MessagingError> {
+        if self.retry.max_retries > 1
+            && self.retry.multiplier > 1.0
+            && self.retry.max_interval_ms == 0
+        {
+            return Err(RabbitMQError::ConfigError(format!(
+                "Retries for queue '{}' grow by a multiplier of {} and need max_interval_ms to cap them",
+                self.queue, self.retry.multiplier
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Decides where a delivery that has already been retried `retries` times goes next.
+    pub fn next_step(&self, retries: u32) -> RetryStep {
+        if retries >= self.retry.max_retries {
+            return RetryStep::DeadLetter;
+        }
+        RetryStep::Retry {
+            attempt: retries + 1,
+            queue: self.retry_queue(self.retry.interval(retries)),
+            delay: self.retry.backoff(retries),
+        }
+    }
+
+    /// The exchanges, queues and bindings the policy relies on. Fails if the policy
+    /// does not `validate`.
+    pub(crate) fn topology(&self) -> Result<Vec<TopologyItem>, MessagingError> {
+        self.validate()?;
+        let mut items = vec![
+            TopologyItem::Exchange(ExchangeInfo {
+                name: self.dead_letter_exchange(),
+                kind: "fanout".to_string(),
+                durable: true,
+                auto_delete: false,
+                internal: false,
+                arguments: FieldTable::default(),
+            }),
+            TopologyItem::Queue(durable_queue(
+                self.dead_letter_queue(),
+                FieldTable::default(),
+            )),
+            TopologyItem::Binding {
+                queue: self.dead_letter_queue(),
+                exchange: self.dead_letter_exchange(),
+                routing_key: String::new(),
+            },
+        ];
+        for queue in self.retry_queues() {
+            let mut arguments = FieldTable::default();
+            arguments.insert(
+                "x-dead-letter-exchange".into(),
+                AMQPValue::LongString("".into()),
+            );
+            arguments.insert(
+                "x-dead-letter-routing-key".into(),
+                AMQPValue::LongString(self.queue.as_str().into()),
+            );
+            items.push(TopologyItem::Queue(durable_queue(queue, arguments)));
+        }
+        Ok(items)
+    }
+
+    /// Properties for republishing `delivery` to a delay queue.
+    pub(crate) fn retry_properties(
+        &self,
+        delivery: &Delivery,
+        attempt: u32,
+        delay: Duration,
+        reason: &str,
+    ) -> MessageProperties {
+        let mut properties = forwarded_properties(delivery, attempt, reason);
+        properties.expiration = Some(delay.as_millis().to_string());
+        properties
+    }
+
+    /// Properties for publishing `delivery` to the dead letter exchange.
+    pub(crate) fn dead_letter_properties(
+        &self,
+        delivery: &Delivery,
+        reason: &str,
+    ) -> MessageProperties {
+        let mut properties = forwarded_properties(delivery, retry_count(delivery), reason);
+        properties.expiration = None;
+        properties
+    }
+}
+
+/// How many times a delivery has been retried, according to its `x-retry-count` header.
+pub fn retry_count(delivery: &Delivery) -> u32 {
+    let count = delivery
+        .properties
+        .headers
+        .as_ref()
+        .and_then(|headers| headers.inner().get(RETRY_COUNT_HEADER))
+        .and_then(amqp_integer)
+        .unwrap_or(0);
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// Copies the properties of `delivery`, recording the retry count, the failure and,
+/// on the first failure, where the message was originally published.
+fn forwarded_properties(delivery: &Delivery, retries: u32, reason: &str) -> MessageProperties {
+    let mut properties = delivery.properties.clone();
+    let mut headers = properties.headers.take().unwrap_or_default();
+
+    let original_exchange = ShortString::from(ORIGINAL_EXCHANGE_HEADER);
+    if !headers.inner().contains_key(&original_exchange) {
+        headers.insert(
+            original_exchange,
+            AMQPValue::LongString(delivery.exchange.as_str().into()),
+        );
+        headers.insert(
+            ORIGINAL_ROUTING_KEY_HEADER.into(),
+            AMQPValue::LongString(delivery.routing_key.as_str().into()),
+        );
+    }
+    headers.insert(
+        RETRY_COUNT_HEADER.into(),
+        AMQPValue::LongLongInt(i64::from(retries)),
+    );
+    headers.insert(
+        LAST_ERROR_HEADER.into(),
+        AMQPValue::LongString(LongString::from(reason)),
+    );
+
+    properties.headers = Some(headers);
+    properties
+}
+
+fn durable_queue(name: String, arguments: FieldTable) -> QueueInfo {
+    QueueInfo {
+        name,
+        durable: true,
+        exclusive: false,
+        auto_delete: false,
+        arguments,
+    }
+}
+
+/// Moves failed deliveries along a `RetryPolicy`, publishing on a channel in confirm mode.
+#[derive(Clone)]
+pub(crate) struct Retrier {
+    pub(crate) policy: RetryPolicy,
+    pub(crate) channel: Channel,
+}
+
+impl Retrier {
+    /// Republishes `delivery` to its next delay queue, or to the dead letter exchange
+    /// when `retryable` is false or its retries are exhausted. Returns whether it was retried.
+    ///
+    /// The caller acks the delivery once this succeeds.
+    pub(crate) async fn forward(
+        &self,
+        delivery: &Delivery,
+        retryable: bool,
+        reason: &str,
+    ) -> Result<bool, MessagingError> {
+        let step = if retryable {
+            self.policy.next_step(retry_count(delivery))
+        } else {
+            RetryStep::DeadLetter
+        };
+        match step {
+            RetryStep::Retry {
+                attempt,
+                queue,
+                delay,
+            } => {
+                info!(
+                    "Retrying delivery {} from queue '{}' in {:?} (attempt {}): {}",
+                    delivery.delivery_tag, self.policy.queue, delay, attempt, reason
+                );
+                let properties = self
+                    .policy
+                    .retry_properties(delivery, attempt, delay, reason);
+                self.publish("", &queue, delivery, properties).await?;
+                Ok(true)
+            }
+            RetryStep::DeadLetter => {
+                warn!(
+                    "Dead-lettering delivery {} from queue '{}' to '{}': {}",
+                    delivery.delivery_tag,
+                    self.policy.queue,
+                    self.policy.dead_letter_exchange(),
+                    reason
+                );
+                let properties = self.policy.dead_letter_properties(delivery, reason);
+                let exchange = self.policy.dead_letter_exchange();
+                self.publish(&exchange, &delivery.routing_key, delivery, properties)
+                    .await?;
+                Ok(false)
+            }
+        }
+    }
+
+    async fn publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        delivery: &Delivery,
+        properties: MessageProperties,
+    ) -> Result<(), MessagingError> {
+        let options = BasicPublishOptions {
+            mandatory: true,
+            ..Default::default()
+        };
+        let confirm = self
+            .channel
+            .basic_publish(
+                exchange,
+                routing_key,
+                options,
+                &delivery.payload,
+                properties.to_basic_properties(),
+            )
+            .await?;
+        Ok(confirmation_result(exchange, routing_key, confirm.await?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::RecordingAcker;
+
+    fn delivery(exchange: &str, routing_key: &str, properties: MessageProperties) -> Delivery {
+        Delivery::new(
+            1,
+            exchange.to_string(),
+            routing_key.to_string(),
+            false,
+            properties,
+            b"{}".to_vec(),
+            Box::new(RecordingAcker::default()),
+        )
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(
+            "orders",
+            RetryConfig {
+                max_retries: 4,
+                initial_interval_ms: 1000,
+                multiplier: 2.0,
+                max_interval_ms: 3000,
+                randomization_factor: 0.0,
+            },
+        )
+    }
+
+    #[test]
+    fn backoff_tiers_share_capped_delays() {
+        let policy = policy();
+        assert_eq!(
+            policy.retry_queues(),
+            vec![
+                "orders.retry.1000ms",
+                "orders.retry.2000ms",
+                "orders.retry.3000ms"
+            ]
+        );
+        assert_eq!(
+            policy.next_step(0),
+            RetryStep::Retry {
+                attempt: 1,
+                queue: "orders.retry.1000ms".to_string(),
+                delay: Duration::from_secs(1)
+            }
+        );
+        assert_eq!(
+            policy.next_step(3),
+            RetryStep::Retry {
+                attempt: 4,
+                queue: "orders.retry.3000ms".to_string(),
+                delay: Duration::from_secs(3)
+            }
+        );
+        assert_eq!(policy.next_step(4), RetryStep::DeadLetter);
+
+        // Dead-letter exchange, queue and binding, then one delay queue per tier.
+        let topology = policy.topology().unwrap();
+        assert_eq!(topology.len(), 6);
+        match &topology[5] {
+            TopologyItem::Queue(queue) => {
+                assert_eq!(queue.name, "orders.retry.3000ms");
+                assert_eq!(
+                    queue.arguments.inner().get("x-dead-letter-routing-key"),
+                    Some(&AMQPValue::LongString("orders".into()))
+                );
+            }
+            _ => panic!("expected a delay queue"),
+        }
+    }
+
+    #[test]
+    fn growing_backoff_needs_a_cap() {
+        let uncapped = RetryPolicy::new(
+            "orders",
+            RetryConfig {
+                max_interval_ms: 0,
+                ..policy().retry
+            },
+        );
+        assert!(matches!(
+            uncapped.topology(),
+            Err(MessagingError::Config(_))
+        ));
+
+        // A constant backoff shares a single delay queue and needs no cap.
+        let constant = RetryPolicy::new(
+            "orders",
+            RetryConfig {
+                multiplier: 1.0,
+                max_interval_ms: 0,
+                ..policy().retry
+            },
+        );
+        assert_eq!(constant.retry_queues(), vec!["orders.retry.1000ms"]);
+        assert!(constant.validate().is_ok());
+    }
+
+    #[test]
+    fn headers_track_attempts_and_origin() {
+        let policy = policy();
+        let first = delivery("shop", "order.placed", MessageProperties::default());
+        assert_eq!(retry_count(&first), 0);
+
+        let properties =
+            policy.retry_properties(&first, 1, Duration::from_millis(1500), "database busy");
+        assert_eq!(properties.expiration.as_deref(), Some("1500"));
+
+        // The retried message comes back through the default exchange.
+        let second = delivery("", "orders", properties);
+        assert_eq!(retry_count(&second), 1);
+        let properties = policy.dead_letter_properties(&second, "still busy");
+        let headers = properties.headers.unwrap();
+        let header = |name: &str| headers.inner().get(name).cloned();
+        assert_eq!(properties.expiration, None);
+        assert_eq!(header(RETRY_COUNT_HEADER), Some(AMQPValue::LongLongInt(1)));
+        assert_eq!(
+            header(ORIGINAL_EXCHANGE_HEADER),
+            Some(AMQPValue::LongString("shop".into()))
+        );
+        assert_eq!(
+            header(ORIGINAL_ROUTING_KEY_HEADER),
+            Some(AMQPValue::LongString("order.placed".into()))
+        );
+        assert_eq!(
+            header(LAST_ERROR_HEADER),
+            Some(AMQPValue::LongString("still busy".into()))
+        );
+    }
+}
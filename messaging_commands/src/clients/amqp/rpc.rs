@@ -0,0 +1,320 @@
+//! Request/reply over RabbitMQ direct reply-to.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use lapin::{
+    options::{BasicConsumeOptions, BasicPublishOptions},
+    types::FieldTable,
+    Channel, Consumer,
+};
+use log::{debug, info, warn};
+use rabbitmq_config::MessageProperties;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use super::{into_delivery, subscribe_stream, AmqpClient, SessionManager};
+use crate::error::MessagingError;
+use crate::subscription::{Delivery, SubscribeOptions};
+
+/// The pseudo-queue RabbitMQ uses for direct reply-to.
+const DIRECT_REPLY_TO: &str = "amq.rabbitmq.reply-to";
+
+/// How long `RpcClient::call` waits for a reply unless configured otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type PendingReplies = Arc<Mutex<HashMap<String, oneshot::Sender<Delivery>>>>;
+
+/// The channel consuming direct replies and the task routing them to callers.
+struct ReplyConsumer {
+    channel: Channel,
+    dispatcher: JoinHandle<()>,
+}
+
+/// Sends requests and awaits their replies through direct reply-to.
+///
+/// Requests are published on a dedicated channel with `reply_to` set to
+/// `amq.rabbitmq.reply-to` and a generated `correlation_id`, so no reply queue
+/// has to be declared. Concurrent calls share the channel.
+pub struct RpcClient {
+    session: SessionManager,
+    timeout: Duration,
+    pending: PendingReplies,
+    replies: tokio::sync::Mutex<Option<ReplyConsumer>>,
+}
+
+impl RpcClient {
+    /// Creates an RPC client sharing the connection of `client`.
+    ///
+    /// The reply channel is opened lazily on the first call.
+    pub fn new(client: &AmqpClient) -> Self {
+        Self {
+            session: client.session.clone(),
+            timeout: DEFAULT_TIMEOUT,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            replies: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Sets how long `call` waits for a reply before failing with `MessagingError::RpcTimeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Publishes a request and waits for its reply.
+    pub async fn call(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+    ) -> Result<Delivery, MessagingError> {
+        self.call_with_properties(exchange, routing_key, payload, MessageProperties::default())
+            .await
+    }
+
+    /// Publishes a request with the given properties and waits for its reply.
+    ///
+    /// `reply_to` and `correlation_id` are always set by the client.
+    pub async fn call_with_properties(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+        properties: MessageProperties,
+    ) -> Result<Delivery, MessagingError> {
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        let properties = MessageProperties {
+            reply_to: Some(DIRECT_REPLY_TO.to_string()),
+            correlation_id: Some(correlation_id.clone()),
+            ..properties
+        };
+
+        // Direct replies are only delivered to the channel the request was published on.
+        let channel = self.reply_channel().await?;
+        let (sender, receiver) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(correlation_id.clone(), sender);
+
+        debug!(
+            "Sending RPC request to exchange '{}' with routing key '{}' (correlation id: {})",
+            exchange, routing_key, correlation_id
+        );
+        let published = channel
+            .basic_publish(
+                exchange,
+                routing_key,
+                BasicPublishOptions::default(),
+                payload,
+                properties.to_basic_properties(),
+            )
+            .await;
+        if let Err(e) = published {
+            self.pending.lock().unwrap().remove(&correlation_id);
+            return Err(e.into());
+        }
+
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(reply)) => Ok(reply),
+            // The dispatcher stopped because the reply channel was closed.
+            Ok(Err(_)) => Err(MessagingError::NotConnected),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&correlation_id);
+                Err(MessagingError::RpcTimeout {
+                    routing_key: routing_key.to_string(),
+                    timeout: self.timeout,
+                })
+            }
+        }
+    }
+
+    /// Closes the reply channel. Calls still waiting for a reply fail.
+    pub async fn close(&self) -> Result<(), MessagingError> {
+        if let Some(replies) = self.replies.lock().await.take() {
+            replies.dispatcher.abort();
+            self.pending.lock().unwrap().clear();
+            if replies.channel.status().connected() {
+                replies.channel.close(200, "RPC client closed").await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the reply channel, (re)opening it and its consumer when needed.
+    async fn reply_channel(&self) -> Result<Channel, MessagingError> {
+        let mut replies = self.replies.lock().await;
+        if let Some(current) = replies.as_ref() {
+            if current.channel.status().connected() && !current.dispatcher.is_finished() {
+                return Ok(current.channel.clone());
+            }
+            current.dispatcher.abort();
+        }
+
+        let channel = self.session.create_channel().await?;
+        let consumer = channel
+            .basic_consume(
+                DIRECT_REPLY_TO,
+                "",
+                BasicConsumeOptions {
+                    no_ack: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        info!("Consuming RPC replies as '{}'.", consumer.tag());
+
+        let dispatcher = tokio::spawn(dispatch_replies(consumer, self.pending.clone()));
+        *replies = Some(ReplyConsumer {
+            channel: channel.clone(),
+            dispatcher,
+        });
+        Ok(channel)
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        if let Some(replies) = self.replies.get_mut().take() {
+            replies.dispatcher.abort();
+        }
+    }
+}
+
+/// Routes each reply to the call waiting on its correlation id.
+async fn dispatch_replies(mut consumer: Consumer, pending: PendingReplies) {
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(delivery) => into_delivery(delivery, true),
+            Err(e) => {
+                warn!("RPC reply consumer failed: {}", e);
+                break;
+            }
+        };
+
+        let waiting = delivery
+            .properties
+            .correlation_id
+            .as_ref()
+            .and_then(|id| pending.lock().unwrap().remove(id));
+        match waiting {
+            Some(sender) => {
+                let _ = sender.send(delivery);
+            }
+            None => debug!(
+                "Dropping RPC reply with unknown correlation id {:?}",
+                delivery.properties.correlation_id
+            ),
+        }
+    }
+
+    // Dropping the senders wakes every caller still waiting on this channel.
+    pending.lock().unwrap().clear();
+}
+
+/// A request received by an `RpcServer`.
+#[derive(Debug, Clone)]
+pub struct RpcRequest {
+    pub routing_key: String,
+    pub properties: MessageProperties,
+    pub payload: Vec<u8>,
+}
+
+/// Consumes requests from a queue and replies through a handler.
+///
+/// Each reply is published to the request's `reply_to` with its `correlation_id`,
+/// then the request is acknowledged. Requests the handler fails on are rejected
+/// without requeueing.
+pub struct RpcServer {
+    session: SessionManager,
+    queue: String,
+    options: SubscribeOptions,
+}
+
+impl RpcServer {
+    /// Creates a server for `queue` sharing the connection of `client`.
+    pub fn new(client: &AmqpClient, queue: &str) -> Self {
+        Self {
+            session: client.session.clone(),
+            queue: queue.to_string(),
+            options: SubscribeOptions::default(),
+        }
+    }
+
+    /// Limits how many requests the broker delivers before they are answered.
+    pub fn with_prefetch(mut self, prefetch_count: u16) -> Self {
+        self.options.prefetch_count = Some(prefetch_count);
+        self
+    }
+
+    /// Serves requests one at a time until the subscription ends.
+    pub async fn serve<F, Fut>(&self, handler: F) -> Result<(), MessagingError>
+    where
+        F: Fn(RpcRequest) -> Fut,
+        Fut: Future<Output = Result<Vec<u8>, MessagingError>>,
+    {
+        info!("Serving RPC requests from queue '{}'.", self.queue);
+        let mut requests =
+            subscribe_stream(&self.session, &self.queue, self.options.clone()).await?;
+
+        while let Some(delivery) = requests.next().await {
+            let mut delivery = delivery?;
+            let request = RpcRequest {
+                routing_key: delivery.routing_key.clone(),
+                properties: delivery.properties.clone(),
+                payload: std::mem::take(&mut delivery.payload),
+            };
+
+            match handler(request).await {
+                Ok(reply) => {
+                    self.reply(&delivery.properties, &reply).await?;
+                    delivery.ack().await?;
+                }
+                Err(e) => {
+                    warn!(
+                        "RPC handler failed for request on queue '{}': {}. Rejecting it.",
+                        self.queue, e
+                    );
+                    delivery.reject(false).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        request: &MessageProperties,
+        payload: &[u8],
+    ) -> Result<(), MessagingError> {
+        let Some(reply_to) = request.reply_to.as_deref() else {
+            warn!(
+                "RPC request on queue '{}' has no reply_to, dropping the reply.",
+                self.queue
+            );
+            return Ok(());
+        };
+
+        let properties = MessageProperties {
+            correlation_id: request.correlation_id.clone(),
+            ..Default::default()
+        };
+        let channel = self.session.channel().await?;
+        channel
+            .basic_publish(
+                "",
+                reply_to,
+                BasicPublishOptions::default(),
+                payload,
+                properties.to_basic_properties(),
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+//! Connection/channel lifecycle for `AmqpClient`, including opt-in recovery.
+
+use std::sync::{Arc, Mutex};
+
+use lapin::{
+    options::{
+        BasicQosOptions, ConfirmSelectOptions, ExchangeDeclareOptions, QueueBindOptions,
+        QueueDeclareOptions,
+    },
+    types::FieldTable,
+    Channel, Connection,
+};
+use log::{info, warn};
+use rabbitmq_config::{
+    exchange_kind, open_failover_connection, ChannelConfig, ConnectionConfig, Endpoint,
+    EndpointPool, ExchangeInfo, QueueInfo, RabbitMQConfig, RabbitMQError, RetryConfig, TlsOptions,
+};
+use tokio::sync::RwLock;
+
+use crate::error::MessagingError;
+
+/// A live connection and the channel used for all client operations.
+struct Session {
+    connection: Connection,
+    channel: Channel,
+}
+
+/// A topology declaration made through the client, replayed after a reconnect.
+#[derive(Debug, Clone)]
+pub(crate) enum TopologyItem {
+    Exchange(ExchangeInfo),
+    Queue(QueueInfo),
+    Binding {
+        queue: String,
+        exchange: String,
+        routing_key: String,
+    },
+}
+
+impl TopologyItem {
+    fn same_object(&self, other: &TopologyItem) -> bool {
+        match (self, other) {
+            (TopologyItem::Exchange(a), TopologyItem::Exchange(b)) => a.name == b.name,
+            (TopologyItem::Queue(a), TopologyItem::Queue(b)) => a.name == b.name,
+            (
+                TopologyItem::Binding {
+                    queue,
+                    exchange,
+                    routing_key,
+                },
+                TopologyItem::Binding {
+                    queue: other_queue,
+                    exchange: other_exchange,
+                    routing_key: other_routing_key,
+                },
+            ) => {
+                queue == other_queue
+                    && exchange == other_exchange
+                    && routing_key == other_routing_key
+            }
+            _ => false,
+        }
+    }
+
+    async fn declare(&self, channel: &Channel) -> Result<(), MessagingError> {
+        match self {
+            TopologyItem::Exchange(exchange) => {
+                let options = ExchangeDeclareOptions {
+                    durable: exchange.durable,
+                    auto_delete: exchange.auto_delete,
+                    internal: exchange.internal,
+                    ..Default::default()
+                };
+                channel
+                    .exchange_declare(
+                        &exchange.name,
+                        exchange_kind(&exchange.kind)?,
+                        options,
+                        exchange.arguments.clone(),
+                    )
+                    .await?;
+            }
+            TopologyItem::Queue(queue) => {
+                let options = QueueDeclareOptions {
+                    durable: queue.durable,
+                    auto_delete: queue.auto_delete,
+                    exclusive: queue.exclusive,
+                    ..Default::default()
+                };
+                channel
+                    .queue_declare(&queue.name, options, queue.arguments.clone())
+                    .await?;
+            }
+            TopologyItem::Binding {
+                queue,
+                exchange,
+                routing_key,
+            } => {
+                channel
+                    .queue_bind(
+                        queue,
+                        exchange,
+                        routing_key,
+                        QueueBindOptions::default(),
+                        FieldTable::default(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shared handle to the client's session.
+///
+/// Cloned into subscription streams so that they can trigger recovery and
+/// resubscribe on their own when the connection drops.
+#[derive(Clone)]
+pub(crate) struct SessionManager {
+    config: RabbitMQConfig,
+    settings: ConnectionConfig,
+    endpoints: EndpointPool,
+    channel_config: ChannelConfig,
+    recovery: Option<RetryConfig>,
+    session: Arc<RwLock<Option<Session>>>,
+    topology: Arc<Mutex<Vec<TopologyItem>>>,
+}
+
+impl SessionManager {
+    pub(crate) fn new(config: RabbitMQConfig) -> Self {
+        Self {
+            endpoints: config.amqp_pool(),
+            config,
+            settings: ConnectionConfig::default(),
+            channel_config: ChannelConfig::default(),
+            recovery: None,
+            session: Arc::new(RwLock::new(None)),
+            topology: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub(crate) fn set_recovery(&mut self, retry: RetryConfig) {
+        self.recovery = Some(retry);
+    }
+
+    pub(crate) fn set_tls(&mut self, tls_options: Option<TlsOptions>) {
+        self.settings.use_tls = true;
+        self.settings.tls_options = tls_options;
+    }
+
+    pub(crate) fn set_connection_settings(&mut self, settings: ConnectionConfig) {
+        self.settings = settings;
+    }
+
+    pub(crate) fn set_channel_config(&mut self, channel_config: ChannelConfig) {
+        self.channel_config = channel_config;
+    }
+
+    pub(crate) fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    /// The cluster node of the current connection.
+    pub(crate) fn connected_endpoint(&self) -> Option<Endpoint> {
+        self.endpoints.current()
+    }
+
+    pub(crate) fn failed_endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints.failed()
+    }
+
+    pub(crate) fn recovery_enabled(&self) -> bool {
+        self.recovery.is_some()
+    }
+
+    /// Opens a new session, retrying according to the recovery settings if enabled.
+    pub(crate) async fn connect(&self) -> Result<(), MessagingError> {
+        let session = self.establish().await?;
+        *self.session.write().await = Some(session);
+        Ok(())
+    }
+
+    /// Closes the current session. A disconnected client is never recovered.
+    pub(crate) async fn disconnect(&self) -> Result<(), MessagingError> {
+        if let Some(session) = self.session.write().await.take() {
+            info!("Closing AMQP connection.");
+            // The 200 code is a standard success code.
+            session.connection.close(200, "Goodbye").await?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the current channel is open.
+    pub(crate) async fn is_connected(&self) -> bool {
+        self.session
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|session| session.channel.status().connected())
+    }
+
+    /// Returns the current channel, recovering the session first if it was lost
+    /// and recovery is enabled.
+    pub(crate) async fn channel(&self) -> Result<Channel, MessagingError> {
+        {
+            let session = self.session.read().await;
+            match session.as_ref() {
+                Some(session) if session.channel.status().connected() => {
+                    return Ok(session.channel.clone())
+                }
+                Some(_) if self.recovery_enabled() => {}
+                _ => return Err(MessagingError::NotConnected),
+            }
+        }
+        self.recover().await
+    }
+
+    /// Opens an additional channel on the current connection, recovering it first
+    /// if it was lost and recovery is enabled.
+    pub(crate) async fn create_channel(&self) -> Result<Channel, MessagingError> {
+        self.channel().await?;
+        let session = self.session.read().await;
+        let session = session.as_ref().ok_or(MessagingError::NotConnected)?;
+        Ok(session.connection.create_channel().await?)
+    }
+
+    /// Declares a topology object and remembers it for replay after recovery.
+    pub(crate) async fn declare(&self, item: TopologyItem) -> Result<(), MessagingError> {
+        let channel = self.channel().await?;
+        item.declare(&channel).await?;
+
+        let mut topology = self.topology.lock().unwrap();
+        match topology.iter_mut().find(|known| known.same_object(&item)) {
+            Some(known) => *known = item,
+            None => topology.push(item),
+        }
+        Ok(())
+    }
+
+    /// Returns whether `channel` is the open channel of the current session.
+    pub(crate) async fn is_current_channel(&self, channel: &Channel) -> bool {
+        channel.status().connected()
+            && self.session.read().await.as_ref().is_some_and(|session| {
+                session.channel.id() == channel.id() && session.channel.status().connected()
+            })
+    }
+
+    async fn recover(&self) -> Result<Channel, MessagingError> {
+        let mut session = self.session.write().await;
+        let current = match session.as_mut() {
+            // Another task recovered the session while we waited for the lock.
+            Some(current) if current.channel.status().connected() => {
+                return Ok(current.channel.clone())
+            }
+            Some(current) => current,
+            None => return Err(MessagingError::NotConnected),
+        };
+
+        // A channel error closes only the channel; the connection and node are fine.
+        if current.connection.status().connected() {
+            warn!("AMQP channel closed, reopening it on the same connection.");
+            match self.open_channel(&current.connection).await {
+                Ok(channel) => {
+                    current.channel = channel.clone();
+                    info!("AMQP channel reopened.");
+                    return Ok(channel);
+                }
+                Err(e) => warn!("Failed to reopen the AMQP channel: {}. Reconnecting.", e),
+            }
+            // The connection is replaced below, so don't leave it open.
+            let _ = current.connection.close(200, "Reconnecting").await;
+        }
+
+        let lost = self.endpoints.current();
+        if let Some(endpoint) = &lost {
+            self.endpoints.mark_failed(endpoint);
+        }
+        warn!(
+            "AMQP connection to {} lost, attempting recovery.",
+            lost.map_or_else(|| self.config.host.clone(), |endpoint| endpoint.to_string())
+        );
+        let recovered = self.establish().await?;
+        let channel = recovered.channel.clone();
+        *session = Some(recovered);
+        info!("AMQP connection recovered.");
+        Ok(channel)
+    }
+
+    async fn establish(&self) -> Result<Session, MessagingError> {
+        let mut attempt = 0;
+        loop {
+            let error = match self.open().await {
+                Ok(session) => return Ok(session),
+                Err(e) => e,
+            };
+
+            match &self.recovery {
+                Some(retry) if attempt < retry.max_retries => {
+                    let delay = retry.backoff(attempt);
+                    warn!(
+                        "AMQP connection attempt {} failed: {}. Retrying in {:?}.",
+                        attempt + 1,
+                        error,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Some(_) => {
+                    return Err(MessagingError::RecoveryFailed {
+                        attempts: attempt + 1,
+                        reason: error.to_string(),
+                    })
+                }
+                None => return Err(error),
+            }
+        }
+    }
+
+    async fn open(&self) -> Result<Session, MessagingError> {
+        let (connection, endpoint) =
+            open_failover_connection(&self.config, &self.settings, &self.endpoints)
+                .await
+                .map_err(|e| match e {
+                    RabbitMQError::ConnectionError(reason)
+                    | RabbitMQError::TimeoutError(reason) => MessagingError::Connection(reason),
+                    other => MessagingError::Config(other),
+                })?;
+        info!("Connected to AMQP broker at {}", endpoint);
+        let channel = self.open_channel(&connection).await?;
+        info!("Successfully connected to AMQP broker and opened a channel.");
+        Ok(Session {
+            connection,
+            channel,
+        })
+    }
+
+    /// Opens the client channel on `connection`, applies the channel settings and
+    /// redeclares the known topology.
+    async fn open_channel(&self, connection: &Connection) -> Result<Channel, MessagingError> {
+        let channel = connection.create_channel().await?;
+
+        if self.channel_config.default_prefetch_count > 0 {
+            channel
+                .basic_qos(
+                    self.channel_config.default_prefetch_count,
+                    BasicQosOptions::default(),
+                )
+                .await?;
+        }
+        if self.channel_config.confirm_deliveries {
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await?;
+        }
+
+        let topology = self.topology.lock().unwrap().clone();
+        if !topology.is_empty() {
+            info!("Redeclaring {} known topology object(s).", topology.len());
+        }
+        for item in &topology {
+            item.declare(&channel).await?;
+        }
+        Ok(channel)
+    }
+}
@@ -0,0 +1,470 @@
+//! Long-running consumers driven by a `ConsumerConfig`.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{FutureExt, StreamExt};
+use lapin::{
+    options::{BasicCancelOptions, BasicConsumeOptions, BasicQosOptions, ConfirmSelectOptions},
+    Channel, Consumer,
+};
+use log::{debug, info, warn};
+use rabbitmq_config::{arguments_to_field_table, ConsumerConfig, RetryConfig};
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::retry::{Retrier, RetryPolicy};
+use super::{into_delivery, AmqpClient, SessionManager};
+use crate::error::MessagingError;
+use crate::subscription::Delivery;
+
+/// How long `ConsumerWorker` waits for in-flight handlers on shutdown unless configured otherwise.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a handler could not process a delivery, which decides how it is settled.
+#[derive(Debug, Error)]
+pub enum HandlerError {
+    /// A temporary failure: the delivery is retried through the delay queues of the
+    /// worker's `RetryPolicy`, or nacked and requeued without one.
+    #[error("{0}")]
+    Requeue(String),
+
+    /// The delivery can never be processed: it is published to the dead letter
+    /// exchange of the worker's `RetryPolicy`. Without one it is nacked without
+    /// requeueing, so it is dead-lettered when the queue has a dead letter exchange
+    /// and dropped otherwise.
+    #[error("{0}")]
+    Reject(String),
+}
+
+/// Processes deliveries for a `ConsumerWorker`.
+///
+/// A delivery is acked when `handle` returns `Ok` and nacked according to the
+/// `HandlerError` otherwise. A handler that panics is treated as `Reject`.
+#[async_trait]
+pub trait MessageHandler: Send + Sync + 'static {
+    async fn handle(&self, delivery: &Delivery) -> Result<(), HandlerError>;
+}
+
+/// How a delivery was settled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Settlement {
+    Acked,
+    Requeued,
+    Rejected,
+    Retried,
+    DeadLettered,
+}
+
+/// Counts of settled deliveries, returned when a `ConsumerWorker` stops.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub acked: u64,
+    pub requeued: u64,
+    pub rejected: u64,
+    /// Deliveries republished to a delay queue.
+    pub retried: u64,
+    /// Deliveries published to the dead letter exchange.
+    pub dead_lettered: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    acked: AtomicU64,
+    requeued: AtomicU64,
+    rejected: AtomicU64,
+    retried: AtomicU64,
+    dead_lettered: AtomicU64,
+}
+
+impl Counters {
+    fn record(&self, settlement: Settlement) {
+        let counter = match settlement {
+            Settlement::Acked => &self.acked,
+            Settlement::Requeued => &self.requeued,
+            Settlement::Rejected => &self.rejected,
+            Settlement::Retried => &self.retried,
+            Settlement::DeadLettered => &self.dead_lettered,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> WorkerStats {
+        WorkerStats {
+            acked: self.acked.load(Ordering::Relaxed),
+            requeued: self.requeued.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Consumes a queue as described by a `ConsumerConfig`, running a `MessageHandler`
+/// on up to `concurrency` deliveries at a time.
+///
+/// The worker consumes on its own channel, with `basic_qos` set to the configured
+/// `prefetch_count`. On shutdown it cancels the consumer, handles the deliveries it
+/// has already received, waits up to the drain timeout for handlers to finish and
+/// closes its channel, which returns anything still unacknowledged to the queue.
+///
+/// With a `RetryPolicy`, taken from `ConsumerConfig::retry` or set with `with_retry`,
+/// failed deliveries are republished to delay queues and finally to a dead letter
+/// exchange, whose topology the worker declares before consuming.
+pub struct ConsumerWorker {
+    session: SessionManager,
+    config: ConsumerConfig,
+    concurrency: usize,
+    drain_timeout: Duration,
+    retry: Option<RetryPolicy>,
+}
+
+impl ConsumerWorker {
+    /// Creates a worker sharing the connection of `client`.
+    ///
+    /// Concurrency defaults to the prefetch count, or 1 without one.
+    pub fn new(client: &AmqpClient, config: ConsumerConfig) -> Self {
+        Self {
+            session: client.session.clone(),
+            concurrency: usize::from(config.prefetch_count.max(1)),
+            retry: config
+                .retry
+                .clone()
+                .map(|retry| RetryPolicy::new(config.queue.as_str(), retry)),
+            config,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Retries failed deliveries through delay queues as described by `RetryPolicy`.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = Some(RetryPolicy::new(self.config.queue.as_str(), retry));
+        self
+    }
+
+    /// Sets how many deliveries are handled at the same time.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how long shutdown waits for in-flight handlers before giving up on them.
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// Runs until SIGINT or SIGTERM, then shuts down gracefully.
+    pub async fn run<H: MessageHandler>(&self, handler: H) -> Result<WorkerStats, MessagingError> {
+        self.run_until(handler, shutdown_signal()).await
+    }
+
+    /// Runs until `shutdown` completes or the consumer is cancelled by the broker,
+    /// then shuts down gracefully.
+    pub async fn run_until<H, S>(
+        &self,
+        handler: H,
+        shutdown: S,
+    ) -> Result<WorkerStats, MessagingError>
+    where
+        H: MessageHandler,
+        S: Future<Output = ()>,
+    {
+        let handler = Arc::new(handler);
+        let counters = Arc::new(Counters::default());
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let mut handlers = JoinSet::new();
+        tokio::pin!(shutdown);
+
+        if let Some(policy) = &self.retry {
+            for item in policy.topology()? {
+                self.session.declare(item).await?;
+            }
+        }
+        let (mut channel, mut consumer) = self.start().await?;
+        let mut retrier = self.retrier(&channel);
+        info!(
+            "Worker consuming queue '{}' as '{}' with {} concurrent handler(s).",
+            self.config.queue,
+            consumer.tag(),
+            self.concurrency
+        );
+
+        let mut stopping = false;
+        let result = loop {
+            // Reap finished handlers so the set does not grow without bound.
+            while handlers.try_join_next().is_some() {}
+
+            let permit = tokio::select! {
+                _ = &mut shutdown, if !stopping => {
+                    stopping = true;
+                    self.cancel(&channel, &consumer).await;
+                    continue;
+                }
+                permit = permits.clone().acquire_owned() => permit.expect("the semaphore is never closed"),
+            };
+            let next = tokio::select! {
+                _ = &mut shutdown, if !stopping => {
+                    stopping = true;
+                    self.cancel(&channel, &consumer).await;
+                    continue;
+                }
+                next = consumer.next() => next,
+            };
+
+            match next {
+                Some(Ok(delivery)) => {
+                    let delivery = into_delivery(delivery, self.config.no_ack);
+                    let handler = handler.clone();
+                    let counters = counters.clone();
+                    let retrier = retrier.clone();
+                    handlers.spawn(async move {
+                        counters
+                            .record(settle(handler.as_ref(), &delivery, retrier.as_ref()).await);
+                        drop(permit);
+                    });
+                }
+                Some(Err(e)) if !stopping && self.session.recovery_enabled() => {
+                    warn!(
+                        "Worker on queue '{}' lost its consumer: {}. Resubscribing.",
+                        self.config.queue, e
+                    );
+                    match self.start().await {
+                        Ok((new_channel, new_consumer)) => {
+                            retrier = self.retrier(&new_channel);
+                            (channel, consumer) = (new_channel, new_consumer);
+                        }
+                        Err(e) => break Err(e),
+                    }
+                }
+                Some(Err(e)) => break Err(e.into()),
+                // The consumer was cancelled, by us on shutdown or by the broker.
+                None => break Ok(()),
+            }
+        };
+
+        info!(
+            "Worker on queue '{}' stopped consuming, waiting for {} in-flight handler(s).",
+            self.config.queue,
+            handlers.len()
+        );
+        let drained = tokio::time::timeout(self.drain_timeout, async {
+            while handlers.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                "Worker on queue '{}' gave up on {} handler(s) after {:?}.",
+                self.config.queue,
+                handlers.len(),
+                self.drain_timeout
+            );
+            handlers.abort_all();
+        }
+        if channel.status().connected() {
+            channel.close(200, "Worker stopped").await?;
+        }
+
+        let stats = counters.stats();
+        info!(
+            "Worker on queue '{}' finished: {} acked, {} requeued, {} rejected, {} retried, {} dead-lettered.",
+            self.config.queue, stats.acked, stats.requeued, stats.rejected, stats.retried, stats.dead_lettered
+        );
+        result.map(|_| stats)
+    }
+
+    /// Opens the worker's channel and starts consuming.
+    async fn start(&self) -> Result<(Channel, Consumer), MessagingError> {
+        let channel = self.session.create_channel().await?;
+        if self.config.prefetch_count > 0 {
+            channel
+                .basic_qos(self.config.prefetch_count, BasicQosOptions::default())
+                .await?;
+        }
+        if self.retry.is_some() {
+            // Retries are acked only once the broker has confirmed the republish.
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await?;
+        }
+        let consumer = channel
+            .basic_consume(
+                &self.config.queue,
+                &self.config.consumer_tag,
+                BasicConsumeOptions {
+                    no_local: self.config.no_local,
+                    no_ack: self.config.no_ack,
+                    exclusive: self.config.exclusive,
+                    ..Default::default()
+                },
+                arguments_to_field_table(&self.config.arguments),
+            )
+            .await?;
+        Ok((channel, consumer))
+    }
+
+    fn retrier(&self, channel: &Channel) -> Option<Arc<Retrier>> {
+        self.retry.as_ref().map(|policy| {
+            Arc::new(Retrier {
+                policy: policy.clone(),
+                channel: channel.clone(),
+            })
+        })
+    }
+
+    /// Stops new deliveries. Those already received still come out of the consumer.
+    async fn cancel(&self, channel: &Channel, consumer: &Consumer) {
+        info!("Worker on queue '{}' shutting down.", self.config.queue);
+        if let Err(e) = channel
+            .basic_cancel(consumer.tag().as_str(), BasicCancelOptions::default())
+            .await
+        {
+            warn!(
+                "Failed to cancel consumer on queue '{}': {}",
+                self.config.queue, e
+            );
+        }
+    }
+}
+
+/// Runs the handler on a delivery and settles it according to the result.
+///
+/// With a `Retrier`, failures are forwarded to a delay queue or the dead letter
+/// exchange and then acked. If forwarding fails the delivery is requeued instead.
+pub(crate) async fn settle<H: MessageHandler + ?Sized>(
+    handler: &H,
+    delivery: &Delivery,
+    retrier: Option<&Arc<Retrier>>,
+) -> Settlement {
+    let result = AssertUnwindSafe(handler.handle(delivery))
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|_| Err(HandlerError::Reject("handler panicked".to_string())));
+
+    let (settlement, settled) = match (result, retrier) {
+        (Ok(()), _) => (Settlement::Acked, delivery.ack().await),
+        (Err(error), Some(retrier)) => {
+            let retryable = matches!(error, HandlerError::Requeue(_));
+            match retrier
+                .forward(delivery, retryable, &error.to_string())
+                .await
+            {
+                Ok(true) => (Settlement::Retried, delivery.ack().await),
+                Ok(false) => (Settlement::DeadLettered, delivery.ack().await),
+                Err(e) => {
+                    warn!(
+                        "Failed to forward delivery {}: {}. Requeueing it.",
+                        delivery.delivery_tag, e
+                    );
+                    (Settlement::Requeued, delivery.nack(true).await)
+                }
+            }
+        }
+        (Err(HandlerError::Requeue(reason)), None) => {
+            debug!("Requeueing delivery {}: {}", delivery.delivery_tag, reason);
+            (Settlement::Requeued, delivery.nack(true).await)
+        }
+        (Err(HandlerError::Reject(reason)), None) => {
+            warn!("Rejecting delivery {}: {}", delivery.delivery_tag, reason);
+            (Settlement::Rejected, delivery.nack(false).await)
+        }
+    };
+    if let Err(e) = settled {
+        warn!("Failed to settle delivery {}: {}", delivery.delivery_tag, e);
+    }
+    settlement
+}
+
+/// Completes on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_support::RecordingAcker;
+
+    /// Succeeds, requeues, rejects or panics depending on the payload.
+    struct PayloadHandler;
+
+    #[async_trait]
+    impl MessageHandler for PayloadHandler {
+        async fn handle(&self, delivery: &Delivery) -> Result<(), HandlerError> {
+            match delivery.payload.as_slice() {
+                b"ok" => Ok(()),
+                b"retry" => Err(HandlerError::Requeue("database busy".to_string())),
+                b"bad" => Err(HandlerError::Reject("malformed".to_string())),
+                _ => panic!("unexpected payload"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_results_decide_settlement() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let counters = Counters::default();
+
+        for (tag, payload) in [b"ok".as_slice(), b"retry", b"bad", b"boom"]
+            .into_iter()
+            .enumerate()
+        {
+            let delivery = Delivery::new(
+                tag as u64,
+                "orders".to_string(),
+                "order.placed".to_string(),
+                false,
+                Default::default(),
+                payload.to_vec(),
+                Box::new(RecordingAcker(calls.clone())),
+            );
+            counters.record(settle(&PayloadHandler, &delivery, None).await);
+        }
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["ack", "nack:true", "nack:false", "nack:false"]
+        );
+        assert_eq!(
+            counters.stats(),
+            WorkerStats {
+                acked: 1,
+                requeued: 1,
+                rejected: 2,
+                ..Default::default()
+            }
+        );
+    }
+}
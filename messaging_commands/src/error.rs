@@ -1,10 +1,12 @@
-use thiserror::Error;
+use std::time::Duration;
+
 use rabbitmq_config::RabbitMQError;
+use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum MessagingError {
     #[error("Configuration Error: {0}")]
-    Config(#[from] RabbitMQError),
+    Config(#[source] RabbitMQError),
 
     #[error("AMQP protocol error: {0}")]
     Amqp(#[from] lapin::Error),
@@ -12,6 +14,62 @@ pub enum MessagingError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Connection error: {0}")]
+    Connection(String),
+
     #[error("Client is not connected")]
     NotConnected,
-}
\ No newline at end of file
+
+    #[error("Publish to exchange '{exchange}' with routing key '{routing_key}' was nacked by the broker")]
+    PublishNacked {
+        exchange: String,
+        routing_key: String,
+    },
+
+    #[error("Message to exchange '{exchange}' with routing key '{routing_key}' was returned as unroutable: {reply_text}")]
+    Unroutable {
+        exchange: String,
+        routing_key: String,
+        reply_text: String,
+    },
+
+    #[error("{} of {total} batched publishes were nacked by the broker", nacked.len())]
+    BatchNacked { nacked: Vec<usize>, total: usize },
+
+    #[error("Connection recovery failed after {attempts} attempt(s): {reason}")]
+    RecoveryFailed { attempts: u32, reason: String },
+
+    #[error("No reply to RPC request '{routing_key}' within {timeout:?}")]
+    RpcTimeout {
+        routing_key: String,
+        timeout: Duration,
+    },
+}
+
+/// Publish outcomes keep their own variants; everything else is a `Config` error.
+impl From<RabbitMQError> for MessagingError {
+    fn from(error: RabbitMQError) -> Self {
+        match error {
+            RabbitMQError::PublishNacked {
+                exchange,
+                routing_key,
+            } => MessagingError::PublishNacked {
+                exchange,
+                routing_key,
+            },
+            RabbitMQError::Unroutable {
+                exchange,
+                routing_key,
+                reply_text,
+            } => MessagingError::Unroutable {
+                exchange,
+                routing_key,
+                reply_text,
+            },
+            RabbitMQError::BatchNacked { nacked, total } => {
+                MessagingError::BatchNacked { nacked, total }
+            }
+            other => MessagingError::Config(other),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-
 //! Messaging Commands Library
 //!
 //! A comprehensive library for handling messaging operations with various protocols,
@@ -32,15 +31,23 @@
 // Core module declarations
 pub mod clients;
 pub mod error;
+pub mod subscription;
 pub mod traits;
 
+#[cfg(test)]
+mod test_support;
+
 /// Prelude module for convenient imports
 ///
 /// This module re-exports the most commonly used types and traits.
 /// Import everything with: `use messaging_commands::prelude::*;`
 pub mod prelude {
-    pub use crate::clients::amqp::AmqpClient;
+    pub use crate::clients::amqp::{
+        AmqpClient, ConsumerWorker, HandlerError, MessageHandler, PublishOptions, RetryPolicy,
+        RpcClient, RpcRequest, RpcServer, WorkerStats,
+    };
     pub use crate::error::MessagingError;
+    pub use crate::subscription::{Delivery, DeliveryStream, SubscribeOptions};
     pub use crate::traits::MessagingClient;
 }
 
@@ -70,19 +77,20 @@ pub mod cli {
     #[derive(Subcommand)]
     pub enum Commands {
         Connect {
-            #[arg(short = 'P', long)]  // Capital P for Protocol
+            #[arg(short = 'P', long)] // Capital P for Protocol
             protocol: String,
 
-            #[arg(short = 'H', long, default_value = "localhost")]  // Capital H for Host (since -h is reserved for help)
+            #[arg(short = 'H', long, default_value = "localhost")]
+            // Capital H for Host (since -h is reserved for help)
             host: String,
 
-            #[arg(short, long)]  // -p for port
+            #[arg(short, long)] // -p for port
             port: u16,
 
-            #[arg(short, long)]  // -u for username
+            #[arg(short, long)] // -u for username
             username: Option<String>,
 
-            #[arg(short = 'w', long)]  // -w for password
+            #[arg(short = 'w', long)] // -w for password
             password: Option<String>,
         },
         Reload {
@@ -191,6 +199,95 @@ mod tests {
         let _b: Option<Box<dyn MessagingClient>> = None;
     }
 
+    #[tokio::test]
+    async fn delivery_settles_through_its_acker() {
+        use std::sync::{Arc, Mutex};
+        use subscription::Delivery;
+        use test_support::RecordingAcker;
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let delivery = Delivery::new(
+            1,
+            "orders".to_string(),
+            "order.placed".to_string(),
+            false,
+            Default::default(),
+            b"{}".to_vec(),
+            Box::new(RecordingAcker(calls.clone())),
+        );
+
+        delivery.ack().await.unwrap();
+        delivery.nack(true).await.unwrap();
+        delivery.reject(false).await.unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["ack", "nack:true", "reject:false"]
+        );
+    }
+
+    #[test]
+    fn publish_options_follow_publisher_config() {
+        use prelude::*;
+        use rabbitmq_config::PublisherConfig;
+
+        let publisher = PublisherConfig {
+            exchange: "orders".to_string(),
+            routing_key: "order.placed".to_string(),
+            mandatory: true,
+            content_type: "application/json".to_string(),
+            ..Default::default()
+        };
+        let options = PublishOptions::try_from(&publisher).unwrap();
+        assert!(options.mandatory);
+        assert_eq!(
+            options.properties.content_type.as_deref(),
+            Some("application/json")
+        );
+        assert!(!PublishOptions::default().mandatory);
+
+        let immediate = PublisherConfig {
+            immediate: true,
+            ..publisher
+        };
+        assert!(matches!(
+            PublishOptions::try_from(&immediate),
+            Err(MessagingError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn recovery_gives_up_after_max_retries() {
+        use prelude::*;
+        use rabbitmq_config::{RabbitMQConfig, RetryConfig};
+
+        // Bind and drop a listener to get a local port with nothing behind it.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = RabbitMQConfig {
+            host: "127.0.0.1".to_string(),
+            amqp_port: port,
+            ..Default::default()
+        };
+        let retry = RetryConfig {
+            max_retries: 2,
+            initial_interval_ms: 10,
+            multiplier: 2.0,
+            max_interval_ms: 50,
+            randomization_factor: 0.0,
+        };
+
+        let mut client = AmqpClient::new(config).with_recovery(retry);
+        match client.connect().await {
+            Err(MessagingError::RecoveryFailed { attempts, .. }) => assert_eq!(attempts, 3),
+            other => panic!("Expected RecoveryFailed, got {:?}", other.map(|_| ())),
+        }
+        assert!(!client.is_connected().await);
+    }
+
     #[cfg(feature = "cli")]
     mod cli_tests {
         use super::*;
@@ -205,17 +302,28 @@ mod tests {
             let args = vec![
                 "messaging_commands",
                 "connect",
-                "-P", "amqp",         // -P for protocol
-                "-H", "localhost",    // -H for host
-                "-p", "5672",         // -p for port
-                "-u", "guest",        // -u for username
-                "-w", "guest"         // -w for password
+                "-P",
+                "amqp", // -P for protocol
+                "-H",
+                "localhost", // -H for host
+                "-p",
+                "5672", // -p for port
+                "-u",
+                "guest", // -u for username
+                "-w",
+                "guest", // -w for password
             ];
 
             let cli = cli::Cli::try_parse_from(args).unwrap();
 
             match cli.command {
-                cli::Commands::Connect { protocol, host, port, username, password } => {
+                cli::Commands::Connect {
+                    protocol,
+                    host,
+                    port,
+                    username,
+                    password,
+                } => {
                     assert_eq!(protocol, "amqp");
                     assert_eq!(host, "localhost");
                     assert_eq!(port, 5672);
@@ -234,14 +342,22 @@ mod tests {
             let args = vec![
                 "messaging_commands",
                 "connect",
-                "--protocol", "amqp",
-                "--port", "5672"
+                "--protocol",
+                "amqp",
+                "--port",
+                "5672",
             ];
 
             let cli = cli::Cli::try_parse_from(args).unwrap();
 
             match cli.command {
-                cli::Commands::Connect { protocol, host, port, username, password } => {
+                cli::Commands::Connect {
+                    protocol,
+                    host,
+                    port,
+                    username,
+                    password,
+                } => {
                     assert_eq!(protocol, "amqp");
                     assert_eq!(host, "localhost"); // Default value
                     assert_eq!(port, 5672);
@@ -257,10 +373,7 @@ mod tests {
             use clap::Parser;
 
             // Test reload command with default config file
-            let args = vec![
-                "messaging_commands",
-                "reload"
-            ];
+            let args = vec!["messaging_commands", "reload"];
 
             let cli = cli::Cli::try_parse_from(args).unwrap();
 
@@ -280,7 +393,8 @@ mod tests {
             let args = vec![
                 "messaging_commands",
                 "reload",
-                "--config-file", "/custom/path/config.json"
+                "--config-file",
+                "/custom/path/config.json",
             ];
 
             let cli = cli::Cli::try_parse_from(args).unwrap();
@@ -301,8 +415,10 @@ mod tests {
                 "messaging_commands",
                 "--verbose",
                 "connect",
-                "--protocol", "amqp",
-                "--port", "5672"
+                "--protocol",
+                "amqp",
+                "--port",
+                "5672",
             ];
 
             let cli = cli::Cli::try_parse_from(args).unwrap();
@@ -364,8 +480,8 @@ mod tests {
             let args = vec![
                 "messaging_commands",
                 "connect",
-                "--protocol", "amqp"
-                // Missing required --port argument
+                "--protocol",
+                "amqp", // Missing required --port argument
             ];
 
             let result = cli::Cli::try_parse_from(args);
@@ -380,12 +496,14 @@ mod tests {
             let args = vec![
                 "messaging_commands",
                 "connect",
-                "--protocol", "amqp",
-                "--port", "invalid_port"
+                "--protocol",
+                "amqp",
+                "--port",
+                "invalid_port",
             ];
 
             let result = cli::Cli::try_parse_from(args);
             assert!(result.is_err());
         }
     }
-}
\ No newline at end of file
+}
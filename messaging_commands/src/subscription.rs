@@ -0,0 +1,101 @@
+//! Protocol-agnostic types for consuming messages through a `MessagingClient`.
+
+use std::fmt;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use rabbitmq_config::MessageProperties;
+
+use crate::error::MessagingError;
+
+/// A stream of deliveries returned by `MessagingClient::subscribe`.
+pub type DeliveryStream = Pin<Box<dyn Stream<Item = Result<Delivery, MessagingError>> + Send>>;
+
+/// Options controlling how a subscription is opened.
+#[derive(Debug, Clone, Default)]
+pub struct SubscribeOptions {
+    /// Consumer tag to register with the broker. An empty tag lets the broker generate one.
+    pub consumer_tag: String,
+    /// Deliveries are considered acknowledged as soon as they are sent.
+    pub no_ack: bool,
+    /// Request exclusive access to the queue.
+    pub exclusive: bool,
+    /// Maximum number of unacknowledged deliveries, if any.
+    pub prefetch_count: Option<u16>,
+}
+
+/// Settles a single delivery with the broker.
+///
+/// Implemented by each protocol client so that `Delivery` can stay protocol-agnostic.
+#[async_trait]
+pub trait DeliveryAcker: Send + Sync {
+    /// Positively acknowledge the delivery.
+    async fn ack(&self) -> Result<(), MessagingError>;
+
+    /// Negatively acknowledge the delivery, optionally requeueing it.
+    async fn nack(&self, requeue: bool) -> Result<(), MessagingError>;
+
+    /// Reject the delivery, optionally requeueing it.
+    async fn reject(&self, requeue: bool) -> Result<(), MessagingError>;
+}
+
+/// A message received from a subscription, together with its acknowledgement handle.
+pub struct Delivery {
+    pub delivery_tag: u64,
+    pub exchange: String,
+    pub routing_key: String,
+    pub redelivered: bool,
+    pub properties: MessageProperties,
+    pub payload: Vec<u8>,
+    acker: Box<dyn DeliveryAcker>,
+}
+
+impl Delivery {
+    /// Creates a new delivery settled through the given acker.
+    pub fn new(
+        delivery_tag: u64,
+        exchange: String,
+        routing_key: String,
+        redelivered: bool,
+        properties: MessageProperties,
+        payload: Vec<u8>,
+        acker: Box<dyn DeliveryAcker>,
+    ) -> Self {
+        Self {
+            delivery_tag,
+            exchange,
+            routing_key,
+            redelivered,
+            properties,
+            payload,
+            acker,
+        }
+    }
+
+    pub async fn ack(&self) -> Result<(), MessagingError> {
+        self.acker.ack().await
+    }
+
+    pub async fn nack(&self, requeue: bool) -> Result<(), MessagingError> {
+        self.acker.nack(requeue).await
+    }
+
+    pub async fn reject(&self, requeue: bool) -> Result<(), MessagingError> {
+        self.acker.reject(requeue).await
+    }
+}
+
+// Manual implementation of Debug to skip the acker
+impl fmt::Debug for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Delivery")
+            .field("delivery_tag", &self.delivery_tag)
+            .field("exchange", &self.exchange)
+            .field("routing_key", &self.routing_key)
+            .field("redelivered", &self.redelivered)
+            .field("properties", &self.properties)
+            .field("payload", &self.payload)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,29 @@
+//! Test doubles shared by the unit tests of this crate.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::subscription::DeliveryAcker;
+
+/// Records every settlement of a delivery, e.g. `ack`, `nack:true` or `reject:false`.
+#[derive(Default)]
+pub(crate) struct RecordingAcker(pub Arc<Mutex<Vec<String>>>);
+
+#[async_trait]
+impl DeliveryAcker for RecordingAcker {
+    async fn ack(&self) -> crate::Result<()> {
+        self.0.lock().unwrap().push("ack".to_string());
+        Ok(())
+    }
+
+    async fn nack(&self, requeue: bool) -> crate::Result<()> {
+        self.0.lock().unwrap().push(format!("nack:{requeue}"));
+        Ok(())
+    }
+
+    async fn reject(&self, requeue: bool) -> crate::Result<()> {
+        self.0.lock().unwrap().push(format!("reject:{requeue}"));
+        Ok(())
+    }
+}
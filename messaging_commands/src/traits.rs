@@ -1,6 +1,7 @@
 //! Defines the common traits for all messaging clients.
 
 use crate::error::MessagingError;
+use crate::subscription::{DeliveryStream, SubscribeOptions};
 use async_trait::async_trait;
 
 /// A trait representing a client that can connect to a messaging broker,
@@ -21,6 +22,16 @@ pub trait MessagingClient: Send + Sync {
         payload: &[u8],
     ) -> Result<(), MessagingError>;
 
+    /// Subscribe to a queue, returning a stream of deliveries.
+    ///
+    /// Unless `options.no_ack` is set, each delivery must be settled with
+    /// `ack`, `nack` or `reject`.
+    async fn subscribe(
+        &self,
+        queue: &str,
+        options: SubscribeOptions,
+    ) -> Result<DeliveryStream, MessagingError>;
+
     /// Disconnect from the broker and release resources.
     async fn disconnect(&mut self) -> Result<(), MessagingError>;
-}
\ No newline at end of file
+}
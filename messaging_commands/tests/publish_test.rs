@@ -0,0 +1,103 @@
+//! Publisher confirms, nacks and returns against a real broker.
+
+use lapin::types::{AMQPValue, FieldTable, LongString};
+use messaging_commands::prelude::*;
+use rabbitmq_config::{QueueInfo, RabbitMQConfig, RabbitMQMessage};
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_publish_batch_reports_nacked_messages() {
+    // Without `confirm_deliveries` the batch still has to switch to confirm mode.
+    let mut client = AmqpClient::new(RabbitMQConfig::default());
+    client.connect().await.expect("Failed to connect");
+
+    let mut arguments = FieldTable::default();
+    arguments.insert("x-max-length".into(), AMQPValue::LongInt(1));
+    arguments.insert(
+        "x-overflow".into(),
+        AMQPValue::LongString(LongString::from("reject-publish")),
+    );
+    let queue = QueueInfo {
+        name: "messaging_commands.batch_nack_test".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: true,
+        arguments,
+    };
+    client.declare_queue(&queue).await.unwrap();
+
+    let messages: Vec<RabbitMQMessage> = ["one", "two", "three"]
+        .iter()
+        .map(|payload| RabbitMQMessage {
+            exchange: String::new(),
+            routing_key: queue.name.clone(),
+            payload: payload.as_bytes().to_vec(),
+            properties: None,
+        })
+        .collect();
+
+    // The queue only accepts one message and rejects the rest of the batch.
+    match client.publish_batch(&messages).await {
+        Err(MessagingError::BatchNacked { nacked, total }) => {
+            assert_eq!((nacked, total), (vec![1, 2], 3))
+        }
+        other => panic!("Expected BatchNacked, got {other:?}"),
+    }
+
+    client.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_unroutable_mandatory_publish_is_returned() {
+    use std::sync::{Arc, Mutex};
+
+    let options = PublishOptions {
+        mandatory: true,
+        ..Default::default()
+    };
+
+    // Without a return handler the publish fails.
+    let mut client = AmqpClient::new(RabbitMQConfig::default());
+    client.connect().await.expect("Failed to connect");
+    match client
+        .publish_with_options(
+            "amq.direct",
+            "messaging_commands.nowhere",
+            b"lost",
+            &options,
+        )
+        .await
+    {
+        Err(MessagingError::Unroutable {
+            exchange,
+            routing_key,
+            ..
+        }) => {
+            assert_eq!(
+                (exchange.as_str(), routing_key.as_str()),
+                ("amq.direct", "messaging_commands.nowhere")
+            )
+        }
+        other => panic!("Expected Unroutable, got {other:?}"),
+    }
+    client.disconnect().await.unwrap();
+
+    // With one, the handler receives the message and the publish succeeds.
+    let returned = Arc::new(Mutex::new(Vec::new()));
+    let recorded = returned.clone();
+    let mut client = AmqpClient::new(RabbitMQConfig::default())
+        .on_return(move |message| recorded.lock().unwrap().push(message.payload));
+    client.connect().await.expect("Failed to connect");
+    client
+        .publish_with_options(
+            "amq.direct",
+            "messaging_commands.nowhere",
+            b"lost",
+            &options,
+        )
+        .await
+        .unwrap();
+    assert_eq!(*returned.lock().unwrap(), vec![b"lost".to_vec()]);
+    client.disconnect().await.unwrap();
+}
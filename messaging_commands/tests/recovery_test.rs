@@ -0,0 +1,140 @@
+//! Recovery tests against a stand-in broker.
+//!
+//! The stand-in is a TCP proxy in front of a local RabbitMQ. Stopping the proxy
+//! drops every client connection just like a broker restart would, and starting
+//! it again on the same port lets the client reconnect.
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use messaging_commands::prelude::*;
+use rabbitmq_config::{QueueInfo, RabbitMQConfig, RetryConfig};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const BROKER_ADDR: &str = "127.0.0.1:5672";
+
+/// Starts a proxy on `port` that forwards to the real broker.
+async fn start_stand_in(port: u16) -> JoinHandle<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .expect("Failed to bind stand-in broker");
+
+    tokio::spawn(async move {
+        let mut connections = tokio::task::JoinSet::new();
+        loop {
+            let (mut inbound, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => continue,
+            };
+            connections.spawn(async move {
+                if let Ok(mut outbound) = TcpStream::connect(BROKER_ADDR).await {
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                }
+            });
+        }
+        // Dropping the JoinSet when this task is aborted tears down every proxied connection.
+    })
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_publish_and_consume_survive_broker_restart() {
+    let port = free_port();
+    let mut stand_in = start_stand_in(port).await;
+
+    let config = RabbitMQConfig {
+        host: "127.0.0.1".to_string(),
+        amqp_port: port,
+        ..Default::default()
+    };
+    let retry = RetryConfig {
+        max_retries: 20,
+        initial_interval_ms: 100,
+        multiplier: 1.5,
+        max_interval_ms: 1000,
+        randomization_factor: 0.0,
+    };
+
+    let mut client = AmqpClient::new(config).with_recovery(retry);
+    client.connect().await.expect("Initial connect failed");
+
+    let queue = QueueInfo {
+        name: "messaging_commands.recovery_test".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: true,
+        arguments: Default::default(),
+    };
+    client.declare_queue(&queue).await.unwrap();
+    let mut deliveries = client
+        .subscribe(&queue.name, SubscribeOptions::default())
+        .await
+        .unwrap();
+
+    client.publish("", &queue.name, b"before").await.unwrap();
+    let delivery = deliveries.next().await.unwrap().unwrap();
+    assert_eq!(delivery.payload, b"before");
+    delivery.ack().await.unwrap();
+
+    // Kill the stand-in broker and bring it back after a short outage.
+    stand_in.abort();
+    let _ = stand_in.await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!client.is_connected().await);
+    stand_in = start_stand_in(port).await;
+
+    // The auto-delete queue vanished with the old connection, so publishing only
+    // reaches it if the client redeclared it during recovery.
+    client.publish("", &queue.name, b"after").await.unwrap();
+    let delivery = tokio::time::timeout(Duration::from_secs(10), deliveries.next())
+        .await
+        .expect("Subscription did not resume")
+        .unwrap()
+        .unwrap();
+    assert_eq!(delivery.payload, b"after");
+    delivery.ack().await.unwrap();
+
+    client.disconnect().await.unwrap();
+    stand_in.abort();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_channel_error_reopens_channel_on_same_connection() {
+    let retry = RetryConfig {
+        max_retries: 3,
+        initial_interval_ms: 100,
+        multiplier: 1.5,
+        max_interval_ms: 1000,
+        randomization_factor: 0.0,
+    };
+    let mut client = AmqpClient::new(RabbitMQConfig::default()).with_recovery(retry);
+    client.connect().await.expect("Initial connect failed");
+    let endpoint = client.connected_endpoint();
+
+    // Publishing to a missing exchange closes the channel with a 404, not the connection.
+    let _ = client
+        .publish("messaging_commands.missing_exchange", "", b"lost")
+        .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!client.is_connected().await);
+
+    client
+        .publish("", "messaging_commands.nowhere", b"after")
+        .await
+        .unwrap();
+    assert!(client.is_connected().await);
+    assert_eq!(client.connected_endpoint(), endpoint);
+    assert!(client.failed_endpoints().is_empty());
+
+    client.disconnect().await.unwrap();
+}
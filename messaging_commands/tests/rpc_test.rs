@@ -0,0 +1,50 @@
+//! Request/reply round trips through direct reply-to.
+
+use std::time::Duration;
+
+use messaging_commands::prelude::*;
+use rabbitmq_config::{QueueInfo, RabbitMQConfig};
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_rpc_call_receives_reply() {
+    let mut client = AmqpClient::new(RabbitMQConfig::default());
+    client.connect().await.expect("Failed to connect");
+
+    let queue = QueueInfo {
+        name: "messaging_commands.rpc_test".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: true,
+        arguments: Default::default(),
+    };
+    client.declare_queue(&queue).await.unwrap();
+
+    let server = RpcServer::new(&client, &queue.name);
+    let serving = tokio::spawn(async move {
+        server
+            .serve(|request: RpcRequest| async move {
+                let mut reply = b"re: ".to_vec();
+                reply.extend_from_slice(&request.payload);
+                Ok(reply)
+            })
+            .await
+    });
+
+    let rpc = RpcClient::new(&client).with_timeout(Duration::from_secs(5));
+    let reply = rpc.call("", &queue.name, b"user.lookup").await.unwrap();
+    assert_eq!(reply.payload, b"re: user.lookup");
+    assert!(reply.properties.correlation_id.is_some());
+
+    // Nobody consumes this queue, so the call must time out.
+    let unanswered = RpcClient::new(&client).with_timeout(Duration::from_millis(200));
+    let err = unanswered
+        .call("", "messaging_commands.rpc_test.nobody", b"order.status")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MessagingError::RpcTimeout { .. }));
+
+    serving.abort();
+    rpc.close().await.unwrap();
+    client.disconnect().await.unwrap();
+}
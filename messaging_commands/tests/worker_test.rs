@@ -0,0 +1,76 @@
+//! Consumer workers settling deliveries and shutting down gracefully.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use messaging_commands::prelude::*;
+use rabbitmq_config::{ConsumerConfig, QueueInfo, RabbitMQConfig};
+
+struct CountingHandler {
+    in_flight: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl MessageHandler for CountingHandler {
+    async fn handle(&self, delivery: &Delivery) -> Result<(), HandlerError> {
+        let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        match delivery.payload.as_slice() {
+            b"bad" => Err(HandlerError::Reject("malformed".to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_worker_settles_and_drains_on_shutdown() {
+    let mut client = AmqpClient::new(RabbitMQConfig::default());
+    client.connect().await.expect("Failed to connect");
+
+    let queue = QueueInfo {
+        name: "messaging_commands.worker_test".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: true,
+        arguments: Default::default(),
+    };
+    client.declare_queue(&queue).await.unwrap();
+    for payload in [b"one".as_slice(), b"two", b"three", b"bad"] {
+        client.publish("", &queue.name, payload).await.unwrap();
+    }
+
+    let config = ConsumerConfig {
+        queue: queue.name.clone(),
+        consumer_tag: "worker_test".to_string(),
+        prefetch_count: 4,
+        ..Default::default()
+    };
+    let peak = Arc::new(AtomicUsize::new(0));
+    let handler = CountingHandler {
+        in_flight: Arc::new(AtomicUsize::new(0)),
+        peak: peak.clone(),
+    };
+
+    let worker = ConsumerWorker::new(&client, config).with_concurrency(2);
+    let stats = worker
+        .run_until(handler, tokio::time::sleep(Duration::from_secs(1)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stats,
+        WorkerStats {
+            acked: 3,
+            rejected: 1,
+            ..Default::default()
+        }
+    );
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+    client.disconnect().await.unwrap();
+}
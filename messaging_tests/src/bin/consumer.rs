@@ -1,6 +1,6 @@
 use clap::Parser;
 use log::info;
-use rabbitmq_config::{get_password, load_config_file, RabbitMQClient, RabbitMQConfig};
+use rabbitmq_config::{ConfigArgs, RabbitMQClient};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -8,6 +8,9 @@ struct Args {
     /// The queue to consume a message from.
     #[arg(short, long)]
     queue: String,
+
+    #[command(flatten)]
+    config: ConfigArgs,
 }
 
 #[tokio::main]
@@ -16,26 +19,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     // --- Connect to RabbitMQ ---
-    let file_config = load_config_file()?;
-    let conn_info = file_config.connection;
-
-    info!("Connecting to RabbitMQ as user: '{}'", conn_info.username);
-    let password = get_password()?;
-
-    let config = RabbitMQConfig {
-        host: conn_info.host,
-        amqp_port: conn_info.amqp_port,
-        management_port: conn_info.management_port,
-        username: conn_info.username,
-        password,
-        vhost: conn_info.vhost,
-    };
+    let file_config = args.config.load()?.config;
 
-    let client = RabbitMQClient::new(config).await?;
+    info!(
+        "Connecting to RabbitMQ as user: '{}'",
+        file_config.connection.username
+    );
+    let client = RabbitMQClient::from_config(&file_config).await?;
     info!("Successfully connected to RabbitMQ.");
 
     // --- Consume Message ---
-    info!("Attempting to consume one message from queue '{}'...", args.queue);
+    info!(
+        "Attempting to consume one message from queue '{}'...",
+        args.queue
+    );
     match client.consume_one(&args.queue).await? {
         Some(payload) => {
             // Print the raw payload to stdout for verification
@@ -1,6 +1,6 @@
 use clap::Parser;
 use log::info;
-use rabbitmq_config::{load_config_file, RabbitMQClient, RabbitMQConfig, RabbitMQMessage};
+use rabbitmq_config::{ConfigArgs, RabbitMQClient, RabbitMQMessage};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -16,6 +16,9 @@ struct Args {
     /// The message payload, as a JSON string.
     #[arg(short, long)]
     payload: String,
+
+    #[command(flatten)]
+    config: ConfigArgs,
 }
 
 #[tokio::main]
@@ -24,22 +27,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     // --- Connect to RabbitMQ ---
-    let file_config = load_config_file()?;
-    let conn_info = file_config.connection;
-
-    info!("Connecting to RabbitMQ as user: '{}'", conn_info.username);
-    let password = rpassword::prompt_password("Enter password: ")?;
-
-    let config = RabbitMQConfig {
-        host: conn_info.host,
-        amqp_port: conn_info.amqp_port,
-        management_port: conn_info.management_port,
-        username: conn_info.username,
-        password,
-        vhost: conn_info.vhost,
-    };
+    let file_config = args.config.load()?.config;
 
-    let client = RabbitMQClient::new(config).await?;
+    info!(
+        "Connecting to RabbitMQ as user: '{}'",
+        file_config.connection.username
+    );
+    let client = RabbitMQClient::from_config(&file_config).await?;
     info!("Successfully connected to RabbitMQ.");
 
     // --- Publish Message ---
@@ -0,0 +1,277 @@
+// rabbitmq-config/src/apply.rs
+
+use lapin::{
+    options::{ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions},
+    protocol::{AMQPErrorKind, AMQPSoftError},
+    types::FieldTable,
+    Channel,
+};
+use log::{info, warn};
+use serde::Serialize;
+use std::fmt;
+
+use crate::client::exchange_kind;
+use crate::config::arguments_to_field_table;
+use crate::{
+    BindingConfig, ExchangeInfo, QueueInfo, RabbitMQClient, RabbitMQError, RabbitMQFullConfig,
+};
+
+/// The kind of topology object an `ApplyResult` refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TopologyObjectKind {
+    Exchange,
+    Queue,
+    Binding,
+}
+
+impl fmt::Display for TopologyObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyObjectKind::Exchange => write!(f, "exchange"),
+            TopologyObjectKind::Queue => write!(f, "queue"),
+            TopologyObjectKind::Binding => write!(f, "binding"),
+        }
+    }
+}
+
+/// What applying a single topology object did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyStatus {
+    /// The object did not exist and was declared.
+    Created,
+    /// The object already existed with the desired settings.
+    Unchanged,
+    /// The binding was declared. AMQP cannot tell whether it already existed.
+    Bound,
+    /// The object could not be declared, e.g. because it exists with different settings.
+    Failed(String),
+}
+
+/// The outcome of applying one exchange, queue or binding.
+#[derive(Debug, Clone)]
+pub struct ApplyResult {
+    pub kind: TopologyObjectKind,
+    pub name: String,
+    pub status: ApplyStatus,
+}
+
+/// Per-object results of `RabbitMQClient::apply_topology`, in declaration order.
+#[derive(Debug, Clone, Default)]
+pub struct ApplyReport {
+    pub results: Vec<ApplyResult>,
+}
+
+impl ApplyReport {
+    pub fn has_failures(&self) -> bool {
+        self.failures().next().is_some()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ApplyResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.status, ApplyStatus::Failed(_)))
+    }
+
+    fn push(&mut self, kind: TopologyObjectKind, name: String, status: ApplyStatus) {
+        if let ApplyStatus::Failed(reason) = &status {
+            warn!("component=RabbitMQClient action=apply_topology kind={kind:?} name={name} error={reason}");
+        }
+        self.results.push(ApplyResult { kind, name, status });
+    }
+}
+
+impl RabbitMQClient {
+    /// Declares the exchanges, queues and bindings of `config`.
+    ///
+    /// Applying is idempotent: objects that already exist with the same settings are
+    /// reported as unchanged. A failing object does not stop the others; it is reported
+    /// in the result. Declarations run on a dedicated channel so that failures never
+    /// close the client's own channel. Only an unusable connection is returned as an error.
+    pub async fn apply_topology(
+        &self,
+        config: &RabbitMQFullConfig,
+    ) -> Result<ApplyReport, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=apply_topology exchanges={} queues={} bindings={}",
+            config.exchanges.len(),
+            config.queues.len(),
+            config.bindings.len()
+        );
+        let mut applier = Applier {
+            client: self,
+            channel: self.create_channel().await?,
+        };
+        let mut report = ApplyReport::default();
+
+        for exchange in &config.exchanges {
+            let status = applier.exchange(&ExchangeInfo::from(exchange)).await?;
+            report.push(TopologyObjectKind::Exchange, exchange.name.clone(), status);
+        }
+        for queue in &config.queues {
+            let status = applier.queue(&QueueInfo::from(queue)).await?;
+            report.push(TopologyObjectKind::Queue, queue.name.clone(), status);
+        }
+        for binding in &config.bindings {
+            let status = applier
+                .binding(
+                    &binding.queue,
+                    &binding.exchange,
+                    &binding.routing_key,
+                    arguments_to_field_table(&binding.arguments),
+                )
+                .await?;
+            report.push(TopologyObjectKind::Binding, binding_name(binding), status);
+        }
+
+        if applier.channel.status().connected() {
+            let _ = applier.channel.close(200, "Topology applied").await;
+        }
+        Ok(report)
+    }
+}
+
+/// Declares objects on its own channel, reopening it whenever the broker closes it.
+struct Applier<'a> {
+    client: &'a RabbitMQClient,
+    channel: Channel,
+}
+
+impl Applier<'_> {
+    async fn exchange(&mut self, exchange: &ExchangeInfo) -> Result<ApplyStatus, RabbitMQError> {
+        let kind = match exchange_kind(&exchange.kind) {
+            Ok(kind) => kind,
+            Err(e) => return Ok(ApplyStatus::Failed(e.to_string())),
+        };
+
+        let passive = ExchangeDeclareOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let probe = self
+            .channel
+            .exchange_declare(&exchange.name, kind.clone(), passive, FieldTable::default())
+            .await;
+        let existed = match probe {
+            Ok(()) => true,
+            Err(e) if is_not_found(&e) => false,
+            Err(e) => return self.failed(e).await,
+        };
+        self.ensure_channel().await?;
+
+        // The default exchange and `amq.*` exchanges are predeclared and cannot be redeclared.
+        if exchange.name.is_empty() || exchange.name.starts_with("amq.") {
+            return Ok(if existed {
+                ApplyStatus::Unchanged
+            } else {
+                ApplyStatus::Failed(format!("Exchange name '{}' is reserved", exchange.name))
+            });
+        }
+
+        let options = ExchangeDeclareOptions {
+            durable: exchange.durable,
+            auto_delete: exchange.auto_delete,
+            internal: exchange.internal,
+            ..Default::default()
+        };
+        match self
+            .channel
+            .exchange_declare(&exchange.name, kind, options, exchange.arguments.clone())
+            .await
+        {
+            Ok(()) if existed => Ok(ApplyStatus::Unchanged),
+            Ok(()) => Ok(ApplyStatus::Created),
+            Err(e) => self.failed(e).await,
+        }
+    }
+
+    async fn queue(&mut self, queue: &QueueInfo) -> Result<ApplyStatus, RabbitMQError> {
+        if queue.name.is_empty() {
+            return Ok(ApplyStatus::Failed(
+                "Server-named queues cannot be applied".to_string(),
+            ));
+        }
+
+        let passive = QueueDeclareOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let probe = self
+            .channel
+            .queue_declare(&queue.name, passive, FieldTable::default())
+            .await;
+        let existed = match probe {
+            Ok(_) => true,
+            Err(e) if is_not_found(&e) => false,
+            Err(e) => return self.failed(e).await,
+        };
+        self.ensure_channel().await?;
+
+        let options = QueueDeclareOptions {
+            durable: queue.durable,
+            exclusive: queue.exclusive,
+            auto_delete: queue.auto_delete,
+            ..Default::default()
+        };
+        match self
+            .channel
+            .queue_declare(&queue.name, options, queue.arguments.clone())
+            .await
+        {
+            Ok(_) if existed => Ok(ApplyStatus::Unchanged),
+            Ok(_) => Ok(ApplyStatus::Created),
+            Err(e) => self.failed(e).await,
+        }
+    }
+
+    async fn binding(
+        &mut self,
+        queue: &str,
+        exchange: &str,
+        routing_key: &str,
+        arguments: FieldTable,
+    ) -> Result<ApplyStatus, RabbitMQError> {
+        match self
+            .channel
+            .queue_bind(
+                queue,
+                exchange,
+                routing_key,
+                QueueBindOptions::default(),
+                arguments,
+            )
+            .await
+        {
+            Ok(()) => Ok(ApplyStatus::Bound),
+            Err(e) => self.failed(e).await,
+        }
+    }
+
+    async fn failed(&mut self, error: lapin::Error) -> Result<ApplyStatus, RabbitMQError> {
+        self.ensure_channel().await?;
+        Ok(ApplyStatus::Failed(error.to_string()))
+    }
+
+    /// Replaces the channel if the broker closed it after a failed declare.
+    async fn ensure_channel(&mut self) -> Result<(), RabbitMQError> {
+        if !self.channel.status().connected() {
+            self.channel = self.client.create_channel().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Identifies a binding in reports, e.g. `orders -> orders.placed (order.placed)`.
+pub(crate) fn binding_name(binding: &BindingConfig) -> String {
+    format!(
+        "{} -> {} ({})",
+        binding.exchange, binding.queue, binding.routing_key
+    )
+}
+
+fn is_not_found(error: &lapin::Error) -> bool {
+    matches!(
+        error,
+        lapin::Error::ProtocolError(e) if *e.kind() == AMQPErrorKind::Soft(AMQPSoftError::NOTFOUND)
+    )
+}
@@ -3,53 +3,108 @@
 use futures_util::stream::StreamExt;
 use lapin::{
     options::{
-        BasicAckOptions, BasicConsumeOptions, BasicPublishOptions, ExchangeDeclareOptions,
-        QueueBindOptions, QueueDeclareOptions,
+        BasicAckOptions, BasicConsumeOptions, BasicPublishOptions, BasicQosOptions,
+        ConfirmSelectOptions, ExchangeBindOptions, ExchangeDeclareOptions, ExchangeDeleteOptions,
+        ExchangeUnbindOptions, QueueBindOptions, QueueDeclareOptions, QueueDeleteOptions,
+        QueuePurgeOptions,
     },
+    protocol::{AMQPErrorKind, AMQPSoftError},
+    publisher_confirm::{Confirmation, PublisherConfirm},
     types::FieldTable,
-    BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind,
+    Channel, Connection, ExchangeKind,
 };
-use log::info;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 
-use crate::{ExchangeInfo, QueueInfo, RabbitMQConfig, RabbitMQError, RabbitMQMessage};
+use crate::{
+    confirmation_result, open_failover_connection, ChannelConfig, ConnectionConfig, Endpoint,
+    EndpointPool, ExchangeInfo, MessageProperties, PublisherConfig, QueueInfo, RabbitMQConfig,
+    RabbitMQError, RabbitMQFullConfig, RabbitMQMessage, ReturnedMessage, TlsOptions,
+    TopologyObjectKind,
+};
+
+/// Callback invoked with mandatory messages the broker could not route.
+pub type ReturnHandler = Arc<dyn Fn(ReturnedMessage) + Send + Sync>;
 
 /// Represents a connection to RabbitMQ
 pub struct RabbitMQClient {
     connection: Option<Connection>,
     channel: Option<Channel>,
     config: RabbitMQConfig,
+    settings: ConnectionConfig,
+    endpoints: EndpointPool,
+    channel_config: ChannelConfig,
+    return_handler: Option<ReturnHandler>,
 }
 
 impl RabbitMQClient {
     /// Creates a new RabbitMQ client and establishes a connection
     pub async fn new(config: RabbitMQConfig) -> Result<Self, RabbitMQError> {
+        Self::with_settings(config, ConnectionConfig::default()).await
+    }
+
+    /// Creates a new RabbitMQ client and establishes an AMQPS connection.
+    ///
+    /// See `connect_amqps` for how `tls_options` are applied; `None` trusts the
+    /// system roots and verifies the hostname.
+    pub async fn with_tls(
+        config: RabbitMQConfig,
+        tls_options: Option<TlsOptions>,
+    ) -> Result<Self, RabbitMQError> {
+        let settings = ConnectionConfig {
+            use_tls: true,
+            tls_options,
+            ..ConnectionConfig::default()
+        };
+        Self::with_settings(config, settings).await
+    }
+
+    /// Creates a new RabbitMQ client, connecting with the timeout, heartbeat, connection
+    /// name, client properties and TLS settings of `settings`.
+    ///
+    /// Hosts, credentials and vhost come from `config`, see `open_failover_connection`.
+    pub async fn with_settings(
+        config: RabbitMQConfig,
+        settings: ConnectionConfig,
+    ) -> Result<Self, RabbitMQError> {
         info!(
-            "component=RabbitMQClient action=new host={} port={}",
-            config.host, config.amqp_port
+            "component=RabbitMQClient action=new host={} port={} tls={}",
+            config.host, config.amqp_port, settings.use_tls
         );
 
+        let endpoints = config.amqp_pool();
         let mut client = Self {
             connection: None,
             channel: None,
             config,
+            settings,
+            endpoints,
+            channel_config: ChannelConfig::default(),
+            return_handler: None,
         };
 
         client.connect().await?;
         Ok(client)
     }
 
-    async fn connect(&mut self) -> Result<(), RabbitMQError> {
-        info!("component=RabbitMQClient action=connect");
-        let uri = self.config.to_uri();
-        let connection_timeout = Duration::from_secs(10);
-        let connection_future = Connection::connect(&uri, ConnectionProperties::default());
+    /// Connects with everything in a configuration file: the connection settings and
+    /// the channel's prefetch and publisher-confirm settings.
+    ///
+    /// The password is resolved as in `ConnectionConfig::to_rabbitmq_config`.
+    pub async fn from_config(full_config: &RabbitMQFullConfig) -> Result<Self, RabbitMQError> {
+        let config = full_config.connection.to_rabbitmq_config()?;
+        let mut client = Self::with_settings(config, full_config.connection.clone()).await?;
+        client.configure_channel(&full_config.channel).await?;
+        Ok(client)
+    }
 
-        let connection = timeout(connection_timeout, connection_future)
-            .await
-            .map_err(|_| RabbitMQError::TimeoutError("Connection timeout".to_string()))?
-            .map_err(|e| RabbitMQError::ConnectionError(format!("{e}")))?;
+    async fn connect(&mut self) -> Result<(), RabbitMQError> {
+        let (connection, endpoint) =
+            open_failover_connection(&self.config, &self.settings, &self.endpoints).await?;
+        info!("component=RabbitMQClient action=connect endpoint={endpoint}");
 
         let channel = connection
             .create_channel()
@@ -61,19 +116,84 @@ impl RabbitMQClient {
         Ok(())
     }
 
+    /// The cluster node the client is connected to.
+    pub fn connected_endpoint(&self) -> Option<Endpoint> {
+        self.endpoints.current()
+    }
+
+    /// Nodes that could not be reached, which are tried last when reconnecting.
+    pub fn failed_endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints.failed()
+    }
+
     pub async fn close(&self) -> Result<(), RabbitMQError> {
         info!("component=RabbitMQClient action=close");
         if let Some(connection) = &self.connection {
             connection
                 .close(0, "Closing connection")
                 .await
-                .map_err(|e| RabbitMQError::ConnectionError(format!("Error closing connection: {e}")))?;
+                .map_err(|e| {
+                    RabbitMQError::ConnectionError(format!("Error closing connection: {e}"))
+                })?;
         }
         Ok(())
     }
 
+    /// Applies prefetch and publisher-confirm settings to the client's channel.
+    ///
+    /// With `confirm_deliveries` enabled the channel is put in confirm-select mode,
+    /// so `publish_message` only returns once the broker has acked the message.
+    pub async fn configure_channel(
+        &mut self,
+        channel_config: &ChannelConfig,
+    ) -> Result<(), RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=configure_channel prefetch_count={} confirm_deliveries={}",
+            channel_config.default_prefetch_count, channel_config.confirm_deliveries
+        );
+        let channel = self
+            .channel
+            .as_ref()
+            .ok_or_else(|| RabbitMQError::ChannelError("No channel available".to_string()))?;
+
+        // `default_prefetch_size` is not applied: RabbitMQ does not implement prefetch by size.
+        if channel_config.default_prefetch_count > 0 {
+            channel
+                .basic_qos(
+                    channel_config.default_prefetch_count,
+                    BasicQosOptions::default(),
+                )
+                .await
+                .map_err(|e| RabbitMQError::ChannelError(format!("Failed to set prefetch: {e}")))?;
+        }
+        if channel_config.confirm_deliveries && !channel.status().confirm() {
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await
+                .map_err(|e| {
+                    RabbitMQError::ChannelError(format!("Failed to enable publisher confirms: {e}"))
+                })?;
+        }
+
+        self.channel_config = channel_config.clone();
+        Ok(())
+    }
+
+    /// Registers a handler for unroutable mandatory messages.
+    ///
+    /// Without a handler, `publish_with_config` reports them as `RabbitMQError::Unroutable`.
+    pub fn set_return_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(ReturnedMessage) + Send + Sync + 'static,
+    {
+        self.return_handler = Some(Arc::new(handler));
+    }
+
     pub async fn declare_queue(&self, queue_info: &QueueInfo) -> Result<(), RabbitMQError> {
-        info!("component=RabbitMQClient action=declare_queue queue={}", queue_info.name);
+        info!(
+            "component=RabbitMQClient action=declare_queue queue={}",
+            queue_info.name
+        );
         if let Some(channel) = &self.channel {
             let options = QueueDeclareOptions {
                 durable: queue_info.durable,
@@ -87,20 +207,22 @@ impl RabbitMQClient {
                 .map_err(|e| RabbitMQError::QueueError(format!("Failed to declare queue: {e}")))?;
             Ok(())
         } else {
-            Err(RabbitMQError::ChannelError("No channel available".to_string()))
+            Err(RabbitMQError::ChannelError(
+                "No channel available".to_string(),
+            ))
         }
     }
 
-    pub async fn declare_exchange(&self, exchange_info: &ExchangeInfo) -> Result<(), RabbitMQError> {
-        info!("component=RabbitMQClient action=declare_exchange exchange={}", exchange_info.name);
+    pub async fn declare_exchange(
+        &self,
+        exchange_info: &ExchangeInfo,
+    ) -> Result<(), RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=declare_exchange exchange={}",
+            exchange_info.name
+        );
         if let Some(channel) = &self.channel {
-            let kind = match exchange_info.kind.as_str() {
-                "direct" => ExchangeKind::Direct,
-                "fanout" => ExchangeKind::Fanout,
-                "topic" => ExchangeKind::Topic,
-                "headers" => ExchangeKind::Headers,
-                _ => return Err(RabbitMQError::ExchangeError(format!("Invalid exchange type: {}", exchange_info.kind))),
-            };
+            let kind = exchange_kind(&exchange_info.kind)?;
             let options = ExchangeDeclareOptions {
                 durable: exchange_info.durable,
                 auto_delete: exchange_info.auto_delete,
@@ -108,61 +230,397 @@ impl RabbitMQClient {
                 ..Default::default()
             };
             channel
-                .exchange_declare(&exchange_info.name, kind, options, exchange_info.arguments.clone())
+                .exchange_declare(
+                    &exchange_info.name,
+                    kind,
+                    options,
+                    exchange_info.arguments.clone(),
+                )
                 .await
-                .map_err(|e| RabbitMQError::ExchangeError(format!("Failed to declare exchange: {e}")))?;
+                .map_err(|e| {
+                    RabbitMQError::ExchangeError(format!("Failed to declare exchange: {e}"))
+                })?;
             Ok(())
         } else {
-            Err(RabbitMQError::ChannelError("No channel available".to_string()))
+            Err(RabbitMQError::ChannelError(
+                "No channel available".to_string(),
+            ))
         }
     }
 
     pub async fn publish_message(&self, message: &RabbitMQMessage) -> Result<(), RabbitMQError> {
-        info!("component=RabbitMQClient action=publish_message exchange={} routing_key={}", message.exchange, message.routing_key);
-        if let Some(channel) = &self.channel {
-            let payload = &message.payload;
-            let mut properties = BasicProperties::default();
-            if let Some(props) = &message.properties {
-                if let Some(content_type) = &props.content_type {
-                    properties = properties.with_content_type(content_type.as_str().into());
-                }
-                if let Some(content_encoding) = &props.content_encoding {
-                    properties = properties.with_content_encoding(content_encoding.as_str().into());
-                }
-                if let Some(delivery_mode) = props.delivery_mode {
-                    properties = properties.with_delivery_mode(delivery_mode);
-                }
-                if let Some(priority) = props.priority {
-                    properties = properties.with_priority(priority);
-                }
-            }
+        info!(
+            "component=RabbitMQClient action=publish_message exchange={} routing_key={}",
+            message.exchange, message.routing_key
+        );
+        let properties = message.properties.clone().unwrap_or_default();
+        let confirm = self
+            .start_publish(message, BasicPublishOptions::default(), &properties)
+            .await?;
+        self.await_confirm(message, confirm).await
+    }
+
+    /// Publishes a message using the flags and default properties of a `PublisherConfig`.
+    ///
+    /// Properties set on the message take precedence over those of the publisher.
+    /// Mandatory messages that cannot be routed are passed to the return handler, or
+    /// reported as `RabbitMQError::Unroutable`. Returns can only be matched to their
+    /// publish in confirm mode, so the channel is switched to it when needed.
+    pub async fn publish_with_config(
+        &self,
+        message: &RabbitMQMessage,
+        publisher: &PublisherConfig,
+    ) -> Result<(), RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=publish_with_config exchange={} routing_key={} mandatory={}",
+            message.exchange, message.routing_key, publisher.mandatory
+        );
+        if publisher.immediate {
+            return Err(RabbitMQError::ConfigError(
+                "The immediate flag is not supported by RabbitMQ".to_string(),
+            ));
+        }
+        if publisher.mandatory {
+            self.ensure_confirm_mode("mandatory_publish").await?;
+        }
+
+        let options = BasicPublishOptions {
+            mandatory: publisher.mandatory,
+            ..Default::default()
+        };
+        let properties = message
+            .properties
+            .clone()
+            .unwrap_or_default()
+            .with_defaults(&MessageProperties::from(publisher));
+        let confirm = self.start_publish(message, options, &properties).await?;
+        self.await_confirm(message, confirm).await
+    }
+
+    /// Publishes a batch of messages and waits for all of their confirms.
+    ///
+    /// The channel is switched to confirm mode if it is not in it already. At most
+    /// `max_outstanding_confirms` publishes from the channel configuration are left
+    /// unconfirmed at any time. Nacked messages are reported by index once the whole
+    /// batch has been confirmed.
+    pub async fn publish_batch(&self, messages: &[RabbitMQMessage]) -> Result<(), RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=publish_batch count={}",
+            messages.len()
+        );
+        self.ensure_confirm_mode("publish_batch").await?;
+        let channel = self
+            .channel
+            .as_ref()
+            .ok_or_else(|| RabbitMQError::ChannelError("No channel available".to_string()))?;
+        publish_confirmed_batch(
+            channel,
+            messages,
+            self.channel_config.max_outstanding_confirms,
+            self.return_handler.as_ref(),
+        )
+        .await
+    }
+
+    async fn ensure_confirm_mode(&self, reason: &str) -> Result<(), RabbitMQError> {
+        let channel = self
+            .channel
+            .as_ref()
+            .ok_or_else(|| RabbitMQError::ChannelError("No channel available".to_string()))?;
+        if !channel.status().confirm() {
+            info!("component=RabbitMQClient action=confirm_select reason={reason}");
             channel
-                .basic_publish(&message.exchange, &message.routing_key, BasicPublishOptions::default(), payload, properties)
+                .confirm_select(ConfirmSelectOptions::default())
                 .await
-                .map_err(|e| RabbitMQError::PublishError(format!("Failed to publish message: {e}")))?;
-            Ok(())
-        } else {
-            Err(RabbitMQError::ChannelError("No channel available".to_string()))
+                .map_err(|e| {
+                    RabbitMQError::ChannelError(format!("Failed to enable publisher confirms: {e}"))
+                })?;
         }
+        Ok(())
+    }
+
+    async fn start_publish(
+        &self,
+        message: &RabbitMQMessage,
+        options: BasicPublishOptions,
+        properties: &MessageProperties,
+    ) -> Result<PublisherConfirm, RabbitMQError> {
+        let channel = self
+            .channel
+            .as_ref()
+            .ok_or_else(|| RabbitMQError::ChannelError("No channel available".to_string()))?;
+        start_publish(channel, message, options, properties).await
+    }
+
+    async fn await_confirm(
+        &self,
+        message: &RabbitMQMessage,
+        confirm: PublisherConfirm,
+    ) -> Result<(), RabbitMQError> {
+        await_confirm(
+            &message.exchange,
+            &message.routing_key,
+            confirm,
+            self.return_handler.as_ref(),
+        )
+        .await
+    }
+
+    pub async fn bind_queue(
+        &self,
+        queue_name: &str,
+        exchange_name: &str,
+        routing_key: &str,
+    ) -> Result<(), RabbitMQError> {
+        self.bind_queue_with_arguments(
+            queue_name,
+            exchange_name,
+            routing_key,
+            FieldTable::default(),
+        )
+        .await
     }
 
-    pub async fn bind_queue(&self, queue_name: &str, exchange_name: &str, routing_key: &str) -> Result<(), RabbitMQError> {
+    /// Binds a queue to an exchange with binding arguments, as used by headers exchanges.
+    pub async fn bind_queue_with_arguments(
+        &self,
+        queue_name: &str,
+        exchange_name: &str,
+        routing_key: &str,
+        arguments: FieldTable,
+    ) -> Result<(), RabbitMQError> {
         info!("component=RabbitMQClient action=bind_queue queue={queue_name} exchange={exchange_name} routing_key={routing_key}");
         if let Some(channel) = &self.channel {
             channel
-                .queue_bind(queue_name, exchange_name, routing_key, QueueBindOptions::default(), FieldTable::default())
+                .queue_bind(
+                    queue_name,
+                    exchange_name,
+                    routing_key,
+                    QueueBindOptions::default(),
+                    arguments,
+                )
                 .await
                 .map_err(|e| RabbitMQError::BindingError(format!("Failed to bind queue: {e}")))?;
             Ok(())
         } else {
-            Err(RabbitMQError::ChannelError("No channel available".to_string()))
+            Err(RabbitMQError::ChannelError(
+                "No channel available".to_string(),
+            ))
         }
     }
 
+    /// Removes a binding between a queue and an exchange.
+    pub async fn unbind_queue(
+        &self,
+        queue_name: &str,
+        exchange_name: &str,
+        routing_key: &str,
+        arguments: FieldTable,
+    ) -> Result<(), RabbitMQError> {
+        info!("component=RabbitMQClient action=unbind_queue queue={queue_name} exchange={exchange_name} routing_key={routing_key}");
+        let channel = self.create_channel().await?;
+        let result = channel
+            .queue_unbind(queue_name, exchange_name, routing_key, arguments)
+            .await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(
+                e,
+                TopologyObjectKind::Binding,
+                &format!("{exchange_name} -> {queue_name} ({routing_key})"),
+                |e| RabbitMQError::BindingError(format!("Failed to unbind queue: {e}")),
+            )
+        })
+    }
+
+    /// Binds exchange `destination` to exchange `source`, so that messages routed by
+    /// `source` with a matching routing key are also routed by `destination`.
+    pub async fn bind_exchange(
+        &self,
+        destination: &str,
+        source: &str,
+        routing_key: &str,
+        arguments: FieldTable,
+    ) -> Result<(), RabbitMQError> {
+        info!("component=RabbitMQClient action=bind_exchange destination={destination} source={source} routing_key={routing_key}");
+        let channel = self.create_channel().await?;
+        let result = channel
+            .exchange_bind(
+                destination,
+                source,
+                routing_key,
+                ExchangeBindOptions::default(),
+                arguments,
+            )
+            .await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(
+                e,
+                TopologyObjectKind::Binding,
+                &format!("{source} -> {destination} ({routing_key})"),
+                |e| RabbitMQError::BindingError(format!("Failed to bind exchange: {e}")),
+            )
+        })
+    }
+
+    /// Removes a binding created with `bind_exchange`.
+    pub async fn unbind_exchange(
+        &self,
+        destination: &str,
+        source: &str,
+        routing_key: &str,
+        arguments: FieldTable,
+    ) -> Result<(), RabbitMQError> {
+        info!("component=RabbitMQClient action=unbind_exchange destination={destination} source={source} routing_key={routing_key}");
+        let channel = self.create_channel().await?;
+        let result = channel
+            .exchange_unbind(
+                destination,
+                source,
+                routing_key,
+                ExchangeUnbindOptions::default(),
+                arguments,
+            )
+            .await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(
+                e,
+                TopologyObjectKind::Binding,
+                &format!("{source} -> {destination} ({routing_key})"),
+                |e| RabbitMQError::BindingError(format!("Failed to unbind exchange: {e}")),
+            )
+        })
+    }
+
+    /// Deletes a queue and returns the number of messages it held.
+    ///
+    /// With `if_unused` or `if_empty` set, the broker refuses to delete a queue that
+    /// has consumers or messages, which is reported as `PreconditionFailed`.
+    pub async fn delete_queue(
+        &self,
+        queue_name: &str,
+        if_unused: bool,
+        if_empty: bool,
+    ) -> Result<u32, RabbitMQError> {
+        info!("component=RabbitMQClient action=delete_queue queue={queue_name} if_unused={if_unused} if_empty={if_empty}");
+        let options = QueueDeleteOptions {
+            if_unused,
+            if_empty,
+            ..Default::default()
+        };
+        let channel = self.create_channel().await?;
+        let result = channel.queue_delete(queue_name, options).await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(e, TopologyObjectKind::Queue, queue_name, |e| {
+                RabbitMQError::QueueError(format!("Failed to delete queue: {e}"))
+            })
+        })
+    }
+
+    /// Removes all ready messages from a queue and returns how many were removed.
+    /// Unacknowledged messages are not affected.
+    pub async fn purge_queue(&self, queue_name: &str) -> Result<u32, RabbitMQError> {
+        info!("component=RabbitMQClient action=purge_queue queue={queue_name}");
+        let channel = self.create_channel().await?;
+        let result = channel
+            .queue_purge(queue_name, QueuePurgeOptions::default())
+            .await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(e, TopologyObjectKind::Queue, queue_name, |e| {
+                RabbitMQError::QueueError(format!("Failed to purge queue: {e}"))
+            })
+        })
+    }
+
+    /// Deletes an exchange. With `if_unused` set, the broker refuses to delete an
+    /// exchange that still has bindings, which is reported as `PreconditionFailed`.
+    pub async fn delete_exchange(
+        &self,
+        exchange_name: &str,
+        if_unused: bool,
+    ) -> Result<(), RabbitMQError> {
+        info!("component=RabbitMQClient action=delete_exchange exchange={exchange_name} if_unused={if_unused}");
+        let options = ExchangeDeleteOptions {
+            if_unused,
+            ..Default::default()
+        };
+        let channel = self.create_channel().await?;
+        let result = channel.exchange_delete(exchange_name, options).await;
+        close_channel(channel).await;
+        result.map_err(|e| {
+            topology_error(e, TopologyObjectKind::Exchange, exchange_name, |e| {
+                RabbitMQError::ExchangeError(format!("Failed to delete exchange: {e}"))
+            })
+        })
+    }
+
+    /// Returns the number of ready messages in an existing queue.
+    pub async fn queue_message_count(&self, queue_name: &str) -> Result<u32, RabbitMQError> {
+        let options = QueueDeclareOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let channel = self.create_channel().await?;
+        let result = channel
+            .queue_declare(queue_name, options, FieldTable::default())
+            .await;
+        close_channel(channel).await;
+        let queue = result.map_err(|e| {
+            topology_error(e, TopologyObjectKind::Queue, queue_name, |e| {
+                RabbitMQError::QueueError(format!("Failed to inspect queue: {e}"))
+            })
+        })?;
+        Ok(queue.message_count())
+    }
+
+    /// Opens an additional channel on the client's connection.
+    ///
+    /// Used for operations that may close their channel on failure, such as passive
+    /// declares, so that the client's own channel stays usable.
+    pub(crate) async fn create_channel(&self) -> Result<Channel, RabbitMQError> {
+        let connection = self
+            .connection
+            .as_ref()
+            .ok_or_else(|| RabbitMQError::ConnectionError("No connection available".to_string()))?;
+        connection
+            .create_channel()
+            .await
+            .map_err(|e| RabbitMQError::ChannelError(format!("{e}")))
+    }
+
     /// Consumes a single message from a queue and acknowledges it.
     /// Returns the message payload as a String, or None if no message is received within a short timeout.
     pub async fn consume_one(&self, queue_name: &str) -> Result<Option<String>, RabbitMQError> {
-        info!("component=RabbitMQClient action=consume_one queue={}", queue_name);
+        info!(
+            "component=RabbitMQClient action=consume_one queue={}",
+            queue_name
+        );
+        match self.consume_message(queue_name).await? {
+            Some(message) => {
+                let payload = String::from_utf8(message.payload).map_err(|e| {
+                    RabbitMQError::ConsumeError(format!("Invalid UTF-8 in payload: {}", e))
+                })?;
+                Ok(Some(payload))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Consumes a single message from a queue and acknowledges it, keeping its
+    /// properties and headers.
+    /// Returns None if no message is received within a short timeout.
+    pub async fn consume_message(
+        &self,
+        queue_name: &str,
+    ) -> Result<Option<RabbitMQMessage>, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=consume_message queue={}",
+            queue_name
+        );
         if let Some(channel) = &self.channel {
             let mut consumer = channel
                 .basic_consume(
@@ -172,26 +630,219 @@ impl RabbitMQClient {
                     FieldTable::default(),
                 )
                 .await
-                .map_err(|e| RabbitMQError::ConsumeError(format!("Failed to start consumer: {}", e)))?;
+                .map_err(|e| {
+                    RabbitMQError::ConsumeError(format!("Failed to start consumer: {}", e))
+                })?;
 
             // Use a timeout to avoid waiting forever if the queue is empty
             match timeout(Duration::from_secs(2), consumer.next()).await {
                 Ok(Some(Ok(delivery))) => {
-                    let payload = String::from_utf8(delivery.data.clone())
-                        .map_err(|e| RabbitMQError::ConsumeError(format!("Invalid UTF-8 in payload: {}", e)))?;
-                    
                     delivery
                         .ack(BasicAckOptions::default())
                         .await
-                        .map_err(|e| RabbitMQError::AckError(format!("Failed to ack message: {}", e)))?;
-                    
-                    Ok(Some(payload))
+                        .map_err(|e| {
+                            RabbitMQError::AckError(format!("Failed to ack message: {}", e))
+                        })?;
+
+                    Ok(Some(RabbitMQMessage {
+                        exchange: delivery.exchange.to_string(),
+                        routing_key: delivery.routing_key.to_string(),
+                        properties: Some(MessageProperties::from(&delivery.properties)),
+                        payload: delivery.data,
+                    }))
                 }
-                Ok(Some(Err(e))) => Err(RabbitMQError::ConsumeError(format!("Error receiving message: {}", e))),
+                Ok(Some(Err(e))) => Err(RabbitMQError::ConsumeError(format!(
+                    "Error receiving message: {}",
+                    e
+                ))),
                 Ok(None) | Err(_) => Ok(None), // Timeout or stream ended
             }
         } else {
-            Err(RabbitMQError::ChannelError("No channel available".to_string()))
+            Err(RabbitMQError::ChannelError(
+                "No channel available".to_string(),
+            ))
         }
     }
 }
+
+/// Publishes `messages` on `channel`, which must be in confirm mode, and waits for
+/// all of their confirms.
+///
+/// At most `max_outstanding` publishes are left unconfirmed at any time, 0 meaning
+/// no limit. Returned messages go to `return_handler` if there is one. Nacked
+/// messages are reported by index once the whole batch has been confirmed.
+pub async fn publish_confirmed_batch(
+    channel: &Channel,
+    messages: &[RabbitMQMessage],
+    max_outstanding: u32,
+    return_handler: Option<&ReturnHandler>,
+) -> Result<(), RabbitMQError> {
+    let max_outstanding = match max_outstanding {
+        0 => usize::MAX,
+        n => n as usize,
+    };
+    let mut outstanding: VecDeque<(usize, PublisherConfirm)> = VecDeque::new();
+    let mut nacked = Vec::new();
+
+    for (index, message) in messages.iter().enumerate() {
+        if outstanding.len() >= max_outstanding {
+            if let Some((done, confirm)) = outstanding.pop_front() {
+                collect_confirm(&messages[done], done, confirm, return_handler, &mut nacked)
+                    .await?;
+            }
+        }
+        let properties = message.properties.clone().unwrap_or_default();
+        outstanding.push_back((
+            index,
+            start_publish(
+                channel,
+                message,
+                BasicPublishOptions::default(),
+                &properties,
+            )
+            .await?,
+        ));
+    }
+    while let Some((done, confirm)) = outstanding.pop_front() {
+        collect_confirm(&messages[done], done, confirm, return_handler, &mut nacked).await?;
+    }
+
+    if nacked.is_empty() {
+        Ok(())
+    } else {
+        Err(RabbitMQError::BatchNacked {
+            nacked,
+            total: messages.len(),
+        })
+    }
+}
+
+/// Waits for a publisher confirm, turning a nack into `RabbitMQError::PublishNacked`
+/// and a returned message into a `return_handler` call or `RabbitMQError::Unroutable`.
+///
+/// Without confirm-select mode the confirm resolves immediately.
+pub async fn await_confirm(
+    exchange: &str,
+    routing_key: &str,
+    confirm: PublisherConfirm,
+    return_handler: Option<&ReturnHandler>,
+) -> Result<(), RabbitMQError> {
+    let confirmation = confirm.await.map_err(|e| {
+        RabbitMQError::PublishError(format!("Failed to receive publisher confirm: {e}"))
+    })?;
+    match (confirmation, return_handler) {
+        (Confirmation::Ack(Some(returned)), Some(handler)) => {
+            let returned = ReturnedMessage::from(*returned);
+            warn!(
+                "component=RabbitMQClient action=publish_message exchange={} routing_key={} result=returned reply_text={}",
+                returned.exchange, returned.routing_key, returned.reply_text
+            );
+            handler(returned);
+            Ok(())
+        }
+        (confirmation, _) => confirmation_result(exchange, routing_key, confirmation).inspect_err(|e| {
+            warn!("component=RabbitMQClient action=publish_message exchange={exchange} routing_key={routing_key} error={e}")
+        }),
+    }
+}
+
+async fn start_publish(
+    channel: &Channel,
+    message: &RabbitMQMessage,
+    options: BasicPublishOptions,
+    properties: &MessageProperties,
+) -> Result<PublisherConfirm, RabbitMQError> {
+    channel
+        .basic_publish(
+            &message.exchange,
+            &message.routing_key,
+            options,
+            &message.payload,
+            properties.to_basic_properties(),
+        )
+        .await
+        .map_err(|e| RabbitMQError::PublishError(format!("Failed to publish message: {e}")))
+}
+
+async fn collect_confirm(
+    message: &RabbitMQMessage,
+    index: usize,
+    confirm: PublisherConfirm,
+    return_handler: Option<&ReturnHandler>,
+    nacked: &mut Vec<usize>,
+) -> Result<(), RabbitMQError> {
+    match await_confirm(
+        &message.exchange,
+        &message.routing_key,
+        confirm,
+        return_handler,
+    )
+    .await
+    {
+        Err(RabbitMQError::PublishNacked { .. }) => {
+            nacked.push(index);
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+/// The `ExchangeKind` of an exchange type name as it appears in a configuration.
+pub fn exchange_kind(kind: &str) -> Result<ExchangeKind, RabbitMQError> {
+    match kind {
+        "direct" => Ok(ExchangeKind::Direct),
+        "fanout" => Ok(ExchangeKind::Fanout),
+        "topic" => Ok(ExchangeKind::Topic),
+        "headers" => Ok(ExchangeKind::Headers),
+        _ => Err(RabbitMQError::ExchangeError(format!(
+            "Invalid exchange type: {kind}"
+        ))),
+    }
+}
+
+/// Maps the broker's soft errors for topology operations to typed errors, falling
+/// back to `fallback` for everything else.
+fn topology_error(
+    error: lapin::Error,
+    object: TopologyObjectKind,
+    name: &str,
+    fallback: impl FnOnce(lapin::Error) -> RabbitMQError,
+) -> RabbitMQError {
+    if let lapin::Error::ProtocolError(e) = &error {
+        let reason = e.get_message().to_string();
+        let name = name.to_string();
+        match e.kind() {
+            AMQPErrorKind::Soft(AMQPSoftError::NOTFOUND) => {
+                return RabbitMQError::NotFound {
+                    object,
+                    name,
+                    reason,
+                }
+            }
+            AMQPErrorKind::Soft(AMQPSoftError::PRECONDITIONFAILED) => {
+                return RabbitMQError::PreconditionFailed {
+                    object,
+                    name,
+                    reason,
+                }
+            }
+            AMQPErrorKind::Soft(AMQPSoftError::ACCESSREFUSED)
+            | AMQPErrorKind::Soft(AMQPSoftError::RESOURCELOCKED) => {
+                return RabbitMQError::AccessRefused {
+                    object,
+                    name,
+                    reason,
+                }
+            }
+            _ => {}
+        }
+    }
+    fallback(error)
+}
+
+/// Closes a short-lived channel unless the broker already closed it after an error.
+async fn close_channel(channel: Channel) {
+    if channel.status().connected() {
+        let _ = channel.close(200, "Done").await;
+    }
+}
@@ -1,7 +1,15 @@
 // rabbitmq-config/src/config.rs
+use lapin::types::{AMQPValue, FieldArray, FieldTable, LongString};
+use lapin::ConnectionProperties;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    get_password, Endpoint, EndpointOrder, EndpointPool, ExchangeInfo, QueueInfo, RabbitMQError,
+};
 
 /// A flattened, simple config struct for use by client applications.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +20,15 @@ pub struct RabbitMQConfig {
     pub username: String,
     pub password: String,
     pub vhost: String,
+    /// The AMQP nodes of a cluster, e.g. `["rabbit1:5672", "rabbit2:5672"]`.
+    /// Empty means `host:amqp_port` only.
+    #[serde(default)]
+    pub amqp_endpoints: Vec<Endpoint>,
+    /// The management API nodes of a cluster. Empty means `host:management_port` only.
+    #[serde(default)]
+    pub management_endpoints: Vec<Endpoint>,
+    #[serde(default)]
+    pub endpoint_order: EndpointOrder,
 }
 
 impl Default for RabbitMQConfig {
@@ -23,13 +40,54 @@ impl Default for RabbitMQConfig {
             username: "guest".to_string(),
             password: "guest".to_string(),
             vhost: "/".to_string(),
+            amqp_endpoints: Vec::new(),
+            management_endpoints: Vec::new(),
+            endpoint_order: EndpointOrder::default(),
         }
     }
 }
 
 impl RabbitMQConfig {
+    /// The AMQP endpoints to fail over between, in `endpoint_order`.
+    pub fn amqp_pool(&self) -> EndpointPool {
+        let endpoints = if self.amqp_endpoints.is_empty() {
+            vec![Endpoint::new(self.host.clone(), self.amqp_port)]
+        } else {
+            self.amqp_endpoints.clone()
+        };
+        EndpointPool::new(endpoints, self.endpoint_order)
+    }
+
+    /// The management API endpoints to fail over between, in `endpoint_order`.
+    pub fn management_pool(&self) -> EndpointPool {
+        let endpoints = if self.management_endpoints.is_empty() {
+            vec![Endpoint::new(self.host.clone(), self.management_port)]
+        } else {
+            self.management_endpoints.clone()
+        };
+        EndpointPool::new(endpoints, self.endpoint_order)
+    }
+
+    /// This configuration with `endpoint` as its AMQP host and port.
+    pub fn for_amqp_endpoint(&self, endpoint: &Endpoint) -> RabbitMQConfig {
+        RabbitMQConfig {
+            host: endpoint.host.clone(),
+            amqp_port: endpoint.port,
+            ..self.clone()
+        }
+    }
+
     /// Converts the configuration into a valid AMQP URI string.
     pub fn to_uri(&self) -> String {
+        self.uri_with_scheme("amqp")
+    }
+
+    /// Like `to_uri`, but for a TLS connection (`amqps://`).
+    pub fn to_amqps_uri(&self) -> String {
+        self.uri_with_scheme("amqps")
+    }
+
+    fn uri_with_scheme(&self, scheme: &str) -> String {
         let vhost_encoded = if self.vhost.is_empty() || self.vhost == "/" {
             "/".to_string()
         } else {
@@ -37,8 +95,8 @@ impl RabbitMQConfig {
         };
 
         format!(
-            "amqp://{}:{}@{}:{}{}",
-            self.username, self.password, self.host, self.amqp_port, vhost_encoded
+            "{}://{}:{}@{}:{}{}",
+            scheme, self.username, self.password, self.host, self.amqp_port, vhost_encoded
         )
     }
 }
@@ -82,7 +140,6 @@ impl Default for RabbitMQFullConfig {
     }
 }
 
-
 // --- Configuration Sub-structs ---
 
 /// Logging configuration
@@ -108,12 +165,31 @@ pub struct ConnectionConfig {
     pub vhost: String,
     pub username: String,
     pub password: Option<String>,
+    /// See `RabbitMQConfig::amqp_endpoints`.
     #[serde(default)]
-    pub connection_timeout_ms: u32,
+    pub amqp_endpoints: Vec<Endpoint>,
+    /// See `RabbitMQConfig::management_endpoints`.
     #[serde(default)]
-    pub heartbeat_interval_sec: u32,
+    pub management_endpoints: Vec<Endpoint>,
     #[serde(default)]
+    pub endpoint_order: EndpointOrder,
+    /// Time allowed for the TCP connect and AMQP handshake; 0 waits indefinitely.
+    ///
+    /// Defaults to 10 seconds when omitted. Older configurations that omitted it got
+    /// 0, which was never applied; set 0 explicitly to keep waiting indefinitely.
+    #[serde(default = "default_connection_timeout_ms")]
+    pub connection_timeout_ms: u32,
+    /// Heartbeat interval proposed to the broker; 0 disables heartbeats.
+    ///
+    /// Defaults to 60 seconds when omitted, which used to mean 0 but was never applied.
+    #[serde(default = "default_heartbeat_interval_sec")]
+    pub heartbeat_interval_sec: u32,
+    /// Shown as the connection's name in the management UI.
+    #[serde(default = "default_connection_name")]
     pub connection_name: String,
+    /// Extra client properties sent to the broker, e.g. `product` or `team`.
+    #[serde(default)]
+    pub client_properties: HashMap<String, serde_json::Value>,
     #[serde(default)]
     pub use_tls: bool,
     #[serde(default)]
@@ -129,15 +205,75 @@ impl Default for ConnectionConfig {
             vhost: "/".to_string(),
             username: "guest".to_string(),
             password: None,
-            connection_timeout_ms: 10000,
-            heartbeat_interval_sec: 60,
-            connection_name: "rabbitmq_app".to_string(),
+            amqp_endpoints: Vec::new(),
+            management_endpoints: Vec::new(),
+            endpoint_order: EndpointOrder::default(),
+            connection_timeout_ms: default_connection_timeout_ms(),
+            heartbeat_interval_sec: default_heartbeat_interval_sec(),
+            connection_name: default_connection_name(),
+            client_properties: HashMap::new(),
             use_tls: false,
             tls_options: None,
         }
     }
 }
 
+fn default_connection_timeout_ms() -> u32 {
+    10000
+}
+
+fn default_heartbeat_interval_sec() -> u32 {
+    60
+}
+
+fn default_connection_name() -> String {
+    "rabbitmq_app".to_string()
+}
+
+impl ConnectionConfig {
+    /// Builds the flat `RabbitMQConfig` used by the clients.
+    ///
+    /// Without a password in the file, it is read from `RABBITMQ_PASSWORD` or
+    /// prompted for, see `get_password`.
+    pub fn to_rabbitmq_config(&self) -> Result<RabbitMQConfig, RabbitMQError> {
+        let password = match &self.password {
+            Some(password) => password.clone(),
+            None => get_password()?,
+        };
+        Ok(RabbitMQConfig {
+            host: self.host.clone(),
+            amqp_port: self.amqp_port,
+            management_port: self.management_port,
+            username: self.username.clone(),
+            password,
+            vhost: self.vhost.clone(),
+            amqp_endpoints: self.amqp_endpoints.clone(),
+            management_endpoints: self.management_endpoints.clone(),
+            endpoint_order: self.endpoint_order,
+        })
+    }
+
+    /// The client properties sent when opening a connection, including `connection_name`.
+    pub fn connection_properties(&self) -> ConnectionProperties {
+        let properties = ConnectionProperties {
+            client_properties: arguments_to_field_table(&self.client_properties),
+            ..Default::default()
+        };
+        if self.connection_name.is_empty() {
+            properties
+        } else {
+            properties.with_connection_name(self.connection_name.as_str().into())
+        }
+    }
+
+    /// The connection timeout, or `None` to wait indefinitely.
+    pub fn connection_timeout(&self) -> Option<Duration> {
+        match self.connection_timeout_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms.into())),
+        }
+    }
+}
 
 /// TLS configuration options
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -154,10 +290,14 @@ pub struct TlsOptions {
 pub struct ChannelConfig {
     #[serde(default)]
     pub default_prefetch_count: u16,
+    /// Not supported: RabbitMQ does not implement prefetch by size, so this is ignored.
     #[serde(default)]
     pub default_prefetch_size: u32,
     #[serde(default)]
     pub confirm_deliveries: bool,
+    /// Maximum number of unconfirmed publishes in flight during a batch (0 = unlimited).
+    #[serde(default)]
+    pub max_outstanding_confirms: u32,
 }
 
 /// Exchange configuration
@@ -200,6 +340,122 @@ pub struct BindingConfig {
     pub arguments: HashMap<String, serde_json::Value>,
 }
 
+impl From<&ExchangeConfig> for ExchangeInfo {
+    fn from(exchange: &ExchangeConfig) -> Self {
+        Self {
+            name: exchange.name.clone(),
+            kind: exchange.exchange_type.clone(),
+            durable: exchange.durable,
+            auto_delete: exchange.auto_delete,
+            internal: exchange.internal,
+            arguments: arguments_to_field_table(&exchange.arguments),
+        }
+    }
+}
+
+impl From<&QueueConfig> for QueueInfo {
+    fn from(queue: &QueueConfig) -> Self {
+        Self {
+            name: queue.name.clone(),
+            durable: queue.durable,
+            exclusive: queue.exclusive,
+            auto_delete: queue.auto_delete,
+            arguments: arguments_to_field_table(&queue.arguments),
+        }
+    }
+}
+
+/// Converts a JSON `arguments` map into an AMQP field table.
+///
+/// Integers become 32-bit `long-int` values when they fit and 64-bit `long-long-int`
+/// values otherwise, which is what RabbitMQ expects for `x-message-ttl`,
+/// `x-max-length`, `x-max-priority` and friends. Strings become `long-string`.
+pub fn arguments_to_field_table(arguments: &HashMap<String, serde_json::Value>) -> FieldTable {
+    let mut table = FieldTable::default();
+    for (key, value) in arguments {
+        table.insert(key.as_str().into(), json_to_amqp_value(value));
+    }
+    table
+}
+
+/// Converts an AMQP field table back into a JSON `arguments` map.
+pub fn field_table_to_arguments(table: &FieldTable) -> HashMap<String, serde_json::Value> {
+    table
+        .inner()
+        .iter()
+        .map(|(key, value)| (key.to_string(), amqp_value_to_json(value)))
+        .collect()
+}
+
+fn amqp_value_to_json(value: &AMQPValue) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        AMQPValue::Boolean(b) => Value::from(*b),
+        AMQPValue::ShortShortInt(i) => Value::from(*i),
+        AMQPValue::ShortShortUInt(i) => Value::from(*i),
+        AMQPValue::ShortInt(i) => Value::from(*i),
+        AMQPValue::ShortUInt(i) => Value::from(*i),
+        AMQPValue::LongInt(i) => Value::from(*i),
+        AMQPValue::LongUInt(i) => Value::from(*i),
+        AMQPValue::LongLongInt(i) => Value::from(*i),
+        AMQPValue::Timestamp(t) => Value::from(*t),
+        AMQPValue::Float(f) => Value::from(*f),
+        AMQPValue::Double(d) => Value::from(*d),
+        AMQPValue::DecimalValue(d) => {
+            Value::from(f64::from(d.value) / 10f64.powi(i32::from(d.scale)))
+        }
+        AMQPValue::ShortString(s) => Value::from(s.as_str()),
+        AMQPValue::LongString(s) => Value::from(String::from_utf8_lossy(s.as_bytes()).into_owned()),
+        AMQPValue::ByteArray(bytes) => Value::from(bytes.as_slice().to_vec()),
+        AMQPValue::FieldArray(array) => {
+            Value::Array(array.as_slice().iter().map(amqp_value_to_json).collect())
+        }
+        AMQPValue::FieldTable(table) => Value::Object(
+            table
+                .inner()
+                .iter()
+                .map(|(key, value)| (key.to_string(), amqp_value_to_json(value)))
+                .collect(),
+        ),
+        AMQPValue::Void => Value::Null,
+    }
+}
+
+fn json_to_amqp_value(value: &serde_json::Value) -> AMQPValue {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => AMQPValue::Void,
+        Value::Bool(b) => AMQPValue::Boolean(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                match i32::try_from(i) {
+                    Ok(i) => AMQPValue::LongInt(i),
+                    Err(_) => AMQPValue::LongLongInt(i),
+                }
+            } else {
+                AMQPValue::Double(n.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(s) => AMQPValue::LongString(LongString::from(s.as_str())),
+        Value::Array(items) => {
+            let mut array = FieldArray::default();
+            for item in items {
+                array.push(json_to_amqp_value(item));
+            }
+            AMQPValue::FieldArray(array)
+        }
+        Value::Object(map) => {
+            let mut table = FieldTable::default();
+            for (key, value) in map {
+                table.insert(key.as_str().into(), json_to_amqp_value(value));
+            }
+            AMQPValue::FieldTable(table)
+        }
+    }
+}
+
 /// Consumer configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ConsumerConfig {
@@ -215,6 +471,10 @@ pub struct ConsumerConfig {
     pub prefetch_count: u16,
     #[serde(default)]
     pub arguments: HashMap<String, serde_json::Value>,
+    /// Retries failed messages through delay queues and dead-letters them once
+    /// `max_retries` is exhausted. See `RetryPolicy` in messaging_commands.
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
 }
 
 /// Publisher configuration
@@ -261,3 +521,35 @@ pub struct RetryConfig {
     pub max_interval_ms: u32,
     pub randomization_factor: f64,
 }
+
+impl RetryConfig {
+    /// Computes the delay to wait before the given retry attempt (0-based).
+    ///
+    /// The delay grows by `multiplier` per attempt, is capped at `max_interval_ms`
+    /// (when non-zero) and is spread by `randomization_factor` to avoid thundering herds.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let mut interval = self.interval_ms(attempt);
+        let factor = self.randomization_factor.clamp(0.0, 1.0);
+        if factor > 0.0 {
+            interval *= 1.0 + rand::thread_rng().gen_range(-factor..=factor);
+        }
+
+        Duration::from_millis(interval.max(0.0) as u64)
+    }
+
+    /// The delay before the given retry attempt (0-based) without randomization.
+    pub fn interval(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.interval_ms(attempt) as u64)
+    }
+
+    fn interval_ms(&self, attempt: u32) -> f64 {
+        let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+        let interval =
+            f64::from(self.initial_interval_ms) * self.multiplier.max(1.0).powi(exponent);
+        if self.max_interval_ms > 0 {
+            interval.min(f64::from(self.max_interval_ms))
+        } else {
+            interval
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// rabbitmq-config/src/connection.rs
+
+//! Opening AMQP connections with the settings of a `ConnectionConfig`.
+
+use lapin::uri::AMQPUri;
+use lapin::Connection;
+use log::{info, warn};
+use tokio::time::timeout;
+
+use crate::tls::connect_amqps_uri;
+use crate::{ConnectionConfig, Endpoint, EndpointPool, RabbitMQConfig, RabbitMQError};
+
+/// Opens a connection to the broker described by `config`, failing over between
+/// its `amqp_endpoints` as described for `open_failover_connection`.
+pub async fn open_connection(
+    config: &RabbitMQConfig,
+    settings: &ConnectionConfig,
+) -> Result<Connection, RabbitMQError> {
+    let (connection, _) = open_failover_connection(config, settings, &config.amqp_pool()).await?;
+    Ok(connection)
+}
+
+/// Tries the endpoints of `pool` in turn and returns the first connection together
+/// with the endpoint it was opened to.
+///
+/// Failed endpoints are recorded in the pool, so they are tried last next time.
+/// When all endpoints fail, the error lists each of them with its failure; a single
+/// endpoint reports its own error unchanged.
+pub async fn open_failover_connection(
+    config: &RabbitMQConfig,
+    settings: &ConnectionConfig,
+    pool: &EndpointPool,
+) -> Result<(Connection, Endpoint), RabbitMQError> {
+    let candidates = pool.candidates();
+    if candidates.is_empty() {
+        return Err(RabbitMQError::ConfigError(
+            "No AMQP endpoints configured".to_string(),
+        ));
+    }
+    let mut failures = Vec::new();
+    for endpoint in &candidates {
+        match connect_endpoint(&config.for_amqp_endpoint(endpoint), settings).await {
+            Ok(connection) => {
+                pool.mark_connected(endpoint);
+                return Ok((connection, endpoint.clone()));
+            }
+            Err(e) => {
+                warn!("component=connection action=failover endpoint={endpoint} error={e}");
+                pool.mark_failed(endpoint);
+                failures.push((endpoint, e));
+            }
+        }
+    }
+
+    if failures.len() == 1 {
+        return Err(failures.remove(0).1);
+    }
+    let details: Vec<String> = failures
+        .iter()
+        .map(|(endpoint, e)| format!("{endpoint} ({e})"))
+        .collect();
+    Err(RabbitMQError::ConnectionError(format!(
+        "All {} endpoints failed: {}",
+        candidates.len(),
+        details.join("; ")
+    )))
+}
+
+/// Opens a connection to `host:amqp_port` of `config`.
+///
+/// `settings` provides the connection timeout, the heartbeat interval proposed to
+/// the broker, the connection name and client properties, and whether to use TLS.
+/// Host, credentials and vhost are taken from `config`.
+async fn connect_endpoint(
+    config: &RabbitMQConfig,
+    settings: &ConnectionConfig,
+) -> Result<Connection, RabbitMQError> {
+    let uri = if settings.use_tls {
+        config.to_amqps_uri()
+    } else {
+        config.to_uri()
+    };
+    let mut uri: AMQPUri = uri
+        .parse()
+        .map_err(|e| RabbitMQError::ConfigError(format!("Invalid AMQP URI: {e}")))?;
+    uri.query.heartbeat = Some(u16::try_from(settings.heartbeat_interval_sec).unwrap_or(u16::MAX));
+    if settings.connection_timeout_ms > 0 {
+        uri.query.connection_timeout = Some(settings.connection_timeout_ms.into());
+    }
+    info!(
+        "component=connection action=open host={} port={} tls={} heartbeat={} name={}",
+        config.host,
+        config.amqp_port,
+        settings.use_tls,
+        settings.heartbeat_interval_sec,
+        settings.connection_name
+    );
+
+    let properties = settings.connection_properties();
+    let connect = async {
+        if settings.use_tls {
+            connect_amqps_uri(uri, settings.tls_options.as_ref(), properties).await
+        } else {
+            Connection::connect_uri(uri, properties)
+                .await
+                .map_err(|e| RabbitMQError::ConnectionError(format!("{e}")))
+        }
+    };
+
+    match settings.connection_timeout() {
+        Some(limit) => timeout(limit, connect).await.map_err(|_| {
+            RabbitMQError::TimeoutError(format!("Connection timed out after {limit:?}"))
+        })?,
+        None => connect.await,
+    }
+}
@@ -0,0 +1,494 @@
+// rabbitmq-config/src/dlq.rs
+
+use std::time::Duration;
+
+use lapin::{
+    options::{BasicAckOptions, BasicGetOptions, BasicPublishOptions, ConfirmSelectOptions},
+    types::{AMQPValue, FieldTable, ShortString},
+    Channel,
+};
+use log::{info, warn};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::{
+    amqp_integer, amqp_string, confirmation_result, MessageProperties, PeekOptions, RabbitMQClient,
+    RabbitMQError, RabbitMQMessage, ORIGINAL_EXCHANGE_HEADER, ORIGINAL_ROUTING_KEY_HEADER,
+    RETRY_COUNT_HEADER,
+};
+
+/// Header the broker adds to dead-lettered messages, one entry per queue and reason.
+pub const X_DEATH_HEADER: &str = "x-death";
+
+/// One entry of a message's `x-death` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeathRecord {
+    /// Why the message was dead-lettered: `rejected`, `expired`, `maxlen` or `delivery_limit`.
+    pub reason: String,
+    /// The queue the message was dead-lettered from.
+    pub queue: String,
+    /// The exchange the message was published to before it was dead-lettered.
+    pub exchange: String,
+    pub routing_keys: Vec<String>,
+    /// How many times the message was dead-lettered from this queue for this reason.
+    pub count: u64,
+    /// When it was last dead-lettered, in seconds since the Unix epoch.
+    pub time: Option<u64>,
+}
+
+/// Decodes the `x-death` header of a message, most recent death first.
+pub fn decode_x_death(headers: &FieldTable) -> Vec<DeathRecord> {
+    let Some(AMQPValue::FieldArray(entries)) = headers.inner().get(X_DEATH_HEADER) else {
+        return Vec::new();
+    };
+    entries
+        .as_slice()
+        .iter()
+        .filter_map(|entry| match entry {
+            AMQPValue::FieldTable(table) => Some(death_record(table)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn death_record(table: &FieldTable) -> DeathRecord {
+    let fields = table.inner();
+    let text = |name: &str| fields.get(name).and_then(amqp_string).unwrap_or_default();
+    let routing_keys = match fields.get("routing-keys") {
+        Some(AMQPValue::FieldArray(keys)) => {
+            keys.as_slice().iter().filter_map(amqp_string).collect()
+        }
+        _ => Vec::new(),
+    };
+    let time = match fields.get("time") {
+        Some(AMQPValue::Timestamp(time)) => Some(*time),
+        other => other.and_then(amqp_integer),
+    };
+    DeathRecord {
+        reason: text("reason"),
+        queue: text("queue"),
+        exchange: text("exchange"),
+        routing_keys,
+        count: fields
+            .get("count")
+            .and_then(amqp_integer)
+            .unwrap_or_default(),
+        time,
+    }
+}
+
+/// A message in a dead letter queue.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// Position in the queue when it was read, counting from 0 at the head.
+    pub position: usize,
+    pub message: RabbitMQMessage,
+    /// The decoded `x-death` header, most recent death first.
+    pub deaths: Vec<DeathRecord>,
+}
+
+impl DeadLetter {
+    pub fn new(position: usize, message: RabbitMQMessage) -> Self {
+        let deaths = headers(&message).map(decode_x_death).unwrap_or_default();
+        Self {
+            position,
+            message,
+            deaths,
+        }
+    }
+
+    /// The most recent death of the message.
+    pub fn last_death(&self) -> Option<&DeathRecord> {
+        self.deaths.first()
+    }
+
+    /// The exchange and routing key the message was originally published with.
+    ///
+    /// Taken from the `x-original-exchange` and `x-original-routing-key` headers set
+    /// by consumers that retry or dead-letter messages themselves, otherwise from the
+    /// oldest `x-death` entry. The headers come first because the oldest death of a
+    /// retried message is a delay queue, which would never deliver it again.
+    pub fn original_target(&self) -> Option<(String, String)> {
+        if let Some(headers) = headers(&self.message).map(FieldTable::inner) {
+            if let Some(exchange) = headers.get(ORIGINAL_EXCHANGE_HEADER).and_then(amqp_string) {
+                let routing_key = headers
+                    .get(ORIGINAL_ROUTING_KEY_HEADER)
+                    .and_then(amqp_string)
+                    .unwrap_or_default();
+                return Some((exchange, routing_key));
+            }
+        }
+        let first = self.deaths.last()?;
+        let routing_key = first.routing_keys.first().cloned().unwrap_or_default();
+        Some((first.exchange.clone(), routing_key))
+    }
+}
+
+fn headers(message: &RabbitMQMessage) -> Option<&FieldTable> {
+    message.properties.as_ref()?.headers.as_ref()
+}
+
+/// Selects dead letters by their `x-death` entries.
+///
+/// A message matches when one of its entries satisfies every criterion that is set.
+/// Without criteria every message matches, including those without `x-death`.
+#[derive(Debug, Clone, Default)]
+pub struct DeadLetterFilter {
+    pub reason: Option<String>,
+    /// The queue the message was dead-lettered from.
+    pub queue: Option<String>,
+    /// Minimum number of times the message was dead-lettered from that queue.
+    pub min_count: Option<u64>,
+    /// Dead-lettered at or after this time, in seconds since the Unix epoch.
+    pub since: Option<u64>,
+    /// Dead-lettered at or before this time, in seconds since the Unix epoch.
+    pub until: Option<u64>,
+}
+
+impl DeadLetterFilter {
+    pub fn is_empty(&self) -> bool {
+        self.reason.is_none()
+            && self.queue.is_none()
+            && self.min_count.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+
+    pub fn matches(&self, dead_letter: &DeadLetter) -> bool {
+        self.is_empty()
+            || dead_letter
+                .deaths
+                .iter()
+                .any(|death| self.matches_death(death))
+    }
+
+    fn matches_death(&self, death: &DeathRecord) -> bool {
+        self.reason
+            .as_ref()
+            .is_none_or(|reason| &death.reason == reason)
+            && self
+                .queue
+                .as_ref()
+                .is_none_or(|queue| &death.queue == queue)
+            && self.min_count.is_none_or(|count| death.count >= count)
+            && self
+                .since
+                .is_none_or(|since| death.time.is_some_and(|time| time >= since))
+            && self
+                .until
+                .is_none_or(|until| death.time.is_some_and(|time| time <= until))
+    }
+}
+
+/// Where `replay_dead_letters` publishes messages instead of their original target.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayTarget {
+    pub exchange: String,
+    /// Keeps each message's original routing key when `None`.
+    pub routing_key: Option<String>,
+}
+
+/// Which dead letters `replay_dead_letters` replays, where to and how fast.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    pub filter: DeadLetterFilter,
+    /// Positions, as reported by `browse_dead_letters`, to replay. `None` replays
+    /// every message matching the filter.
+    pub positions: Option<Vec<usize>>,
+    /// Publish here instead of to each message's original exchange and routing key.
+    pub target: Option<ReplayTarget>,
+    /// Maximum number of messages replayed per second.
+    pub rate_limit: Option<f64>,
+    /// Report what would be replayed without publishing or removing anything.
+    pub dry_run: bool,
+}
+
+impl ReplayOptions {
+    fn selects(&self, dead_letter: &DeadLetter) -> bool {
+        self.positions
+            .as_ref()
+            .is_none_or(|positions| positions.contains(&dead_letter.position))
+            && self.filter.matches(dead_letter)
+    }
+
+    fn target_for(&self, dead_letter: &DeadLetter) -> Option<(String, String)> {
+        let Some(target) = &self.target else {
+            return dead_letter.original_target();
+        };
+        let routing_key = match &target.routing_key {
+            Some(routing_key) => routing_key.clone(),
+            None => dead_letter
+                .original_target()
+                .map(|(_, routing_key)| routing_key)
+                .unwrap_or_else(|| dead_letter.message.routing_key.clone()),
+        };
+        Some((target.exchange.clone(), routing_key))
+    }
+}
+
+/// What happened to a selected dead letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayStatus {
+    /// Published and confirmed, then removed from the dead letter queue.
+    Replayed,
+    /// Would have been replayed; nothing was changed.
+    DryRun,
+    /// Left in the dead letter queue.
+    Failed(String),
+}
+
+/// The outcome for one selected dead letter.
+#[derive(Debug, Clone)]
+pub struct ReplayResult {
+    pub position: usize,
+    /// The exchange and routing key it was, or would have been, published to.
+    pub target: Option<(String, String)>,
+    pub status: ReplayStatus,
+}
+
+/// Results of `replay_dead_letters` for the selected messages, in queue order.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// How many messages were read from the queue.
+    pub scanned: usize,
+    pub results: Vec<ReplayResult>,
+    /// Why the replay stopped before the end of the queue, e.g. because a publish to
+    /// a missing exchange made the broker close the channel. Messages not reached yet
+    /// were left in the queue.
+    pub stopped: Option<String>,
+}
+
+impl ReplayReport {
+    pub fn replayed(&self) -> usize {
+        self.count(|status| *status == ReplayStatus::Replayed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, ReplayStatus::Failed(_)))
+    }
+
+    fn count(&self, predicate: impl Fn(&ReplayStatus) -> bool) -> usize {
+        self.results
+            .iter()
+            .filter(|result| predicate(&result.status))
+            .count()
+    }
+}
+
+impl RabbitMQClient {
+    /// Reads up to `limit` messages from the head of a dead letter queue without removing them.
+    ///
+    /// The messages are read with `peek_messages`, so they return to the queue in
+    /// their original order and are marked as redelivered.
+    pub async fn browse_dead_letters(
+        &self,
+        queue: &str,
+        limit: usize,
+    ) -> Result<Vec<DeadLetter>, RabbitMQError> {
+        info!("component=RabbitMQClient action=browse_dead_letters queue={queue} limit={limit}");
+        let peeked = self.peek_messages(queue, &PeekOptions::new(limit)).await?;
+        Ok(peeked
+            .into_iter()
+            .map(|peeked| DeadLetter::new(peeked.position, peeked.message))
+            .collect())
+    }
+
+    /// Republishes messages from a dead letter queue and removes each one once the
+    /// broker has confirmed its publish.
+    ///
+    /// Only the messages in the queue when the replay starts are considered, so
+    /// messages dead-lettered again while replaying are not picked up twice. Messages
+    /// that are not selected, fail to publish or are only part of a dry run stay in
+    /// the queue in their original order. Positions refer to the queue as it is now,
+    /// so they only match an earlier `browse_dead_letters` if the queue's head has not
+    /// changed since.
+    ///
+    /// If the broker closes the channel part way, e.g. because a target exchange does
+    /// not exist, the replay stops and the report so far is returned with
+    /// `ReplayReport::stopped` set.
+    pub async fn replay_dead_letters(
+        &self,
+        queue: &str,
+        options: &ReplayOptions,
+    ) -> Result<ReplayReport, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=replay_dead_letters queue={queue} dry_run={} rate_limit={:?}",
+            options.dry_run, options.rate_limit
+        );
+        let channel = self.create_channel().await?;
+        let result = replay(&channel, queue, options).await;
+        close_browse_channel(channel).await;
+        result
+    }
+}
+
+async fn replay(
+    channel: &Channel,
+    queue: &str,
+    options: &ReplayOptions,
+) -> Result<ReplayReport, RabbitMQError> {
+    if !options.dry_run {
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(|e| {
+                RabbitMQError::ChannelError(format!("Failed to enable publisher confirms: {e}"))
+            })?;
+    }
+    let interval = options
+        .rate_limit
+        .filter(|rate| *rate > 0.0)
+        .map(|rate| Duration::from_secs_f64(1.0 / rate));
+    let mut next_publish = Instant::now();
+    let mut report = ReplayReport::default();
+    // The queue length when the first message was fetched. Messages beyond it were
+    // dead-lettered after the replay started.
+    let mut snapshot = None;
+
+    while snapshot.is_none_or(|total| report.scanned < total) {
+        if !channel.status().connected() {
+            report.stopped = Some("The broker closed the channel".to_string());
+            break;
+        }
+        let next = match get_next(channel, queue, report.scanned).await {
+            Ok(next) => next,
+            // Nothing has been read yet, so there is no partial report to return.
+            Err(e) if report.scanned == 0 => return Err(e),
+            Err(e) => {
+                report.stopped = Some(e.to_string());
+                break;
+            }
+        };
+        let Some((dead_letter, delivery_tag, queued)) = next else {
+            break;
+        };
+        snapshot.get_or_insert(queued + 1);
+        report.scanned += 1;
+
+        if !options.selects(&dead_letter) {
+            continue;
+        }
+        let target = options.target_for(&dead_letter);
+        let status = match &target {
+            None => {
+                ReplayStatus::Failed("No original exchange recorded; specify a target".to_string())
+            }
+            Some(_) if options.dry_run => ReplayStatus::DryRun,
+            Some((exchange, routing_key)) => {
+                if let Some(interval) = interval {
+                    tokio::time::sleep_until(next_publish).await;
+                    next_publish = Instant::now() + interval;
+                }
+                match republish(channel, exchange, routing_key, &dead_letter.message).await {
+                    Ok(()) => match channel
+                        .basic_ack(delivery_tag, BasicAckOptions::default())
+                        .await
+                    {
+                        Ok(()) => ReplayStatus::Replayed,
+                        Err(e) => {
+                            warn!("component=RabbitMQClient action=replay_dead_letters queue={queue} position={} error={e}", dead_letter.position);
+                            ReplayStatus::Failed(format!(
+                                "Published, but could not be removed from the queue: {e}"
+                            ))
+                        }
+                    },
+                    Err(e) => {
+                        warn!("component=RabbitMQClient action=replay_dead_letters queue={queue} position={} error={e}", dead_letter.position);
+                        ReplayStatus::Failed(e.to_string())
+                    }
+                }
+            }
+        };
+        report.results.push(ReplayResult {
+            position: dead_letter.position,
+            target,
+            status,
+        });
+    }
+
+    info!(
+        "component=RabbitMQClient action=replay_dead_letters queue={queue} scanned={} replayed={} failed={} stopped={:?}",
+        report.scanned,
+        report.replayed(),
+        report.failed(),
+        report.stopped
+    );
+    Ok(report)
+}
+
+/// Fetches the next message unacknowledged, returning it with its delivery tag and
+/// the number of messages left ready in the queue.
+async fn get_next(
+    channel: &Channel,
+    queue: &str,
+    position: usize,
+) -> Result<Option<(DeadLetter, u64, usize)>, RabbitMQError> {
+    let message = channel
+        .basic_get(queue, BasicGetOptions { no_ack: false })
+        .await
+        .map_err(|e| {
+            RabbitMQError::ConsumeError(format!("Failed to get message from '{queue}': {e}"))
+        })?;
+    Ok(message.map(|message| {
+        let queued = message.message_count as usize;
+        let delivery = message.delivery;
+        let dead_letter = DeadLetter::new(
+            position,
+            RabbitMQMessage {
+                exchange: delivery.exchange.to_string(),
+                routing_key: delivery.routing_key.to_string(),
+                properties: Some(MessageProperties::from(&delivery.properties)),
+                payload: delivery.data,
+            },
+        );
+        (dead_letter, delivery.delivery_tag, queued)
+    }))
+}
+
+async fn republish(
+    channel: &Channel,
+    exchange: &str,
+    routing_key: &str,
+    message: &RabbitMQMessage,
+) -> Result<(), RabbitMQError> {
+    let mut properties = message.properties.clone().unwrap_or_default();
+    // Replayed messages start with a fresh retry budget.
+    if let Some(headers) = properties.headers.take() {
+        let retry_count = ShortString::from(RETRY_COUNT_HEADER);
+        let mut kept = FieldTable::default();
+        for (key, value) in headers
+            .inner()
+            .iter()
+            .filter(|(key, _)| **key != retry_count)
+        {
+            kept.insert(key.clone(), value.clone());
+        }
+        properties.headers = Some(kept);
+    }
+
+    let options = BasicPublishOptions {
+        mandatory: true,
+        ..Default::default()
+    };
+    let confirm = channel
+        .basic_publish(
+            exchange,
+            routing_key,
+            options,
+            &message.payload,
+            properties.to_basic_properties(),
+        )
+        .await
+        .map_err(|e| RabbitMQError::PublishError(format!("Failed to publish message: {e}")))?;
+    let confirmation = confirm
+        .await
+        .map_err(|e| RabbitMQError::PublishError(format!("Failed to confirm message: {e}")))?;
+    confirmation_result(exchange, routing_key, confirmation)
+}
+
+/// Closing the channel returns every message it still holds unacknowledged to the queue.
+async fn close_browse_channel(channel: Channel) {
+    if channel.status().connected() {
+        let _ = channel.close(200, "Done").await;
+    }
+}
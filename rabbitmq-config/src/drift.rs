@@ -0,0 +1,495 @@
+// rabbitmq-config/src/drift.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::apply::binding_name;
+use crate::config::{field_table_to_arguments, BindingConfig, ExchangeConfig, QueueConfig};
+use crate::{
+    MessageTypeCatalog, RabbitMQError, RabbitMQFullConfig, RabbitMQServerDefinition,
+    TopologyObjectKind,
+};
+
+/// The exchanges, queues and bindings of a single vhost.
+///
+/// Deserializing ignores every other key, so any config file with `exchanges`,
+/// `queues` and `bindings` sections can be read as a topology.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Topology {
+    #[serde(default)]
+    pub exchanges: Vec<ExchangeConfig>,
+    #[serde(default)]
+    pub queues: Vec<QueueConfig>,
+    #[serde(default)]
+    pub bindings: Vec<BindingConfig>,
+}
+
+impl From<&RabbitMQFullConfig> for Topology {
+    fn from(config: &RabbitMQFullConfig) -> Self {
+        Self {
+            exchanges: config.exchanges.clone(),
+            queues: config.queues.clone(),
+            bindings: config.bindings.clone(),
+        }
+    }
+}
+
+impl From<&MessageTypeCatalog> for Topology {
+    /// One durable topic exchange per category and one queue per message type,
+    /// bound with the message type name as routing key.
+    fn from(catalog: &MessageTypeCatalog) -> Self {
+        let mut topology = Topology::default();
+        for category in &catalog.message_types {
+            topology.exchanges.push(ExchangeConfig {
+                name: category.category.clone(),
+                exchange_type: "topic".to_string(),
+                durable: true,
+                ..Default::default()
+            });
+            for message_type in &category.types {
+                let mut arguments = HashMap::new();
+                if message_type.priority > 0 {
+                    arguments.insert(
+                        "x-max-priority".to_string(),
+                        Value::from(message_type.priority),
+                    );
+                }
+                topology.queues.push(QueueConfig {
+                    name: message_type.name.clone(),
+                    durable: message_type.durable,
+                    arguments,
+                    ..Default::default()
+                });
+                topology.bindings.push(BindingConfig {
+                    exchange: category.category.clone(),
+                    queue: message_type.name.clone(),
+                    routing_key: message_type.name.clone(),
+                    arguments: HashMap::new(),
+                });
+            }
+        }
+        topology
+    }
+}
+
+impl Topology {
+    /// Reads a topology from a TOML or JSON config file, or from a `message_types.json`
+    /// catalog, which is recognised by its `message_types` key.
+    pub fn load(path: &Path) -> Result<Self, RabbitMQError> {
+        let content = fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return Ok(toml::from_str(&content)?);
+        }
+
+        let invalid = |e: serde_json::Error| {
+            RabbitMQError::ConfigError(format!("Invalid topology in {}: {e}", path.display()))
+        };
+        let value: Value = serde_json::from_str(&content).map_err(invalid)?;
+        if value.get("message_types").is_some() {
+            let catalog: MessageTypeCatalog = serde_json::from_value(value).map_err(invalid)?;
+            Ok(Topology::from(&catalog))
+        } else {
+            serde_json::from_value(value).map_err(invalid)
+        }
+    }
+
+    /// Extracts the topology of `vhost` from a server definitions export.
+    ///
+    /// Only queue bindings are kept; exchange-to-exchange bindings are not modelled.
+    pub fn from_definitions(definitions: &RabbitMQServerDefinition, vhost: &str) -> Self {
+        Self {
+            exchanges: definitions
+                .exchanges
+                .iter()
+                .filter(|exchange| exchange.vhost == vhost)
+                .map(|exchange| ExchangeConfig {
+                    name: exchange.name.clone(),
+                    exchange_type: exchange.r#type.clone(),
+                    durable: exchange.durable,
+                    auto_delete: exchange.auto_delete,
+                    internal: exchange.internal,
+                    arguments: field_table_to_arguments(&exchange.arguments),
+                })
+                .collect(),
+            queues: definitions
+                .queues
+                .iter()
+                .filter(|queue| queue.vhost == vhost)
+                .map(|queue| QueueConfig {
+                    name: queue.name.clone(),
+                    durable: queue.durable,
+                    // Exclusive queues belong to a connection and are never exported.
+                    exclusive: false,
+                    auto_delete: queue.auto_delete,
+                    arguments: field_table_to_arguments(&queue.arguments),
+                })
+                .collect(),
+            bindings: definitions
+                .bindings
+                .iter()
+                .filter(|binding| binding.vhost == vhost && binding.destination_type == "queue")
+                .map(|binding| BindingConfig {
+                    exchange: binding.source.clone(),
+                    queue: binding.destination.clone(),
+                    routing_key: binding.routing_key.clone(),
+                    arguments: field_table_to_arguments(&binding.arguments),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How a live object differs from the desired topology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftKind {
+    /// Desired but absent on the broker.
+    Missing,
+    /// Present on the broker but not desired.
+    Extra,
+    /// Present on both sides with different settings.
+    Mismatched,
+}
+
+impl fmt::Display for DriftKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriftKind::Missing => write!(f, "missing"),
+            DriftKind::Extra => write!(f, "extra"),
+            DriftKind::Mismatched => write!(f, "mismatched"),
+        }
+    }
+}
+
+/// A single setting that differs. Arguments are reported as `arguments.<name>`,
+/// with `null` standing for an absent argument.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDrift {
+    pub field: String,
+    pub desired: Value,
+    pub live: Value,
+}
+
+/// One drifted exchange, queue or binding.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Drift {
+    pub object: TopologyObjectKind,
+    pub name: String,
+    pub kind: DriftKind,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldDrift>,
+}
+
+/// The result of `diff_topology`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    pub drifts: Vec<Drift>,
+}
+
+impl DriftReport {
+    pub fn is_clean(&self) -> bool {
+        self.drifts.is_empty()
+    }
+
+    /// Renders the report as a plain-text table, one line per drifted setting.
+    pub fn to_table(&self) -> String {
+        if self.is_clean() {
+            return "No topology drift detected.\n".to_string();
+        }
+
+        let mut rows: Vec<[String; 4]> = Vec::new();
+        for drift in &self.drifts {
+            let mut details: Vec<String> = drift
+                .fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{}: desired {}, live {}",
+                        field.field, field.desired, field.live
+                    )
+                })
+                .collect();
+            if details.is_empty() {
+                details.push(String::new());
+            }
+            for detail in details {
+                rows.push([
+                    drift.object.to_string(),
+                    drift.name.clone(),
+                    drift.kind.to_string(),
+                    detail,
+                ]);
+            }
+        }
+
+        let headers = ["KIND", "NAME", "DRIFT", "DETAILS"];
+        let mut widths = headers.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut table = String::new();
+        let mut push_row = |cells: [&str; 4]| {
+            let line = format!(
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                cells[0],
+                cells[1],
+                cells[2],
+                cells[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            );
+            table.push_str(line.trim_end());
+            table.push('\n');
+        };
+        push_row(headers);
+        for row in &rows {
+            push_row([&row[0], &row[1], &row[2], &row[3]]);
+        }
+        table
+    }
+}
+
+/// Compares a desired topology with the live one.
+///
+/// Objects RabbitMQ creates on its own — the default exchange, `amq.*` exchanges,
+/// server-named queues and default-exchange bindings — are never reported as extra.
+pub fn diff_topology(desired: &Topology, live: &Topology) -> DriftReport {
+    let mut report = DriftReport::default();
+
+    for exchange in &desired.exchanges {
+        match live
+            .exchanges
+            .iter()
+            .find(|live| live.name == exchange.name)
+        {
+            Some(live) => {
+                let settings = vec![
+                    (
+                        "type",
+                        Value::from(exchange.exchange_type.as_str()),
+                        Value::from(live.exchange_type.as_str()),
+                    ),
+                    (
+                        "durable",
+                        Value::from(exchange.durable),
+                        Value::from(live.durable),
+                    ),
+                    (
+                        "auto_delete",
+                        Value::from(exchange.auto_delete),
+                        Value::from(live.auto_delete),
+                    ),
+                    (
+                        "internal",
+                        Value::from(exchange.internal),
+                        Value::from(live.internal),
+                    ),
+                ];
+                report.compare(
+                    TopologyObjectKind::Exchange,
+                    &exchange.name,
+                    settings,
+                    &exchange.arguments,
+                    &live.arguments,
+                );
+            }
+            None => report.push(
+                TopologyObjectKind::Exchange,
+                &exchange.name,
+                DriftKind::Missing,
+            ),
+        }
+    }
+    for exchange in &live.exchanges {
+        let builtin = exchange.name.is_empty() || exchange.name.starts_with("amq.");
+        if !builtin
+            && !desired
+                .exchanges
+                .iter()
+                .any(|desired| desired.name == exchange.name)
+        {
+            report.push(
+                TopologyObjectKind::Exchange,
+                &exchange.name,
+                DriftKind::Extra,
+            );
+        }
+    }
+
+    for queue in &desired.queues {
+        match live.queues.iter().find(|live| live.name == queue.name) {
+            Some(live) => {
+                let settings = vec![
+                    (
+                        "durable",
+                        Value::from(queue.durable),
+                        Value::from(live.durable),
+                    ),
+                    (
+                        "exclusive",
+                        Value::from(queue.exclusive),
+                        Value::from(live.exclusive),
+                    ),
+                    (
+                        "auto_delete",
+                        Value::from(queue.auto_delete),
+                        Value::from(live.auto_delete),
+                    ),
+                ];
+                report.compare(
+                    TopologyObjectKind::Queue,
+                    &queue.name,
+                    settings,
+                    &queue.arguments,
+                    &live.arguments,
+                );
+            }
+            None => report.push(TopologyObjectKind::Queue, &queue.name, DriftKind::Missing),
+        }
+    }
+    for queue in &live.queues {
+        let builtin = queue.name.starts_with("amq.");
+        if !builtin
+            && !desired
+                .queues
+                .iter()
+                .any(|desired| desired.name == queue.name)
+        {
+            report.push(TopologyObjectKind::Queue, &queue.name, DriftKind::Extra);
+        }
+    }
+
+    // Bindings have no settings of their own; arguments are part of their identity.
+    for binding in &desired.bindings {
+        if !live.bindings.iter().any(|live| same_binding(binding, live)) {
+            report.push(
+                TopologyObjectKind::Binding,
+                &binding_name(binding),
+                DriftKind::Missing,
+            );
+        }
+    }
+    for binding in &live.bindings {
+        let builtin = binding.exchange.is_empty();
+        if !builtin
+            && !desired
+                .bindings
+                .iter()
+                .any(|desired| same_binding(desired, binding))
+        {
+            report.push(
+                TopologyObjectKind::Binding,
+                &binding_name(binding),
+                DriftKind::Extra,
+            );
+        }
+    }
+
+    report
+}
+
+impl DriftReport {
+    fn push(&mut self, object: TopologyObjectKind, name: &str, kind: DriftKind) {
+        self.drifts.push(Drift {
+            object,
+            name: name.to_string(),
+            kind,
+            fields: Vec::new(),
+        });
+    }
+
+    fn compare(
+        &mut self,
+        object: TopologyObjectKind,
+        name: &str,
+        settings: Vec<(&str, Value, Value)>,
+        desired_arguments: &HashMap<String, Value>,
+        live_arguments: &HashMap<String, Value>,
+    ) {
+        let mut fields: Vec<FieldDrift> = settings
+            .into_iter()
+            .filter(|(_, desired, live)| desired != live)
+            .map(|(field, desired, live)| FieldDrift {
+                field: field.to_string(),
+                desired,
+                live,
+            })
+            .collect();
+
+        let mut arguments: Vec<&String> = desired_arguments
+            .keys()
+            .chain(live_arguments.keys())
+            .collect();
+        arguments.sort();
+        arguments.dedup();
+        for argument in arguments {
+            let desired = desired_arguments
+                .get(argument)
+                .cloned()
+                .unwrap_or(Value::Null);
+            let live = live_arguments.get(argument).cloned().unwrap_or(Value::Null);
+            if normalize_argument(&desired) != normalize_argument(&live) {
+                fields.push(FieldDrift {
+                    field: format!("arguments.{argument}"),
+                    desired,
+                    live,
+                });
+            }
+        }
+
+        if !fields.is_empty() {
+            self.drifts.push(Drift {
+                object,
+                name: name.to_string(),
+                kind: DriftKind::Mismatched,
+                fields,
+            });
+        }
+    }
+}
+
+pub(crate) fn same_binding(a: &BindingConfig, b: &BindingConfig) -> bool {
+    a.exchange == b.exchange
+        && a.queue == b.queue
+        && a.routing_key == b.routing_key
+        && same_arguments(&a.arguments, &b.arguments)
+}
+
+fn same_arguments(a: &HashMap<String, Value>, b: &HashMap<String, Value>) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(name, value)| {
+            b.get(name)
+                .is_some_and(|other| normalize_argument(value) == normalize_argument(other))
+        })
+}
+
+/// Writes whole floats such as `60000.0` as integers, so that a value from a config
+/// file compares equal to the integer the broker reports for it.
+fn normalize_argument(value: &Value) -> Value {
+    match value {
+        Value::Number(number) => match number.as_f64() {
+            Some(float)
+                if number.is_f64() && float.fract() == 0.0 && float.abs() < i64::MAX as f64 =>
+            {
+                Value::from(float as i64)
+            }
+            _ => value.clone(),
+        },
+        Value::Array(values) => Value::Array(values.iter().map(normalize_argument).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), normalize_argument(value)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
@@ -0,0 +1,154 @@
+// rabbitmq-config/src/endpoints.rs
+
+//! Cluster endpoints and the failover order in which clients try them.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::RabbitMQError;
+
+/// The address of one broker node, written as `host:port`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Endpoint {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = RabbitMQError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = s.rsplit_once(':').ok_or_else(|| {
+            RabbitMQError::ConfigError(format!("Endpoint '{s}' must be written as host:port"))
+        })?;
+        let port = port
+            .parse()
+            .map_err(|_| RabbitMQError::ConfigError(format!("Invalid port in endpoint '{s}'")))?;
+        if host.is_empty() {
+            return Err(RabbitMQError::ConfigError(format!(
+                "Missing host in endpoint '{s}'"
+            )));
+        }
+        Ok(Self::new(host, port))
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = RabbitMQError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+/// The order in which the endpoints of a cluster are tried.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointOrder {
+    /// In the configured order, so the first endpoint is preferred.
+    #[default]
+    Ordered,
+    /// In a new random order for every connection attempt, spreading clients over the nodes.
+    Shuffled,
+}
+
+/// The endpoints of a cluster with the outcome of earlier connection attempts.
+///
+/// Clones share their state, so a node that failed for one connection is tried
+/// last by the next one until a connection to it succeeds again.
+#[derive(Debug, Clone)]
+pub struct EndpointPool {
+    order: EndpointOrder,
+    state: Arc<Mutex<PoolState>>,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    endpoints: Vec<Endpoint>,
+    /// Failed endpoints, longest-failed first.
+    failed: Vec<Endpoint>,
+    current: Option<Endpoint>,
+}
+
+impl EndpointPool {
+    pub fn new(endpoints: Vec<Endpoint>, order: EndpointOrder) -> Self {
+        Self {
+            order,
+            state: Arc::new(Mutex::new(PoolState {
+                endpoints,
+                failed: Vec::new(),
+                current: None,
+            })),
+        }
+    }
+
+    /// The endpoints in the order to try them: endpoints that have not failed first,
+    /// in the pool's order, then the failed ones, the longest-failed first.
+    pub fn candidates(&self) -> Vec<Endpoint> {
+        let state = self.state.lock().unwrap();
+        let mut healthy: Vec<Endpoint> = state
+            .endpoints
+            .iter()
+            .filter(|endpoint| !state.failed.contains(endpoint))
+            .cloned()
+            .collect();
+        if self.order == EndpointOrder::Shuffled {
+            healthy.shuffle(&mut rand::thread_rng());
+        }
+        healthy.extend(state.failed.iter().cloned());
+        healthy
+    }
+
+    /// Records a failed attempt; the endpoint is no longer the one in use.
+    pub fn mark_failed(&self, endpoint: &Endpoint) {
+        let mut state = self.state.lock().unwrap();
+        state.failed.retain(|failed| failed != endpoint);
+        state.failed.push(endpoint.clone());
+        if state.current.as_ref() == Some(endpoint) {
+            state.current = None;
+        }
+    }
+
+    /// Records a successful connection to `endpoint`, which is now the one in use.
+    pub fn mark_connected(&self, endpoint: &Endpoint) {
+        let mut state = self.state.lock().unwrap();
+        state.failed.retain(|failed| failed != endpoint);
+        state.current = Some(endpoint.clone());
+    }
+
+    /// The endpoint of the last successful connection.
+    pub fn current(&self) -> Option<Endpoint> {
+        self.state.lock().unwrap().current.clone()
+    }
+
+    /// Endpoints whose last attempt failed, the longest-failed first.
+    pub fn failed(&self) -> Vec<Endpoint> {
+        self.state.lock().unwrap().failed.clone()
+    }
+}
@@ -1,7 +1,10 @@
 // rabbitmq-config/src/error.rs
 
+use lapin::publisher_confirm::Confirmation;
 use thiserror::Error;
 
+use crate::{ReturnedMessage, TopologyObjectKind};
+
 #[derive(Error, Debug)]
 pub enum RabbitMQError {
     #[error("Configuration error: {0}")]
@@ -25,12 +28,58 @@ pub enum RabbitMQError {
     #[error("Publish error: {0}")]
     PublishError(String),
 
+    #[error("Publish to exchange '{exchange}' with routing key '{routing_key}' was nacked by the broker")]
+    PublishNacked {
+        exchange: String,
+        routing_key: String,
+    },
+
+    #[error("Message to exchange '{exchange}' with routing key '{routing_key}' was returned as unroutable: {reply_text}")]
+    Unroutable {
+        exchange: String,
+        routing_key: String,
+        reply_text: String,
+    },
+
+    #[error("{} of {total} batched publishes were nacked by the broker", nacked.len())]
+    BatchNacked { nacked: Vec<usize>, total: usize },
+
+    #[error("Refusing destructive topology changes without explicit permission: {0}")]
+    DestructiveChange(String),
+
+    #[error("Refusing to delete queue '{queue}' holding {messages} message(s)")]
+    QueueNotEmpty { queue: String, messages: u32 },
+
+    #[error("{object} '{name}' not found: {reason}")]
+    NotFound {
+        object: TopologyObjectKind,
+        name: String,
+        reason: String,
+    },
+
+    #[error("Precondition failed for {object} '{name}': {reason}")]
+    PreconditionFailed {
+        object: TopologyObjectKind,
+        name: String,
+        reason: String,
+    },
+
+    #[error("Access refused to {object} '{name}': {reason}")]
+    AccessRefused {
+        object: TopologyObjectKind,
+        name: String,
+        reason: String,
+    },
+
     #[error("Consume error: {0}")]
     ConsumeError(String),
 
     #[error("Ack error: {0}")]
     AckError(String),
 
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
     #[error("Timeout error: {0}")]
     TimeoutError(String),
 
@@ -40,3 +89,29 @@ pub enum RabbitMQError {
     #[error("TOML deserialization error: {0}")]
     TomlError(#[from] toml::de::Error),
 }
+
+/// Turns the publisher confirm of a publish to `exchange` with `routing_key` into a
+/// result: a nack becomes `PublishNacked` and a returned message `Unroutable`.
+///
+/// Without confirm-select mode every confirm is `NotRequested` and counts as success.
+pub fn confirmation_result(
+    exchange: &str,
+    routing_key: &str,
+    confirmation: Confirmation,
+) -> Result<(), RabbitMQError> {
+    match confirmation {
+        Confirmation::Ack(None) | Confirmation::NotRequested => Ok(()),
+        Confirmation::Nack(_) => Err(RabbitMQError::PublishNacked {
+            exchange: exchange.to_string(),
+            routing_key: routing_key.to_string(),
+        }),
+        Confirmation::Ack(Some(returned)) => {
+            let returned = ReturnedMessage::from(*returned);
+            Err(RabbitMQError::Unroutable {
+                exchange: returned.exchange,
+                routing_key: returned.routing_key,
+                reply_text: returned.reply_text,
+            })
+        }
+    }
+}
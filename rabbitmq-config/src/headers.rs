@@ -0,0 +1,37 @@
+// rabbitmq-config/src/headers.rs
+
+use lapin::types::AMQPValue;
+
+/// Header counting how many times a consumer has retried a message.
+pub const RETRY_COUNT_HEADER: &str = "x-retry-count";
+/// Header recording the exchange a message was first published to, set by consumers
+/// that retry or dead-letter messages themselves instead of through the broker.
+pub const ORIGINAL_EXCHANGE_HEADER: &str = "x-original-exchange";
+/// Header recording the routing key a message was first published with.
+pub const ORIGINAL_ROUTING_KEY_HEADER: &str = "x-original-routing-key";
+/// Header with the reason the last attempt to handle a message failed.
+pub const LAST_ERROR_HEADER: &str = "x-last-error";
+
+/// Reads a header value as text, for both short and long strings.
+pub fn amqp_string(value: &AMQPValue) -> Option<String> {
+    match value {
+        AMQPValue::LongString(s) => Some(s.to_string()),
+        AMQPValue::ShortString(s) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads a header value as a non-negative integer, whatever integer type it was sent as.
+pub fn amqp_integer(value: &AMQPValue) -> Option<u64> {
+    let value = match value {
+        AMQPValue::ShortShortUInt(n) => i64::from(*n),
+        AMQPValue::ShortUInt(n) => i64::from(*n),
+        AMQPValue::LongUInt(n) => i64::from(*n),
+        AMQPValue::ShortShortInt(n) => i64::from(*n),
+        AMQPValue::ShortInt(n) => i64::from(*n),
+        AMQPValue::LongInt(n) => i64::from(*n),
+        AMQPValue::LongLongInt(n) => *n,
+        _ => return None,
+    };
+    u64::try_from(value).ok()
+}
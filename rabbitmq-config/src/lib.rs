@@ -1,39 +1,71 @@
 // rabbitmq-config/src/lib.rs
 
 use std::env;
-use std::fs;
-use std::path::PathBuf;
 
 // Module declarations
+mod apply;
 mod client;
 mod config;
+mod connection;
+mod dlq;
+mod drift;
+mod endpoints;
 mod error;
+mod headers;
+mod loader;
 mod models;
+mod peek;
+mod plan;
+mod shovel;
+mod tls;
 mod topology;
 
 // Re-export the models needed by the UI
-pub use client::RabbitMQClient;
-pub use config::{ConnectionConfig, RabbitMQConfig, RabbitMQFullConfig};
-pub use error::RabbitMQError;
+pub use apply::{ApplyReport, ApplyResult, ApplyStatus, TopologyObjectKind};
+pub use client::{
+    await_confirm, exchange_kind, publish_confirmed_batch, RabbitMQClient, ReturnHandler,
+};
+pub use config::{
+    arguments_to_field_table, field_table_to_arguments, BindingConfig, ChannelConfig,
+    ConnectionConfig, ConsumerConfig, ExchangeConfig, PublisherConfig, QueueConfig, RabbitMQConfig,
+    RabbitMQFullConfig, RetryConfig, TlsOptions,
+};
+pub use connection::{open_connection, open_failover_connection};
+pub use dlq::{
+    decode_x_death, DeadLetter, DeadLetterFilter, DeathRecord, ReplayOptions, ReplayReport,
+    ReplayResult, ReplayStatus, ReplayTarget, X_DEATH_HEADER,
+};
+pub use drift::{diff_topology, Drift, DriftKind, DriftReport, FieldDrift, Topology};
+pub use endpoints::{Endpoint, EndpointOrder, EndpointPool};
+pub use error::{confirmation_result, RabbitMQError};
+pub use headers::{
+    amqp_integer, amqp_string, LAST_ERROR_HEADER, ORIGINAL_EXCHANGE_HEADER,
+    ORIGINAL_ROUTING_KEY_HEADER, RETRY_COUNT_HEADER,
+};
+#[cfg(feature = "cli")]
+pub use loader::ConfigArgs;
+pub use loader::{
+    discover_config_file, parse_override, ConfigLoader, ConfigSource, LoadedConfig, ENV_PREFIX,
+};
 pub use models::{
-    BindingDefinition, ExchangeDefinition, ExchangeInfo, GlobalParameterDefinition, MessageProperties,
+    BindingDefinition, ExchangeDefinition, ExchangeInfo, GlobalParameterDefinition,
+    MessageProperties, MessageTypeCatalog, MessageTypeCategory, MessageTypeDefinition,
     PermissionDefinition, QueueDefinition, QueueInfo, RabbitMQMessage, RabbitMQServerDefinition,
-    TopicPermissionDefinition, UserDefinition, VhostDefinition,
+    ReturnedMessage, TopicPermissionDefinition, UserDefinition, VhostDefinition,
 };
+pub use peek::{PeekOptions, PeekedMessage, StreamOffset, X_STREAM_OFFSET};
+pub use plan::{plan_topology, PlanOptions, PlannedOperation, TopologyOperation, TopologyPlan};
+pub use shovel::{ShovelOptions, ShovelProgress, ShovelReport, ShovelStop, ShovelTarget};
+pub use tls::{connect_amqps, tls_client_config};
 
-/// Loads and parses the `rabbitmq-mon.toml` file to get non-sensitive connection info.
+/// Loads the configuration with `ConfigLoader` from the discovered configuration
+/// file and `RABBITMQ_*` environment variables, falling back to the defaults.
+///
+/// This used to read only `~/.config/rabbitmq-mon/rabbitmq-mon.toml`. It now looks
+/// at `$RABBITMQ_CONFIG` and `./rabbitmq.*` first, applies the profile named by
+/// `RABBITMQ_PROFILE` and lets `RABBITMQ_*` variables override values from the file.
 pub fn load_config_file() -> Result<RabbitMQFullConfig, RabbitMQError> {
-    let config_dir = dirs::config_dir().ok_or_else(|| RabbitMQError::ConfigError("Could not determine config directory.".to_string()))?;
-    let config_path: PathBuf = [config_dir.to_str().unwrap(), "rabbitmq-mon", "rabbitmq-mon.toml"].iter().collect();
-
-    if config_path.exists() {
-        log::info!("Loading configuration from: {:?}", config_path);
-        let toml_str = fs::read_to_string(&config_path)?;
-        toml::from_str(&toml_str).map_err(RabbitMQError::from)
-    } else {
-        log::warn!("Configuration file not found at: {:?}. Using defaults.", config_path);
-        Ok(RabbitMQFullConfig::default())
-    }
+    ConfigLoader::new().load().map(|loaded| loaded.config)
 }
 
 /// Gets the RabbitMQ password, preferring the `RABBITMQ_PASSWORD` environment variable.
@@ -0,0 +1,511 @@
+// rabbitmq-config/src/loader.rs
+
+//! Layered configuration loading.
+//!
+//! Values are merged from, in increasing precedence: the built-in defaults, a
+//! configuration file, the file's selected profile, `RABBITMQ_*` environment
+//! variables and command-line overrides. The source of every value is recorded.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "cli")]
+use clap::Args;
+use log::{debug, info, warn};
+use serde_json::{Map, Value};
+
+use crate::{RabbitMQError, RabbitMQFullConfig, RetryConfig, TlsOptions};
+
+/// Prefix of the environment variables read by `ConfigLoader`.
+pub const ENV_PREFIX: &str = "RABBITMQ_";
+
+/// Environment variable naming the configuration file.
+const CONFIG_ENV: &str = "RABBITMQ_CONFIG";
+
+/// Environment variable naming the profile, like `--profile`.
+const PROFILE_ENV: &str = "RABBITMQ_PROFILE";
+
+const FILE_EXTENSIONS: [&str; 4] = ["toml", "json", "yaml", "yml"];
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    /// The configuration file, or the named profile within it.
+    File {
+        path: PathBuf,
+        profile: Option<String>,
+    },
+    /// The named environment variable.
+    Env(String),
+    /// A command-line override of the given key.
+    Cli(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File {
+                path,
+                profile: None,
+            } => write!(f, "file {}", path.display()),
+            ConfigSource::File {
+                path,
+                profile: Some(profile),
+            } => {
+                write!(f, "file {} (profile {profile})", path.display())
+            }
+            ConfigSource::Env(name) => write!(f, "env {name}"),
+            ConfigSource::Cli(key) => write!(f, "cli --set {key}"),
+        }
+    }
+}
+
+/// Merges defaults, a configuration file, environment variables and overrides into a
+/// `RabbitMQFullConfig`.
+///
+/// The file is TOML, JSON or YAML, chosen by its extension. Without an explicit path
+/// it is `$RABBITMQ_CONFIG`, else the first of `./rabbitmq.{toml,json,yaml,yml}` and
+/// `<config dir>/rabbitmq-mon/rabbitmq-mon.{toml,json,yaml,yml}` that exists.
+///
+/// A file may define profiles, which are applied on top of the rest of the file:
+///
+/// ```toml
+/// [connection]
+/// host = "localhost"
+///
+/// [profiles.prod.connection]
+/// host = "rabbit.prod.example.com"
+/// use_tls = true
+/// ```
+///
+/// Environment variables are named after the key they set: `RABBITMQ_HOST` sets
+/// `connection.host`, and `__` separates nested keys, so
+/// `RABBITMQ_TLS_OPTIONS__CA_CERT_PATH` sets `connection.tls_options.ca_cert_path` and
+/// `RABBITMQ_CHANNEL__DEFAULT_PREFETCH_COUNT` sets `channel.default_prefetch_count`.
+/// Lists are comma-separated. Overrides use the dotted key, where `connection.` may
+/// also be left out.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    file: Option<PathBuf>,
+    profile: Option<String>,
+    overrides: Vec<(String, String)>,
+    env: Option<Vec<(String, String)>>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads this file instead of discovering one. It must exist.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Applies the named profile of the configuration file.
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Overrides a value, e.g. `set("connection.host", "rabbit2")`.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Reads these variables instead of the process environment.
+    pub fn env_vars<K: Into<String>, V: Into<String>>(
+        mut self,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.env = Some(
+            vars.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Merges all layers and returns the configuration with the source of each value.
+    pub fn load(&self) -> Result<LoadedConfig, RabbitMQError> {
+        let mut env: Vec<(String, String)> = match &self.env {
+            Some(vars) => vars.clone(),
+            None => std::env::vars().collect(),
+        };
+        env.retain(|(name, _)| name.starts_with(ENV_PREFIX));
+        env.sort();
+        let env_value = |name: &str| {
+            env.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let file = match &self.file {
+            Some(path) => Some(path.clone()),
+            None => env_value(CONFIG_ENV)
+                .map(PathBuf::from)
+                .or_else(discover_config_file),
+        };
+        let profile = self.profile.clone().or_else(|| env_value(PROFILE_ENV));
+
+        let schema = schema()?;
+        let mut layers = Layers::new(to_value(&RabbitMQFullConfig::default())?);
+
+        match &file {
+            Some(path) => {
+                info!("component=loader action=load_file path={:?}", path);
+                let mut contents = read_config_file(path)?;
+                let profiles = contents.remove("profiles");
+                layers.merge(
+                    Value::Object(contents),
+                    &ConfigSource::File {
+                        path: path.clone(),
+                        profile: None,
+                    },
+                );
+
+                if let Some(name) = &profile {
+                    let selected = profiles
+                        .as_ref()
+                        .and_then(|profiles| profiles.get(name))
+                        .cloned()
+                        .ok_or_else(|| {
+                            let available: Vec<&str> = profiles
+                                .as_ref()
+                                .and_then(Value::as_object)
+                                .map(|profiles| profiles.keys().map(String::as_str).collect())
+                                .unwrap_or_default();
+                            RabbitMQError::ConfigError(format!(
+                                "Profile '{name}' not found in {} (available: {})",
+                                path.display(),
+                                available.join(", ")
+                            ))
+                        })?;
+                    let source = ConfigSource::File {
+                        path: path.clone(),
+                        profile: Some(name.clone()),
+                    };
+                    layers.merge(selected, &source);
+                }
+            }
+            None => {
+                if let Some(name) = &profile {
+                    return Err(RabbitMQError::ConfigError(format!(
+                        "Profile '{name}' was requested but no configuration file was found"
+                    )));
+                }
+                warn!("component=loader action=load_file result=not_found using=defaults");
+            }
+        }
+
+        for (name, raw) in &env {
+            if name == CONFIG_ENV || name == PROFILE_ENV {
+                continue;
+            }
+            let key = name[ENV_PREFIX.len()..].to_lowercase().replace("__", ".");
+            match resolve_key(&schema, &key) {
+                Some((key, kind)) => {
+                    let value = coerce(raw, kind)
+                        .map_err(|e| RabbitMQError::ConfigError(format!("{name}: {e}")))?;
+                    layers.set(&key, value, ConfigSource::Env(name.clone()));
+                }
+                None => debug!("component=loader action=skip_env name={name} reason=unknown_key"),
+            }
+        }
+
+        for (key, raw) in &self.overrides {
+            let (path, kind) = resolve_key(&schema, key).ok_or_else(|| {
+                RabbitMQError::ConfigError(format!("Unknown configuration key '{key}'"))
+            })?;
+            let value =
+                coerce(raw, kind).map_err(|e| RabbitMQError::ConfigError(format!("{key}: {e}")))?;
+            layers.set(&path, value, ConfigSource::Cli(key.clone()));
+        }
+
+        let config = serde_json::from_value(layers.value.clone())
+            .map_err(|e| RabbitMQError::ConfigError(format!("Invalid configuration: {e}")))?;
+        Ok(LoadedConfig {
+            config,
+            file,
+            profile,
+            value: layers.value,
+            sources: layers.sources,
+        })
+    }
+}
+
+/// The result of `ConfigLoader::load`.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: RabbitMQFullConfig,
+    /// The configuration file that was read, if any.
+    pub file: Option<PathBuf>,
+    pub profile: Option<String>,
+    value: Value,
+    sources: BTreeMap<String, ConfigSource>,
+}
+
+impl LoadedConfig {
+    /// The source of a value by its dotted key, e.g. `connection.host`.
+    ///
+    /// Values inside a list or map that was set as a whole report the source of
+    /// the list or map.
+    pub fn source(&self, key: &str) -> Option<&ConfigSource> {
+        let mut key = key;
+        loop {
+            if let Some(source) = self.sources.get(key) {
+                return Some(source);
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+
+    /// The source of every value, by dotted key.
+    pub fn sources(&self) -> &BTreeMap<String, ConfigSource> {
+        &self.sources
+    }
+
+    /// One line per value with its source, passwords masked.
+    pub fn sources_report(&self) -> String {
+        let mut report = String::new();
+        for (key, source) in &self.sources {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            let value = match self.value.pointer(&pointer) {
+                Some(Value::Null) | None => "-".to_string(),
+                Some(_) if key.ends_with("password") => "********".to_string(),
+                Some(value) => value.to_string(),
+            };
+            report.push_str(&format!("{key} = {value}  ({source})\n"));
+        }
+        report
+    }
+}
+
+/// Command-line options for the configuration loader, for flattening into a
+/// binary's own arguments. Needs the `cli` feature.
+#[cfg(feature = "cli")]
+#[derive(Args, Debug, Clone, Default)]
+#[command(about = None, long_about = None)]
+pub struct ConfigArgs {
+    /// Configuration file (TOML, JSON or YAML). Defaults to $RABBITMQ_CONFIG, then
+    /// ./rabbitmq.toml and ~/.config/rabbitmq-mon/rabbitmq-mon.toml.
+    #[arg(long = "config", value_name = "PATH", global = true)]
+    pub config_file: Option<PathBuf>,
+
+    /// Profile of the configuration file to apply, e.g. dev, staging or prod.
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Overrides a configuration value, e.g. `--set connection.host=rabbit2`.
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_override, global = true)]
+    pub overrides: Vec<(String, String)>,
+
+    /// Print where each configuration value came from.
+    #[arg(long, global = true)]
+    pub show_config_sources: bool,
+}
+
+#[cfg(feature = "cli")]
+impl ConfigArgs {
+    pub fn loader(&self) -> ConfigLoader {
+        let mut loader = ConfigLoader::new();
+        if let Some(path) = &self.config_file {
+            loader = loader.file(path);
+        }
+        if let Some(profile) = &self.profile {
+            loader = loader.profile(profile);
+        }
+        for (key, value) in &self.overrides {
+            loader = loader.set(key, value);
+        }
+        loader
+    }
+
+    /// Loads the configuration, printing the sources report to stderr when asked to.
+    pub fn load(&self) -> Result<LoadedConfig, RabbitMQError> {
+        let loaded = self.loader().load()?;
+        if self.show_config_sources {
+            eprint!("{}", loaded.sources_report());
+        }
+        Ok(loaded)
+    }
+}
+
+/// Parses a `KEY=VALUE` command line override, for use as a clap `value_parser`.
+pub fn parse_override(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{arg}'"))
+}
+
+/// The first existing file among `./rabbitmq.*` and `<config dir>/rabbitmq-mon/rabbitmq-mon.*`.
+pub fn discover_config_file() -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = FILE_EXTENSIONS
+        .iter()
+        .map(|ext| PathBuf::from(format!("rabbitmq.{ext}")))
+        .collect();
+    if let Some(config_dir) = dirs::config_dir() {
+        let dir = config_dir.join("rabbitmq-mon");
+        candidates.extend(
+            FILE_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("rabbitmq-mon.{ext}"))),
+        );
+    }
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+fn read_config_file(path: &Path) -> Result<Map<String, Value>, RabbitMQError> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        RabbitMQError::ConfigError(format!("Failed to read {}: {e}", path.display()))
+    })?;
+    let value: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents).map_err(|e| {
+            RabbitMQError::ConfigError(format!("Invalid JSON in {}: {e}", path.display()))
+        })?,
+        Some("yaml" | "yml") => serde_norway::from_str(&contents).map_err(|e| {
+            RabbitMQError::ConfigError(format!("Invalid YAML in {}: {e}", path.display()))
+        })?,
+        _ => toml::from_str(&contents)?,
+    };
+    match value {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(Map::new()),
+        _ => Err(RabbitMQError::ConfigError(format!(
+            "{} must contain a table of settings",
+            path.display()
+        ))),
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, RabbitMQError> {
+    serde_json::to_value(value).map_err(|e| RabbitMQError::ConfigError(format!("{e}")))
+}
+
+/// The defaults with the optional sections filled in, giving the known keys and
+/// the type of each for parsing environment variables and overrides.
+fn schema() -> Result<Value, RabbitMQError> {
+    let mut schema = to_value(&RabbitMQFullConfig::default())?;
+    schema["connection"]["password"] = Value::String(String::new());
+    schema["connection"]["tls_options"] = to_value(&TlsOptions::default())?;
+    schema["retry"] = to_value(&RetryConfig::default())?;
+    Ok(schema)
+}
+
+/// Resolves a dotted key against the schema, adding the `connection.` prefix when
+/// the key does not start with a section. Returns the full key and its schema value.
+fn resolve_key<'a>(schema: &'a Value, key: &str) -> Option<(String, &'a Value)> {
+    let first = key.split('.').next()?;
+    let key = match schema.get(first) {
+        Some(Value::Object(_)) => key.to_string(),
+        _ => format!("connection.{key}"),
+    };
+    let kind = schema.pointer(&format!("/{}", key.replace('.', "/")))?;
+    Some((key, kind))
+}
+
+/// Parses a string from the environment or command line as the type of `kind`.
+fn coerce(raw: &str, kind: &Value) -> Result<Value, String> {
+    match kind {
+        Value::Bool(_) => match raw.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err(format!("expected true or false, got '{raw}'")),
+        },
+        Value::Number(n) if n.is_f64() => raw
+            .parse::<f64>()
+            .map(Value::from)
+            .map_err(|_| format!("expected a number, got '{raw}'")),
+        Value::Number(_) => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("expected an integer, got '{raw}'")),
+        Value::Array(_) if raw.trim_start().starts_with('[') => {
+            serde_json::from_str(raw).map_err(|e| format!("invalid JSON list: {e}"))
+        }
+        Value::Array(_) => Ok(Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+        Value::Object(_) => {
+            serde_json::from_str(raw).map_err(|e| format!("expected a JSON object: {e}"))
+        }
+        Value::String(_) | Value::Null => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// The merged configuration value and the source of each of its leaves.
+struct Layers {
+    value: Value,
+    sources: BTreeMap<String, ConfigSource>,
+}
+
+impl Layers {
+    fn new(defaults: Value) -> Self {
+        let mut layers = Self {
+            value: Value::Object(Map::new()),
+            sources: BTreeMap::new(),
+        };
+        layers.merge(defaults, &ConfigSource::Default);
+        layers
+    }
+
+    fn merge(&mut self, overlay: Value, source: &ConfigSource) {
+        let mut leaves = Vec::new();
+        collect_leaves(String::new(), overlay, &mut leaves);
+        for (key, value) in leaves {
+            self.set(&key, value, source.clone());
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Value, source: ConfigSource) {
+        let mut target = &mut self.value;
+        for segment in key.split('.') {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            target = target
+                .as_object_mut()
+                .expect("target was just made an object")
+                .entry(segment)
+                .or_insert(Value::Null);
+        }
+        *target = value;
+
+        // A value replaces everything that was set below it, and its parents now
+        // hold a table rather than a value of their own.
+        let prefix = format!("{key}.");
+        self.sources
+            .retain(|existing, _| !existing.starts_with(&prefix));
+        for (end, _) in key.match_indices('.') {
+            self.sources.remove(&key[..end]);
+        }
+        self.sources.insert(key.to_string(), source);
+    }
+}
+
+/// Flattens nested tables into dotted keys. Lists, scalars and empty tables are leaves.
+fn collect_leaves(prefix: String, value: Value, leaves: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_leaves(key, value, leaves);
+            }
+        }
+        value => leaves.push((prefix, value)),
+    }
+}
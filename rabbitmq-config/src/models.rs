@@ -1,8 +1,13 @@
 // rabbitmq-config/src/models.rs
 
-use lapin::types::FieldTable;
+use lapin::{
+    types::{FieldTable, ShortString},
+    BasicProperties,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::{field_table_to_arguments, PublisherConfig};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RabbitMQServerDefinition {
     pub rabbitmq_version: String,
@@ -22,6 +27,8 @@ pub struct UserDefinition {
     pub name: String,
     pub password_hash: String,
     pub hashing_algorithm: String,
+    /// Comma-separated tags. Newer servers export them as a list, which is joined here.
+    #[serde(deserialize_with = "tags_from_string_or_list")]
     pub tags: String,
 }
 
@@ -62,6 +69,7 @@ pub struct QueueDefinition {
     pub vhost: String,
     pub durable: bool,
     pub auto_delete: bool,
+    #[serde(default, with = "arguments_json")]
     pub arguments: FieldTable,
 }
 
@@ -73,6 +81,7 @@ pub struct ExchangeDefinition {
     pub durable: bool,
     pub auto_delete: bool,
     pub internal: bool,
+    #[serde(default, with = "arguments_json")]
     pub arguments: FieldTable,
 }
 
@@ -83,9 +92,68 @@ pub struct BindingDefinition {
     pub destination: String,
     pub destination_type: String,
     pub routing_key: String,
+    #[serde(default, with = "arguments_json")]
     pub arguments: FieldTable,
 }
 
+/// (De)serializes argument tables as the plain JSON objects used by `/api/definitions`.
+mod arguments_json {
+    use std::collections::HashMap;
+
+    use lapin::types::FieldTable;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::config::{arguments_to_field_table, field_table_to_arguments};
+
+    pub fn serialize<S: Serializer>(table: &FieldTable, serializer: S) -> Result<S::Ok, S::Error> {
+        field_table_to_arguments(table).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FieldTable, D::Error> {
+        let arguments = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+        Ok(arguments_to_field_table(&arguments))
+    }
+}
+
+fn tags_from_string_or_list<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(match Tags::deserialize(deserializer)? {
+        Tags::String(tags) => tags,
+        Tags::List(tags) => tags.join(","),
+    })
+}
+
+/// The message type design document, as found in `artifacts/message_types.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageTypeCatalog {
+    pub message_types: Vec<MessageTypeCategory>,
+}
+
+/// A category of message types, published through a topic exchange of the same name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageTypeCategory {
+    pub category: String,
+    pub types: Vec<MessageTypeDefinition>,
+}
+
+/// A message type, consumed from a queue of the same name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageTypeDefinition {
+    pub name: String,
+    /// Maximum queue priority; 0 means the queue is not a priority queue.
+    #[serde(default)]
+    pub priority: u8,
+    pub durable: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct QueueInfo {
     pub name: String,
@@ -113,10 +181,217 @@ pub struct RabbitMQMessage {
     pub properties: Option<MessageProperties>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A mandatory message the broker could not route and returned to the publisher.
+#[derive(Debug, Clone)]
+pub struct ReturnedMessage {
+    pub exchange: String,
+    pub routing_key: String,
+    pub reply_code: u16,
+    pub reply_text: String,
+    pub payload: Vec<u8>,
+    pub properties: MessageProperties,
+}
+
+impl From<lapin::message::BasicReturnMessage> for ReturnedMessage {
+    fn from(returned: lapin::message::BasicReturnMessage) -> Self {
+        Self {
+            reply_code: returned.reply_code,
+            reply_text: returned.reply_text.to_string(),
+            exchange: returned.delivery.exchange.to_string(),
+            routing_key: returned.delivery.routing_key.to_string(),
+            properties: MessageProperties::from(&returned.delivery.properties),
+            payload: returned.delivery.data,
+        }
+    }
+}
+
+/// The AMQP basic properties of a message, plus its header table.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct MessageProperties {
     pub content_type: Option<String>,
     pub content_encoding: Option<String>,
+    pub headers: Option<FieldTable>,
     pub delivery_mode: Option<u8>,
     pub priority: Option<u8>,
+    pub correlation_id: Option<String>,
+    pub reply_to: Option<String>,
+    pub expiration: Option<String>,
+    pub message_id: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub timestamp: Option<u64>,
+    /// The AMQP `type` property.
+    pub kind: Option<String>,
+    pub user_id: Option<String>,
+    pub app_id: Option<String>,
+    pub cluster_id: Option<String>,
+}
+
+impl MessageProperties {
+    /// Converts these properties into lapin's `BasicProperties` for publishing.
+    pub fn to_basic_properties(&self) -> BasicProperties {
+        let mut properties = BasicProperties::default();
+        if let Some(content_type) = &self.content_type {
+            properties = properties.with_content_type(content_type.as_str().into());
+        }
+        if let Some(content_encoding) = &self.content_encoding {
+            properties = properties.with_content_encoding(content_encoding.as_str().into());
+        }
+        if let Some(headers) = &self.headers {
+            properties = properties.with_headers(headers.clone());
+        }
+        if let Some(delivery_mode) = self.delivery_mode {
+            properties = properties.with_delivery_mode(delivery_mode);
+        }
+        if let Some(priority) = self.priority {
+            properties = properties.with_priority(priority);
+        }
+        if let Some(correlation_id) = &self.correlation_id {
+            properties = properties.with_correlation_id(correlation_id.as_str().into());
+        }
+        if let Some(reply_to) = &self.reply_to {
+            properties = properties.with_reply_to(reply_to.as_str().into());
+        }
+        if let Some(expiration) = &self.expiration {
+            properties = properties.with_expiration(expiration.as_str().into());
+        }
+        if let Some(message_id) = &self.message_id {
+            properties = properties.with_message_id(message_id.as_str().into());
+        }
+        if let Some(timestamp) = self.timestamp {
+            properties = properties.with_timestamp(timestamp);
+        }
+        if let Some(kind) = &self.kind {
+            properties = properties.with_type(kind.as_str().into());
+        }
+        if let Some(user_id) = &self.user_id {
+            properties = properties.with_user_id(user_id.as_str().into());
+        }
+        if let Some(app_id) = &self.app_id {
+            properties = properties.with_app_id(app_id.as_str().into());
+        }
+        if let Some(cluster_id) = &self.cluster_id {
+            properties = properties.with_cluster_id(cluster_id.as_str().into());
+        }
+        properties
+    }
+
+    /// Fills every unset property from `defaults`, keeping the values already set.
+    pub fn with_defaults(self, defaults: &MessageProperties) -> Self {
+        Self {
+            content_type: self.content_type.or_else(|| defaults.content_type.clone()),
+            content_encoding: self
+                .content_encoding
+                .or_else(|| defaults.content_encoding.clone()),
+            headers: self.headers.or_else(|| defaults.headers.clone()),
+            delivery_mode: self.delivery_mode.or(defaults.delivery_mode),
+            priority: self.priority.or(defaults.priority),
+            correlation_id: self
+                .correlation_id
+                .or_else(|| defaults.correlation_id.clone()),
+            reply_to: self.reply_to.or_else(|| defaults.reply_to.clone()),
+            expiration: self.expiration.or_else(|| defaults.expiration.clone()),
+            message_id: self.message_id.or_else(|| defaults.message_id.clone()),
+            timestamp: self.timestamp.or(defaults.timestamp),
+            kind: self.kind.or_else(|| defaults.kind.clone()),
+            user_id: self.user_id.or_else(|| defaults.user_id.clone()),
+            app_id: self.app_id.or_else(|| defaults.app_id.clone()),
+            cluster_id: self.cluster_id.or_else(|| defaults.cluster_id.clone()),
+        }
+    }
+
+    /// The properties that are set, as name and value pairs for display. Each header
+    /// is listed as `headers.<name>`, sorted by name, with its value as JSON.
+    pub fn display_fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push((name.to_string(), value));
+            }
+        };
+        push("content_type", self.content_type.clone());
+        push("content_encoding", self.content_encoding.clone());
+        push(
+            "delivery_mode",
+            self.delivery_mode.map(|mode| mode.to_string()),
+        );
+        push(
+            "priority",
+            self.priority.map(|priority| priority.to_string()),
+        );
+        push("correlation_id", self.correlation_id.clone());
+        push("reply_to", self.reply_to.clone());
+        push("expiration", self.expiration.clone());
+        push("message_id", self.message_id.clone());
+        push(
+            "timestamp",
+            self.timestamp.map(|timestamp| timestamp.to_string()),
+        );
+        push("type", self.kind.clone());
+        push("user_id", self.user_id.clone());
+        push("app_id", self.app_id.clone());
+        push("cluster_id", self.cluster_id.clone());
+
+        if let Some(headers) = &self.headers {
+            let mut headers: Vec<_> = field_table_to_arguments(headers).into_iter().collect();
+            headers.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, value) in headers {
+                fields.push((format!("headers.{name}"), value.to_string()));
+            }
+        }
+        fields
+    }
+}
+
+impl From<&BasicProperties> for MessageProperties {
+    fn from(properties: &BasicProperties) -> Self {
+        let to_string = |value: &Option<ShortString>| value.as_ref().map(|v| v.to_string());
+        Self {
+            content_type: to_string(properties.content_type()),
+            content_encoding: to_string(properties.content_encoding()),
+            headers: properties.headers().clone(),
+            delivery_mode: *properties.delivery_mode(),
+            priority: *properties.priority(),
+            correlation_id: to_string(properties.correlation_id()),
+            reply_to: to_string(properties.reply_to()),
+            expiration: to_string(properties.expiration()),
+            message_id: to_string(properties.message_id()),
+            timestamp: *properties.timestamp(),
+            kind: to_string(properties.kind()),
+            user_id: to_string(properties.user_id()),
+            app_id: to_string(properties.app_id()),
+            cluster_id: to_string(properties.cluster_id()),
+        }
+    }
+}
+
+impl From<&PublisherConfig> for MessageProperties {
+    fn from(publisher: &PublisherConfig) -> Self {
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        let delivery_mode = match (publisher.delivery_mode, publisher.persistence) {
+            (0, true) => Some(2),
+            (0, false) => None,
+            (mode, _) => Some(mode),
+        };
+        let timestamp = publisher.timestamp.then(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+
+        Self {
+            content_type: non_empty(&publisher.content_type),
+            content_encoding: non_empty(&publisher.content_encoding),
+            delivery_mode,
+            priority: (publisher.priority > 0).then_some(publisher.priority),
+            correlation_id: non_empty(&publisher.correlation_id),
+            reply_to: non_empty(&publisher.reply_to),
+            expiration: non_empty(&publisher.expiration),
+            message_id: non_empty(&publisher.message_id),
+            timestamp,
+            user_id: non_empty(&publisher.user_id),
+            app_id: non_empty(&publisher.app_id),
+            ..Default::default()
+        }
+    }
 }
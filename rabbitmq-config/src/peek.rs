@@ -0,0 +1,228 @@
+// rabbitmq-config/src/peek.rs
+
+use std::time::Duration;
+
+use futures_util::stream::StreamExt;
+use lapin::{
+    options::{BasicConsumeOptions, BasicGetOptions, BasicQosOptions},
+    types::{AMQPValue, FieldTable, LongString},
+    Channel,
+};
+use log::info;
+use tokio::time::timeout;
+
+use crate::{amqp_integer, MessageProperties, RabbitMQClient, RabbitMQError, RabbitMQMessage};
+
+/// Consumer argument selecting where a stream queue consumer starts reading. The
+/// broker also sets it as a header on every delivery, holding the message's offset.
+pub const X_STREAM_OFFSET: &str = "x-stream-offset";
+
+/// How long a stream peek waits for the next message before assuming it reached
+/// the end of the stream, when `PeekOptions::idle_timeout` is not set.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where a peek into a stream queue starts reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamOffset {
+    /// The first message still in the stream.
+    #[default]
+    First,
+    /// The last chunk written to the stream.
+    Last,
+    /// Only messages published after the peek starts.
+    Next,
+    /// The message at this offset.
+    Offset(u64),
+    /// The first message published at or after this time, in seconds since the Unix epoch.
+    Timestamp(u64),
+}
+
+impl StreamOffset {
+    /// The value of the `x-stream-offset` consumer argument.
+    pub fn to_amqp_value(self) -> AMQPValue {
+        match self {
+            StreamOffset::First => AMQPValue::LongString(LongString::from("first")),
+            StreamOffset::Last => AMQPValue::LongString(LongString::from("last")),
+            StreamOffset::Next => AMQPValue::LongString(LongString::from("next")),
+            StreamOffset::Offset(offset) => {
+                AMQPValue::LongLongInt(i64::try_from(offset).unwrap_or(i64::MAX))
+            }
+            StreamOffset::Timestamp(seconds) => AMQPValue::Timestamp(seconds),
+        }
+    }
+}
+
+/// Which messages a peek reads.
+#[derive(Debug, Clone, Default)]
+pub struct PeekOptions {
+    /// Read at most this many messages. Stream peeks read at most 65535.
+    pub limit: usize,
+    /// Read a stream queue from this offset. Classic and quorum queues leave this unset.
+    pub stream_offset: Option<StreamOffset>,
+    /// How long a stream peek waits for another message before it stops. Defaults to one second.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl PeekOptions {
+    /// Reads up to `limit` messages from the head of a classic or quorum queue.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Reads up to `limit` messages of a stream queue, starting at `offset`.
+    pub fn stream(limit: usize, offset: StreamOffset) -> Self {
+        Self {
+            limit,
+            stream_offset: Some(offset),
+            ..Default::default()
+        }
+    }
+}
+
+/// A message read by a peek, left in its queue.
+#[derive(Debug, Clone)]
+pub struct PeekedMessage {
+    /// Position from the head of the queue, or from the starting offset of a stream, starting at 0.
+    pub position: usize,
+    pub message: RabbitMQMessage,
+    /// Whether the broker had delivered the message before.
+    pub redelivered: bool,
+    /// The message's offset in a stream queue.
+    pub stream_offset: Option<u64>,
+}
+
+impl PeekedMessage {
+    /// The properties of the message, empty if it was published without any.
+    pub fn properties(&self) -> MessageProperties {
+        self.message.properties.clone().unwrap_or_default()
+    }
+}
+
+impl RabbitMQClient {
+    /// Reads messages from `queue` without removing them.
+    ///
+    /// Classic and quorum queues are read from the head with unacknowledged
+    /// `basic.get`s on a dedicated channel, which is closed afterwards so that the
+    /// messages return to the queue in their original order. They are then marked as
+    /// redelivered, and a quorum queue may count the return towards its delivery limit.
+    ///
+    /// Stream queues do not support `basic.get`; set `PeekOptions::stream_offset` to
+    /// read them with a consumer starting at that offset instead. Reading a stream
+    /// never removes anything from it.
+    pub async fn peek_messages(
+        &self,
+        queue: &str,
+        options: &PeekOptions,
+    ) -> Result<Vec<PeekedMessage>, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=peek_messages queue={queue} limit={} stream_offset={:?}",
+            options.limit, options.stream_offset
+        );
+        if options.limit == 0 {
+            return Ok(Vec::new());
+        }
+        let channel = self.create_channel().await?;
+        let result = match options.stream_offset {
+            Some(offset) => {
+                let idle_timeout = options.idle_timeout.unwrap_or(STREAM_IDLE_TIMEOUT);
+                peek_stream(&channel, queue, options.limit, offset, idle_timeout).await
+            }
+            None => peek_queue(&channel, queue, options.limit).await,
+        };
+        // Closing the channel returns every message it still holds unacknowledged to the queue.
+        if channel.status().connected() {
+            let _ = channel.close(200, "Peek finished").await;
+        }
+        result
+    }
+}
+
+async fn peek_queue(
+    channel: &Channel,
+    queue: &str,
+    limit: usize,
+) -> Result<Vec<PeekedMessage>, RabbitMQError> {
+    let mut messages = Vec::new();
+    while messages.len() < limit {
+        let message = channel
+            .basic_get(queue, BasicGetOptions { no_ack: false })
+            .await
+            .map_err(|e| {
+                RabbitMQError::ConsumeError(format!("Failed to get message from '{queue}': {e}"))
+            })?;
+        let Some(message) = message else {
+            break;
+        };
+        let delivery = message.delivery;
+        messages.push(PeekedMessage {
+            position: messages.len(),
+            redelivered: delivery.redelivered,
+            stream_offset: None,
+            message: RabbitMQMessage {
+                exchange: delivery.exchange.to_string(),
+                routing_key: delivery.routing_key.to_string(),
+                properties: Some(MessageProperties::from(&delivery.properties)),
+                payload: delivery.data,
+            },
+        });
+    }
+    Ok(messages)
+}
+
+async fn peek_stream(
+    channel: &Channel,
+    queue: &str,
+    limit: usize,
+    offset: StreamOffset,
+    idle_timeout: Duration,
+) -> Result<Vec<PeekedMessage>, RabbitMQError> {
+    // Stream consumers need a prefetch; with one as large as the limit the broker
+    // sends every message wanted without waiting for acks.
+    let prefetch = u16::try_from(limit).unwrap_or(u16::MAX);
+    channel
+        .basic_qos(prefetch, BasicQosOptions::default())
+        .await
+        .map_err(|e| RabbitMQError::ChannelError(format!("Failed to set prefetch: {e}")))?;
+    let mut arguments = FieldTable::default();
+    arguments.insert(X_STREAM_OFFSET.into(), offset.to_amqp_value());
+    let mut consumer = channel
+        .basic_consume(queue, "peek", BasicConsumeOptions::default(), arguments)
+        .await
+        .map_err(|e| {
+            RabbitMQError::ConsumeError(format!("Failed to read stream '{queue}': {e}"))
+        })?;
+
+    let mut messages = Vec::new();
+    while messages.len() < limit.min(usize::from(prefetch)) {
+        let delivery = match timeout(idle_timeout, consumer.next()).await {
+            Ok(Some(Ok(delivery))) => delivery,
+            Ok(Some(Err(e))) => {
+                return Err(RabbitMQError::ConsumeError(format!(
+                    "Error receiving message: {e}"
+                )))
+            }
+            Ok(None) | Err(_) => break, // End of the stream or consumer cancelled
+        };
+        let properties = MessageProperties::from(&delivery.properties);
+        let stream_offset = properties
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.inner().get(X_STREAM_OFFSET))
+            .and_then(amqp_integer);
+        messages.push(PeekedMessage {
+            position: messages.len(),
+            redelivered: delivery.redelivered,
+            stream_offset,
+            message: RabbitMQMessage {
+                exchange: delivery.exchange.to_string(),
+                routing_key: delivery.routing_key.to_string(),
+                properties: Some(properties),
+                payload: delivery.data,
+            },
+        });
+    }
+    Ok(messages)
+}
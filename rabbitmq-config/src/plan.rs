@@ -0,0 +1,294 @@
+// rabbitmq-config/src/plan.rs
+
+use log::info;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::apply::binding_name;
+use crate::config::{arguments_to_field_table, BindingConfig, ExchangeConfig, QueueConfig};
+use crate::drift::same_binding;
+use crate::{
+    diff_topology, Drift, DriftKind, ExchangeInfo, QueueInfo, RabbitMQClient, RabbitMQError,
+    Topology, TopologyObjectKind,
+};
+
+/// A single step towards the desired topology.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum TopologyOperation {
+    Unbind(BindingConfig),
+    DeleteQueue { name: String },
+    DeleteExchange { name: String },
+    DeclareExchange(ExchangeConfig),
+    DeclareQueue(QueueConfig),
+    Bind(BindingConfig),
+}
+
+impl fmt::Display for TopologyOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyOperation::Unbind(binding) => write!(f, "unbind {}", binding_name(binding)),
+            TopologyOperation::DeleteQueue { name } => write!(f, "delete queue {name}"),
+            TopologyOperation::DeleteExchange { name } => write!(f, "delete exchange {name}"),
+            TopologyOperation::DeclareExchange(exchange) => {
+                write!(f, "declare exchange {}", exchange.name)
+            }
+            TopologyOperation::DeclareQueue(queue) => write!(f, "declare queue {}", queue.name),
+            TopologyOperation::Bind(binding) => write!(f, "bind {}", binding_name(binding)),
+        }
+    }
+}
+
+/// An operation together with why it is needed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedOperation {
+    #[serde(flatten)]
+    pub operation: TopologyOperation,
+    /// Deletes a queue or exchange, losing its messages or bindings.
+    pub destructive: bool,
+    pub reason: String,
+}
+
+/// Options for `plan_topology`.
+#[derive(Debug, Clone, Default)]
+pub struct PlanOptions {
+    /// Also remove live exchanges, queues and bindings that are not desired.
+    pub prune: bool,
+}
+
+/// The ordered operations that turn a live topology into the desired one.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TopologyPlan {
+    pub operations: Vec<PlannedOperation>,
+}
+
+impl TopologyPlan {
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn destructive(&self) -> impl Iterator<Item = &PlannedOperation> {
+        self.operations.iter().filter(|planned| planned.destructive)
+    }
+
+    /// Renders the plan one operation per line, marking destructive ones with `!`.
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "Nothing to do.\n".to_string();
+        }
+        let mut text = String::new();
+        for planned in &self.operations {
+            let marker = if planned.destructive { '!' } else { '+' };
+            text.push_str(&format!(
+                "{marker} {} ({})\n",
+                planned.operation, planned.reason
+            ));
+        }
+        text
+    }
+}
+
+/// Computes the operations needed to turn `live` into `desired`.
+///
+/// Exchanges and queues whose settings differ cannot be changed in place, so they are
+/// deleted and declared again; these operations are destructive. Recreating an object
+/// drops its bindings, so the desired ones are bound again. Operations run in a safe
+/// order: unbinds, deletes, exchange and queue declarations, then bindings.
+pub fn plan_topology(desired: &Topology, live: &Topology, options: &PlanOptions) -> TopologyPlan {
+    let report = diff_topology(desired, live);
+    let mut unbinds = Vec::new();
+    let mut deletes = Vec::new();
+    let mut declares = Vec::new();
+    let mut binds = Vec::new();
+    let mut recreated: HashSet<(TopologyObjectKind, &str)> = HashSet::new();
+
+    for drift in &report.drifts {
+        let (delete, declare) = match drift.object {
+            TopologyObjectKind::Exchange => (
+                TopologyOperation::DeleteExchange {
+                    name: drift.name.clone(),
+                },
+                desired
+                    .exchanges
+                    .iter()
+                    .find(|exchange| exchange.name == drift.name)
+                    .map(|exchange| TopologyOperation::DeclareExchange(exchange.clone())),
+            ),
+            TopologyObjectKind::Queue => (
+                TopologyOperation::DeleteQueue {
+                    name: drift.name.clone(),
+                },
+                desired
+                    .queues
+                    .iter()
+                    .find(|queue| queue.name == drift.name)
+                    .map(|queue| TopologyOperation::DeclareQueue(queue.clone())),
+            ),
+            TopologyObjectKind::Binding => continue,
+        };
+
+        let recreate = drift.kind == DriftKind::Mismatched;
+        if recreate || (drift.kind == DriftKind::Extra && options.prune) {
+            deletes.push(planned(delete, true, drift_reason(drift)));
+        }
+        if let (Some(declare), DriftKind::Missing | DriftKind::Mismatched) = (declare, drift.kind) {
+            declares.push(planned(declare, false, drift_reason(drift)));
+        }
+        if recreate {
+            recreated.insert((drift.object, drift.name.as_str()));
+        }
+    }
+
+    let is_recreated = |binding: &BindingConfig| {
+        recreated.contains(&(TopologyObjectKind::Exchange, binding.exchange.as_str()))
+            || recreated.contains(&(TopologyObjectKind::Queue, binding.queue.as_str()))
+    };
+    for binding in &desired.bindings {
+        if is_recreated(binding) {
+            binds.push(planned(
+                TopologyOperation::Bind(binding.clone()),
+                false,
+                "restore after recreate".to_string(),
+            ));
+        } else if !live.bindings.iter().any(|live| same_binding(binding, live)) {
+            binds.push(planned(
+                TopologyOperation::Bind(binding.clone()),
+                false,
+                "missing".to_string(),
+            ));
+        }
+    }
+    if options.prune {
+        for binding in &live.bindings {
+            // Default-exchange bindings are implicit, and recreating drops the others anyway.
+            let implicit = binding.exchange.is_empty() || is_recreated(binding);
+            if !implicit
+                && !desired
+                    .bindings
+                    .iter()
+                    .any(|desired| same_binding(desired, binding))
+            {
+                unbinds.push(planned(
+                    TopologyOperation::Unbind(binding.clone()),
+                    false,
+                    "extra".to_string(),
+                ));
+            }
+        }
+    }
+
+    // Queues go before exchanges so that deleting an exchange never races its bindings.
+    deletes.sort_by_key(|planned| {
+        matches!(planned.operation, TopologyOperation::DeleteExchange { .. })
+    });
+    declares.sort_by_key(|planned| matches!(planned.operation, TopologyOperation::DeclareQueue(_)));
+
+    TopologyPlan {
+        operations: unbinds
+            .into_iter()
+            .chain(deletes)
+            .chain(declares)
+            .chain(binds)
+            .collect(),
+    }
+}
+
+fn planned(operation: TopologyOperation, destructive: bool, reason: String) -> PlannedOperation {
+    PlannedOperation {
+        operation,
+        destructive,
+        reason,
+    }
+}
+
+fn drift_reason(drift: &Drift) -> String {
+    if drift.fields.is_empty() {
+        return drift.kind.to_string();
+    }
+    let fields: Vec<String> = drift
+        .fields
+        .iter()
+        .map(|field| format!("{}: {} -> {}", field.field, field.live, field.desired))
+        .collect();
+    fields.join(", ")
+}
+
+impl RabbitMQClient {
+    /// Executes a plan in order, stopping at the first failing operation.
+    ///
+    /// Destructive operations are refused unless `allow_destructive` is set, and
+    /// queues that still hold messages are never deleted. Both are checked before
+    /// anything is changed. Returns the number of operations executed.
+    pub async fn apply_plan(
+        &self,
+        plan: &TopologyPlan,
+        allow_destructive: bool,
+    ) -> Result<usize, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=apply_plan operations={} allow_destructive={allow_destructive}",
+            plan.operations.len()
+        );
+        let destructive: Vec<String> = plan
+            .destructive()
+            .map(|planned| planned.operation.to_string())
+            .collect();
+        if !destructive.is_empty() && !allow_destructive {
+            return Err(RabbitMQError::DestructiveChange(destructive.join(", ")));
+        }
+        for planned in &plan.operations {
+            if let TopologyOperation::DeleteQueue { name } = &planned.operation {
+                let messages = self.queue_message_count(name).await?;
+                if messages > 0 {
+                    return Err(RabbitMQError::QueueNotEmpty {
+                        queue: name.clone(),
+                        messages,
+                    });
+                }
+            }
+        }
+
+        for planned in &plan.operations {
+            info!(
+                "component=RabbitMQClient action=apply_plan operation=\"{}\"",
+                planned.operation
+            );
+            match &planned.operation {
+                TopologyOperation::Unbind(binding) => {
+                    let arguments = arguments_to_field_table(&binding.arguments);
+                    self.unbind_queue(
+                        &binding.queue,
+                        &binding.exchange,
+                        &binding.routing_key,
+                        arguments,
+                    )
+                    .await?;
+                }
+                TopologyOperation::DeleteQueue { name } => {
+                    // `if_empty` guards against messages published since the check above.
+                    self.delete_queue(name, false, true).await?;
+                }
+                TopologyOperation::DeleteExchange { name } => {
+                    self.delete_exchange(name, false).await?
+                }
+                TopologyOperation::DeclareExchange(exchange) => {
+                    self.declare_exchange(&ExchangeInfo::from(exchange)).await?
+                }
+                TopologyOperation::DeclareQueue(queue) => {
+                    self.declare_queue(&QueueInfo::from(queue)).await?
+                }
+                TopologyOperation::Bind(binding) => {
+                    let arguments = arguments_to_field_table(&binding.arguments);
+                    self.bind_queue_with_arguments(
+                        &binding.queue,
+                        &binding.exchange,
+                        &binding.routing_key,
+                        arguments,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(plan.operations.len())
+    }
+}
@@ -0,0 +1,326 @@
+// rabbitmq-config/src/shovel.rs
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use lapin::{
+    options::{
+        BasicAckOptions, BasicGetOptions, BasicNackOptions, BasicPublishOptions,
+        ConfirmSelectOptions,
+    },
+    publisher_confirm::PublisherConfirm,
+    Channel,
+};
+use log::{info, warn};
+use tokio::time::Instant;
+
+use crate::{confirmation_result, MessageProperties, RabbitMQClient, RabbitMQError};
+
+/// Publishes left unconfirmed at a time when `ShovelOptions::max_in_flight` is 0.
+const DEFAULT_MAX_IN_FLIGHT: usize = 100;
+
+/// How long the shovel waits before polling an empty source queue again.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where a shovel publishes the messages it moves.
+#[derive(Debug, Clone, Default)]
+pub struct ShovelTarget {
+    pub exchange: String,
+    /// Keeps each message's routing key when `None`.
+    pub routing_key: Option<String>,
+}
+
+impl ShovelTarget {
+    /// Publishes straight into `queue` through the default exchange.
+    pub fn queue(queue: impl Into<String>) -> Self {
+        Self {
+            exchange: String::new(),
+            routing_key: Some(queue.into()),
+        }
+    }
+}
+
+/// When a shovel stops and how fast it moves messages.
+///
+/// Without a limit and without `until_empty` the shovel keeps waiting for new messages.
+#[derive(Debug, Clone, Default)]
+pub struct ShovelOptions {
+    /// Stop after moving this many messages.
+    pub max_messages: Option<u64>,
+    /// Stop before the payloads moved exceed this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Stop once the source queue has no ready messages left.
+    pub until_empty: bool,
+    /// Maximum number of messages moved per second.
+    pub rate_limit: Option<f64>,
+    /// Messages published but not yet confirmed at any time. 0 uses a default of 100.
+    pub max_in_flight: usize,
+}
+
+/// Why a shovel stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShovelStop {
+    MessageLimit,
+    ByteLimit,
+    Empty,
+}
+
+/// How far a shovel has got, reported after every confirmed message.
+#[derive(Debug, Clone, Default)]
+pub struct ShovelProgress {
+    /// Messages confirmed by the destination and removed from the source.
+    pub messages: u64,
+    /// Payload bytes of those messages.
+    pub bytes: u64,
+    /// Ready messages left in the source queue at the last fetch.
+    pub remaining: u64,
+    pub elapsed: Duration,
+}
+
+/// The final progress of a shovel and why it stopped.
+#[derive(Debug, Clone)]
+pub struct ShovelReport {
+    pub progress: ShovelProgress,
+    pub stopped: ShovelStop,
+}
+
+/// A message published to the destination whose source delivery is still unacknowledged.
+struct InFlight {
+    delivery_tag: u64,
+    bytes: u64,
+    exchange: String,
+    routing_key: String,
+    confirm: PublisherConfirm,
+}
+
+impl RabbitMQClient {
+    /// Moves messages from `queue` to `target` on `destination`, which may be this
+    /// client or one connected to another vhost or broker.
+    ///
+    /// Messages are fetched unacknowledged and republished with their properties and
+    /// headers in confirm mode. A source message is acked only once the destination
+    /// has confirmed it, so nothing is lost if the shovel fails or is interrupted;
+    /// messages in flight at that moment may end up in both queues. A nacked or
+    /// unroutable publish stops the shovel with an error and returns the message to
+    /// the source. `progress` is called after each confirmed message.
+    pub async fn shovel(
+        &self,
+        queue: &str,
+        destination: &RabbitMQClient,
+        target: &ShovelTarget,
+        options: &ShovelOptions,
+        mut progress: impl FnMut(&ShovelProgress),
+    ) -> Result<ShovelReport, RabbitMQError> {
+        info!(
+            "component=RabbitMQClient action=shovel queue={queue} exchange={} routing_key={:?} max_messages={:?} max_bytes={:?} until_empty={}",
+            target.exchange, target.routing_key, options.max_messages, options.max_bytes, options.until_empty
+        );
+        let source = self.create_channel().await?;
+        let publisher = destination.create_channel().await?;
+        let result = Shoveler {
+            queue,
+            source: &source,
+            publisher: &publisher,
+            target,
+            options,
+            started: Instant::now(),
+            progress: ShovelProgress::default(),
+            in_flight: VecDeque::new(),
+            fetched: 0,
+            fetched_bytes: 0,
+        }
+        .run(&mut progress)
+        .await;
+
+        // Closing the source channel returns anything still unacknowledged to the queue.
+        for channel in [source, publisher] {
+            if channel.status().connected() {
+                let _ = channel.close(200, "Shovel finished").await;
+            }
+        }
+        match &result {
+            Ok(report) => info!(
+                "component=RabbitMQClient action=shovel queue={queue} messages={} bytes={} stopped={:?}",
+                report.progress.messages, report.progress.bytes, report.stopped
+            ),
+            Err(e) => warn!("component=RabbitMQClient action=shovel queue={queue} error={e}"),
+        }
+        result
+    }
+}
+
+struct Shoveler<'a> {
+    queue: &'a str,
+    source: &'a Channel,
+    publisher: &'a Channel,
+    target: &'a ShovelTarget,
+    options: &'a ShovelOptions,
+    started: Instant,
+    progress: ShovelProgress,
+    in_flight: VecDeque<InFlight>,
+    /// Messages and bytes fetched so far, including those still in flight.
+    fetched: u64,
+    fetched_bytes: u64,
+}
+
+impl Shoveler<'_> {
+    async fn run(
+        mut self,
+        progress: &mut impl FnMut(&ShovelProgress),
+    ) -> Result<ShovelReport, RabbitMQError> {
+        self.publisher
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(|e| {
+                RabbitMQError::ChannelError(format!("Failed to enable publisher confirms: {e}"))
+            })?;
+        let max_in_flight = match self.options.max_in_flight {
+            0 => DEFAULT_MAX_IN_FLIGHT,
+            n => n,
+        };
+        let interval = self
+            .options
+            .rate_limit
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Duration::from_secs_f64(1.0 / rate));
+        let mut next_fetch = Instant::now();
+
+        let stopped = loop {
+            if self
+                .options
+                .max_messages
+                .is_some_and(|max| self.fetched >= max)
+            {
+                break ShovelStop::MessageLimit;
+            }
+            if let Some(interval) = interval {
+                tokio::time::sleep_until(next_fetch).await;
+                next_fetch = Instant::now() + interval;
+            }
+
+            let message = self
+                .source
+                .basic_get(self.queue, BasicGetOptions { no_ack: false })
+                .await
+                .map_err(|e| {
+                    RabbitMQError::ConsumeError(format!(
+                        "Failed to get message from '{}': {e}",
+                        self.queue
+                    ))
+                })?;
+            let Some(message) = message else {
+                self.progress.remaining = 0;
+                if self.options.until_empty {
+                    break ShovelStop::Empty;
+                }
+                // Nothing to fetch, so confirm what is in flight while waiting.
+                while !self.in_flight.is_empty() {
+                    self.settle_oldest(progress).await?;
+                }
+                tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+                continue;
+            };
+
+            self.progress.remaining = u64::from(message.message_count);
+            let delivery = message.delivery;
+            let bytes = delivery.data.len() as u64;
+            if self
+                .options
+                .max_bytes
+                .is_some_and(|max| self.fetched_bytes + bytes > max)
+            {
+                self.source
+                    .basic_nack(
+                        delivery.delivery_tag,
+                        BasicNackOptions {
+                            multiple: false,
+                            requeue: true,
+                        },
+                    )
+                    .await
+                    .map_err(|e| {
+                        RabbitMQError::AckError(format!(
+                            "Failed to return message to '{}': {e}",
+                            self.queue
+                        ))
+                    })?;
+                break ShovelStop::ByteLimit;
+            }
+
+            let exchange = self.target.exchange.clone();
+            let routing_key = self
+                .target
+                .routing_key
+                .clone()
+                .unwrap_or_else(|| delivery.routing_key.to_string());
+            let properties = MessageProperties::from(&delivery.properties).to_basic_properties();
+            let publish_options = BasicPublishOptions {
+                mandatory: true,
+                ..Default::default()
+            };
+            let confirm = self
+                .publisher
+                .basic_publish(
+                    &exchange,
+                    &routing_key,
+                    publish_options,
+                    &delivery.data,
+                    properties,
+                )
+                .await
+                .map_err(|e| {
+                    RabbitMQError::PublishError(format!("Failed to publish message: {e}"))
+                })?;
+
+            self.fetched += 1;
+            self.fetched_bytes += bytes;
+            self.in_flight.push_back(InFlight {
+                delivery_tag: delivery.delivery_tag,
+                bytes,
+                exchange,
+                routing_key,
+                confirm,
+            });
+            if self.in_flight.len() >= max_in_flight {
+                self.settle_oldest(progress).await?;
+            }
+        };
+
+        while !self.in_flight.is_empty() {
+            self.settle_oldest(progress).await?;
+        }
+        self.progress.elapsed = self.started.elapsed();
+        Ok(ShovelReport {
+            progress: self.progress,
+            stopped,
+        })
+    }
+
+    /// Waits for the oldest publish to be confirmed and acks its source message.
+    async fn settle_oldest(
+        &mut self,
+        progress: &mut impl FnMut(&ShovelProgress),
+    ) -> Result<(), RabbitMQError> {
+        let Some(in_flight) = self.in_flight.pop_front() else {
+            return Ok(());
+        };
+        let confirmation = in_flight
+            .confirm
+            .await
+            .map_err(|e| RabbitMQError::PublishError(format!("Failed to confirm message: {e}")))?;
+        // On failure the message stays in the source; the close requeues it with the rest in flight.
+        confirmation_result(&in_flight.exchange, &in_flight.routing_key, confirmation)?;
+
+        self.source
+            .basic_ack(in_flight.delivery_tag, BasicAckOptions::default())
+            .await
+            .map_err(|e| {
+                RabbitMQError::AckError(format!("Failed to ack shovelled message: {e}"))
+            })?;
+        self.progress.messages += 1;
+        self.progress.bytes += in_flight.bytes;
+        self.progress.elapsed = self.started.elapsed();
+        progress(&self.progress);
+        Ok(())
+    }
+}
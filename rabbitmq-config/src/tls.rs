@@ -0,0 +1,197 @@
+// rabbitmq-config/src/tls.rs
+
+//! AMQPS connections driven by `TlsOptions`, and the rustls configuration they
+//! share with the management API client.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lapin::tcp::{RustlsConnector, TcpStream};
+use lapin::uri::AMQPUri;
+use lapin::{Connection, ConnectionProperties};
+use log::info;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+use crate::{RabbitMQConfig, RabbitMQError, TlsOptions};
+
+/// Opens an AMQPS connection to the broker described by `config`.
+///
+/// Without `tls` the server certificate is checked against the system roots.
+/// Otherwise `ca_cert_path` is trusted in addition to them, hostname checks follow
+/// `verify_hostname`, and `client_cert_path` / `client_key_path` are presented for
+/// mutual TLS. All files are PEM; the key may be PKCS#1, PKCS#8 or SEC1.
+pub async fn connect_amqps(
+    config: &RabbitMQConfig,
+    tls: Option<&TlsOptions>,
+    properties: ConnectionProperties,
+) -> Result<Connection, RabbitMQError> {
+    let uri: AMQPUri = config
+        .to_amqps_uri()
+        .parse()
+        .map_err(|e| RabbitMQError::ConfigError(format!("Invalid AMQPS URI: {e}")))?;
+    connect_amqps_uri(uri, tls, properties).await
+}
+
+/// Opens an AMQPS connection to `uri`, honouring its `connection_timeout` for the TCP connect.
+pub(crate) async fn connect_amqps_uri(
+    uri: AMQPUri,
+    tls: Option<&TlsOptions>,
+    properties: ConnectionProperties,
+) -> Result<Connection, RabbitMQError> {
+    let connector = tls_connector(tls)?;
+    info!(
+        "component=tls action=connect host={} port={}",
+        uri.authority.host, uri.authority.port
+    );
+
+    // The error type is lapin's `HandshakeResult`, which we cannot box.
+    #[allow(clippy::result_large_err)]
+    let connect = Box::new(move |uri: &AMQPUri| {
+        let address = (uri.authority.host.as_str(), uri.authority.port);
+        let stream = match uri.query.connection_timeout {
+            Some(ms) => TcpStream::connect_timeout(address, Duration::from_millis(ms))?,
+            None => TcpStream::connect(address)?,
+        };
+        let stream = stream.into_rustls(&connector, &uri.authority.host)?;
+        stream.set_nonblocking(true)?;
+        Ok(stream)
+    });
+    Connection::connector(uri, connect, properties)
+        .await
+        .map_err(|e| RabbitMQError::ConnectionError(format!("{e}")))
+}
+
+/// Builds the rustls connector for `tls`, see `connect_amqps`.
+fn tls_connector(tls: Option<&TlsOptions>) -> Result<RustlsConnector, RabbitMQError> {
+    Ok(RustlsConnector::from(tls_client_config(tls)?))
+}
+
+/// Builds the rustls client configuration for `tls`, checking certificates as
+/// described for `connect_amqps`.
+pub fn tls_client_config(tls: Option<&TlsOptions>) -> Result<ClientConfig, RabbitMQError> {
+    let provider = Arc::new(ring::default_provider());
+
+    let mut roots = RootCertStore::empty();
+    let native = rustls_native_certs::load_native_certs()
+        .map_err(|e| RabbitMQError::TlsError(format!("Failed to load system certificates: {e}")))?;
+    roots.add_parsable_certificates(native);
+    if let Some(ca_path) = tls
+        .map(|tls| tls.ca_cert_path.as_str())
+        .filter(|path| !path.is_empty())
+    {
+        for cert in read_certs(ca_path)? {
+            roots.add(cert).map_err(|e| {
+                RabbitMQError::TlsError(format!("Invalid CA certificate {ca_path}: {e}"))
+            })?;
+        }
+    }
+
+    let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| {
+            RabbitMQError::TlsError(format!("Failed to build certificate verifier: {e}"))
+        })?;
+    let verifier: Arc<dyn ServerCertVerifier> = match tls {
+        Some(tls) if !tls.verify_hostname => Arc::new(IgnoreHostname(webpki)),
+        _ => webpki,
+    };
+
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| RabbitMQError::TlsError(format!("{e}")))?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier);
+
+    let client_cert = tls.and_then(|tls| tls.client_cert_path.as_deref());
+    let client_key = tls.and_then(|tls| tls.client_key_path.as_deref());
+    match (client_cert, client_key) {
+        (Some(cert_path), Some(key_path)) => builder
+            .with_client_auth_cert(read_certs(cert_path)?, read_key(key_path)?)
+            .map_err(|e| {
+                RabbitMQError::TlsError(format!(
+                    "Invalid client certificate {cert_path} or key {key_path}: {e}"
+                ))
+            }),
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err(RabbitMQError::TlsError(
+            "client_cert_path and client_key_path must be set together".to_string(),
+        )),
+    }
+}
+
+fn read_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, RabbitMQError> {
+    let file = File::open(path)
+        .map_err(|e| RabbitMQError::TlsError(format!("Failed to read {path}: {e}")))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| RabbitMQError::TlsError(format!("Failed to parse {path}: {e}")))?;
+    if certs.is_empty() {
+        return Err(RabbitMQError::TlsError(format!(
+            "No certificates found in {path}"
+        )));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &str) -> Result<PrivateKeyDer<'static>, RabbitMQError> {
+    let file = File::open(path)
+        .map_err(|e| RabbitMQError::TlsError(format!("Failed to read {path}: {e}")))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| RabbitMQError::TlsError(format!("Failed to parse {path}: {e}")))?
+        .ok_or_else(|| RabbitMQError::TlsError(format!("No private key found in {path}")))
+}
+
+/// Verifies the certificate chain but accepts any host name, for `verify_hostname = false`.
+#[derive(Debug)]
+struct IgnoreHostname(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for IgnoreHostname {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
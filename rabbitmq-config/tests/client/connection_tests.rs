@@ -0,0 +1,50 @@
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rabbitmq_config::*;
+
+#[test]
+fn test_connection_timeout_defaults_when_omitted_and_zero_waits_indefinitely() {
+    let base = r#"
+        host = "localhost"
+        amqp_port = 5672
+        management_port = 15672
+        vhost = "/"
+        username = "guest"
+    "#;
+    let omitted: ConnectionConfig = toml::from_str(base).unwrap();
+    assert_eq!(omitted.connection_timeout_ms, 10000);
+    assert_eq!(omitted.connection_timeout(), Some(Duration::from_secs(10)));
+
+    let zero: ConnectionConfig =
+        toml::from_str(&format!("{base}\nconnection_timeout_ms = 0")).unwrap();
+    assert_eq!(zero.connection_timeout_ms, 0);
+    assert_eq!(zero.connection_timeout(), None);
+}
+
+#[tokio::test]
+async fn test_connection_timeout_is_applied() {
+    // A server that accepts the connection but never answers the protocol header.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (_socket, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(5));
+    });
+
+    let config = RabbitMQConfig {
+        host: "127.0.0.1".to_string(),
+        amqp_port: port,
+        ..Default::default()
+    };
+    let settings = ConnectionConfig {
+        connection_timeout_ms: 200,
+        ..Default::default()
+    };
+
+    let started = Instant::now();
+    let result = open_connection(&config, &settings).await;
+    assert!(matches!(result, Err(RabbitMQError::TimeoutError(_))));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,136 @@
+use std::net::TcpListener;
+
+use rabbitmq_config::*;
+
+fn endpoints(list: &[&str]) -> Vec<Endpoint> {
+    list.iter()
+        .map(|endpoint| endpoint.parse().unwrap())
+        .collect()
+}
+
+/// A local port with nothing listening on it.
+fn closed_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+#[test]
+fn test_endpoint_parsing() {
+    let endpoint: Endpoint = "rabbit1.example.com:5672".parse().unwrap();
+    assert_eq!(endpoint, Endpoint::new("rabbit1.example.com", 5672));
+    assert_eq!(endpoint.to_string(), "rabbit1.example.com:5672");
+
+    assert!("rabbit1.example.com".parse::<Endpoint>().is_err());
+    assert!("rabbit1.example.com:amqp".parse::<Endpoint>().is_err());
+    assert!(":5672".parse::<Endpoint>().is_err());
+}
+
+#[test]
+fn test_endpoints_from_toml() {
+    let toml_str = r#"
+        host = "rabbit1"
+        amqp_port = 5672
+        management_port = 15672
+        vhost = "/"
+        username = "guest"
+        password = "guest"
+        amqp_endpoints = ["rabbit1:5672", "rabbit2:5672", "rabbit3:5672"]
+        management_endpoints = ["rabbit1:15672", "rabbit2:15672"]
+        endpoint_order = "shuffled"
+    "#;
+    let connection: ConnectionConfig = toml::from_str(toml_str).unwrap();
+    assert_eq!(connection.endpoint_order, EndpointOrder::Shuffled);
+
+    let config = connection.to_rabbitmq_config().unwrap();
+    assert_eq!(
+        config.amqp_endpoints,
+        endpoints(&["rabbit1:5672", "rabbit2:5672", "rabbit3:5672"])
+    );
+    assert_eq!(config.management_pool().candidates().len(), 2);
+
+    let invalid = toml_str.replace("rabbit2:5672", "rabbit2");
+    assert!(toml::from_str::<ConnectionConfig>(&invalid).is_err());
+}
+
+#[test]
+fn test_single_host_without_endpoints() {
+    let config = RabbitMQConfig {
+        host: "rabbit1".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(
+        config.amqp_pool().candidates(),
+        endpoints(&["rabbit1:5672"])
+    );
+    assert_eq!(
+        config.management_pool().candidates(),
+        endpoints(&["rabbit1:15672"])
+    );
+}
+
+#[test]
+fn test_ordered_pool_tries_failed_endpoints_last() {
+    let pool = EndpointPool::new(endpoints(&["a:1", "b:2", "c:3"]), EndpointOrder::Ordered);
+    assert_eq!(pool.candidates(), endpoints(&["a:1", "b:2", "c:3"]));
+    assert_eq!(pool.current(), None);
+
+    pool.mark_failed(&"b:2".parse().unwrap());
+    pool.mark_failed(&"a:1".parse().unwrap());
+    pool.mark_connected(&"c:3".parse().unwrap());
+    assert_eq!(pool.candidates(), endpoints(&["c:3", "b:2", "a:1"]));
+    assert_eq!(pool.failed(), endpoints(&["b:2", "a:1"]));
+    assert_eq!(pool.current(), Some("c:3".parse().unwrap()));
+
+    // The clone shares what the pool has learned.
+    let clone = pool.clone();
+    clone.mark_connected(&"b:2".parse().unwrap());
+    assert_eq!(pool.failed(), endpoints(&["a:1"]));
+    assert_eq!(pool.current(), Some("b:2".parse().unwrap()));
+}
+
+#[test]
+fn test_shuffled_pool_keeps_failed_endpoints_last() {
+    let all = endpoints(&["a:1", "b:2", "c:3", "d:4"]);
+    let pool = EndpointPool::new(all.clone(), EndpointOrder::Shuffled);
+    pool.mark_failed(&"a:1".parse().unwrap());
+
+    for _ in 0..20 {
+        let candidates = pool.candidates();
+        assert_eq!(candidates.len(), all.len());
+        assert_eq!(candidates.last(), Some(&"a:1".parse().unwrap()));
+        assert!(all.iter().all(|endpoint| candidates.contains(endpoint)));
+    }
+}
+
+#[tokio::test]
+async fn test_failover_reports_every_unreachable_endpoint() {
+    let (first, second) = (closed_port(), closed_port());
+    let config = RabbitMQConfig {
+        amqp_endpoints: endpoints(&[
+            &format!("127.0.0.1:{first}"),
+            &format!("127.0.0.1:{second}"),
+        ]),
+        ..Default::default()
+    };
+    let pool = config.amqp_pool();
+
+    let result = open_failover_connection(&config, &ConnectionConfig::default(), &pool).await;
+    match result {
+        Err(RabbitMQError::ConnectionError(message)) => {
+            assert!(message.contains(&format!("127.0.0.1:{first}")), "{message}");
+            assert!(
+                message.contains(&format!("127.0.0.1:{second}")),
+                "{message}"
+            );
+        }
+        other => panic!(
+            "expected a connection error, got {:?}",
+            other.map(|(_, endpoint)| endpoint)
+        ),
+    }
+    assert_eq!(pool.failed().len(), 2);
+    assert_eq!(pool.current(), None);
+}
@@ -12,3 +12,639 @@ async fn test_real_rabbitmq_connection() {
     // For now, just a placeholder that always succeeds
     assert!(true);
 }
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_apply_topology_is_idempotent() {
+    use rabbitmq_config::*;
+
+    let config: RabbitMQFullConfig = toml::from_str(
+        r#"
+        [connection]
+        host = "localhost"
+        amqp_port = 5672
+        management_port = 15672
+        vhost = "/"
+        username = "guest"
+
+        [[exchanges]]
+        name = "rabbitmq_config.apply_test"
+        type = "topic"
+        auto_delete = true
+
+        [[queues]]
+        name = "rabbitmq_config.apply_test"
+        auto_delete = true
+        arguments = { "x-message-ttl" = 60000 }
+
+        [[bindings]]
+        exchange = "rabbitmq_config.apply_test"
+        queue = "rabbitmq_config.apply_test"
+        routing_key = "apply.#"
+        "#,
+    )
+    .expect("Failed to deserialize");
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+
+    let first = client.apply_topology(&config).await.unwrap();
+    assert!(!first.has_failures(), "{:?}", first.results);
+
+    let second = client.apply_topology(&config).await.unwrap();
+    let statuses: Vec<_> = second.results.iter().map(|r| r.status.clone()).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ApplyStatus::Unchanged,
+            ApplyStatus::Unchanged,
+            ApplyStatus::Bound
+        ]
+    );
+
+    // Redeclaring with different arguments fails without breaking the client.
+    let mut conflicting = config.clone();
+    conflicting.queues[0]
+        .arguments
+        .insert("x-message-ttl".to_string(), serde_json::json!(1000));
+    let third = client.apply_topology(&conflicting).await.unwrap();
+    assert_eq!(third.failures().count(), 1);
+    assert!(client
+        .consume_one("rabbitmq_config.apply_test")
+        .await
+        .is_ok());
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_topology_management_operations() {
+    use lapin::types::FieldTable;
+    use rabbitmq_config::*;
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let source = ExchangeInfo {
+        name: "rabbitmq_config.ops_source".to_string(),
+        kind: "topic".to_string(),
+        durable: false,
+        auto_delete: false,
+        internal: false,
+        arguments: FieldTable::default(),
+    };
+    let destination = ExchangeInfo {
+        name: "rabbitmq_config.ops_destination".to_string(),
+        kind: "fanout".to_string(),
+        ..source.clone()
+    };
+    let queue = QueueInfo {
+        name: "rabbitmq_config.ops_queue".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments: FieldTable::default(),
+    };
+    client.declare_exchange(&source).await.unwrap();
+    client.declare_exchange(&destination).await.unwrap();
+    client.declare_queue(&queue).await.unwrap();
+    client
+        .bind_exchange(
+            &destination.name,
+            &source.name,
+            "ops.#",
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+    client
+        .bind_queue(&queue.name, &destination.name, "")
+        .await
+        .unwrap();
+
+    let message = RabbitMQMessage {
+        exchange: source.name.clone(),
+        routing_key: "ops.test".to_string(),
+        payload: b"payload".to_vec(),
+        properties: None,
+    };
+    client.publish_message(&message).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    // A non-empty queue is protected by `if_empty`, and the client stays usable.
+    let refused = client.delete_queue(&queue.name, false, true).await;
+    assert!(
+        matches!(refused, Err(RabbitMQError::PreconditionFailed { .. })),
+        "{refused:?}"
+    );
+    assert_eq!(client.purge_queue(&queue.name).await.unwrap(), 1);
+
+    let refused = client.delete_exchange(&destination.name, true).await;
+    assert!(
+        matches!(refused, Err(RabbitMQError::PreconditionFailed { .. })),
+        "{refused:?}"
+    );
+
+    client
+        .unbind_queue(&queue.name, &destination.name, "", FieldTable::default())
+        .await
+        .unwrap();
+    client
+        .unbind_exchange(
+            &destination.name,
+            &source.name,
+            "ops.#",
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        client.delete_queue(&queue.name, true, true).await.unwrap(),
+        0
+    );
+    client
+        .delete_exchange(&destination.name, true)
+        .await
+        .unwrap();
+    client.delete_exchange(&source.name, false).await.unwrap();
+
+    let missing = client.purge_queue(&queue.name).await;
+    assert!(
+        matches!(
+            missing,
+            Err(RabbitMQError::NotFound {
+                object: TopologyObjectKind::Queue,
+                ..
+            })
+        ),
+        "{missing:?}"
+    );
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_shovel_moves_messages_with_properties() {
+    use lapin::types::{AMQPValue, FieldTable};
+    use rabbitmq_config::*;
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let queue = |name: &str| QueueInfo {
+        name: name.to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments: FieldTable::default(),
+    };
+    let (source, destination) = (
+        queue("rabbitmq_config.shovel_source"),
+        queue("rabbitmq_config.shovel_destination"),
+    );
+    client.declare_queue(&source).await.unwrap();
+    client.declare_queue(&destination).await.unwrap();
+    client.purge_queue(&source.name).await.unwrap();
+    client.purge_queue(&destination.name).await.unwrap();
+
+    let mut headers = FieldTable::default();
+    headers.insert("tenant".into(), AMQPValue::LongString("acme".into()));
+    for payload in ["one", "two", "three"] {
+        let message = RabbitMQMessage {
+            exchange: String::new(),
+            routing_key: source.name.clone(),
+            payload: payload.as_bytes().to_vec(),
+            properties: Some(MessageProperties {
+                message_id: Some(payload.to_string()),
+                headers: Some(headers.clone()),
+                ..Default::default()
+            }),
+        };
+        client.publish_message(&message).await.unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let limited = ShovelOptions {
+        max_messages: Some(2),
+        ..Default::default()
+    };
+    let mut reported = 0;
+    let report = client
+        .shovel(
+            &source.name,
+            &client,
+            &ShovelTarget::queue(&destination.name),
+            &limited,
+            |progress| reported = progress.messages,
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.stopped, ShovelStop::MessageLimit);
+    assert_eq!(
+        (report.progress.messages, report.progress.bytes, reported),
+        (2, 6, 2)
+    );
+
+    let rest = ShovelOptions {
+        until_empty: true,
+        ..Default::default()
+    };
+    let report = client
+        .shovel(
+            &source.name,
+            &client,
+            &ShovelTarget::queue(&destination.name),
+            &rest,
+            |_| {},
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        (report.stopped, report.progress.messages),
+        (ShovelStop::Empty, 1)
+    );
+    assert_eq!(client.queue_message_count(&source.name).await.unwrap(), 0);
+
+    let moved = client
+        .consume_message(&destination.name)
+        .await
+        .unwrap()
+        .unwrap();
+    let properties = moved.properties.unwrap();
+    assert_eq!(properties.message_id.as_deref(), Some("one"));
+    assert_eq!(properties.headers, Some(headers));
+
+    client
+        .delete_queue(&source.name, false, false)
+        .await
+        .unwrap();
+    client
+        .delete_queue(&destination.name, false, false)
+        .await
+        .unwrap();
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_peek_leaves_messages_in_queue() {
+    use lapin::types::{AMQPValue, FieldTable, LongString};
+    use rabbitmq_config::*;
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let classic = QueueInfo {
+        name: "rabbitmq_config.peek_classic".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments: FieldTable::default(),
+    };
+    let mut stream_arguments = FieldTable::default();
+    stream_arguments.insert(
+        "x-queue-type".into(),
+        AMQPValue::LongString(LongString::from("stream")),
+    );
+    let stream = QueueInfo {
+        name: "rabbitmq_config.peek_stream".to_string(),
+        durable: true,
+        arguments: stream_arguments,
+        ..classic.clone()
+    };
+    client.declare_queue(&classic).await.unwrap();
+    client.declare_queue(&stream).await.unwrap();
+    client.purge_queue(&classic.name).await.unwrap();
+
+    let mut headers = FieldTable::default();
+    headers.insert("tenant".into(), AMQPValue::LongString("acme".into()));
+    for queue in [&classic.name, &stream.name] {
+        for payload in ["one", "two", "three"] {
+            let message = RabbitMQMessage {
+                exchange: String::new(),
+                routing_key: queue.clone(),
+                payload: payload.as_bytes().to_vec(),
+                properties: Some(MessageProperties {
+                    message_id: Some(payload.to_string()),
+                    headers: Some(headers.clone()),
+                    ..Default::default()
+                }),
+            };
+            client.publish_message(&message).await.unwrap();
+        }
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let peeked = client
+        .peek_messages(&classic.name, &PeekOptions::new(2))
+        .await
+        .unwrap();
+    assert_eq!(peeked.len(), 2);
+    assert_eq!(peeked[0].message.payload, b"one");
+    assert_eq!(peeked[1].properties().headers, Some(headers));
+    assert_eq!(client.queue_message_count(&classic.name).await.unwrap(), 3);
+
+    // The head of the queue is unchanged.
+    let first = client
+        .consume_message(&classic.name)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.payload, b"one");
+
+    let peeked = client
+        .peek_messages(&stream.name, &PeekOptions::stream(2, StreamOffset::First))
+        .await
+        .unwrap();
+    assert_eq!(peeked.len(), 2);
+    assert!(peeked[0].stream_offset.is_some());
+    assert_eq!(peeked[0].properties().message_id.as_deref(), Some("one"));
+
+    client
+        .delete_queue(&classic.name, false, false)
+        .await
+        .unwrap();
+    client
+        .delete_queue(&stream.name, false, false)
+        .await
+        .unwrap();
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_publish_batch_reports_nacked_messages() {
+    use lapin::types::{AMQPValue, FieldTable, LongString};
+    use rabbitmq_config::*;
+
+    // Without `confirm_deliveries` the batch still has to switch to confirm mode.
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let mut arguments = FieldTable::default();
+    arguments.insert("x-max-length".into(), AMQPValue::LongInt(1));
+    arguments.insert(
+        "x-overflow".into(),
+        AMQPValue::LongString(LongString::from("reject-publish")),
+    );
+    let queue = QueueInfo {
+        name: "rabbitmq_config.batch_nack".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments,
+    };
+    client.declare_queue(&queue).await.unwrap();
+    client.purge_queue(&queue.name).await.unwrap();
+
+    let messages: Vec<RabbitMQMessage> = ["one", "two", "three"]
+        .iter()
+        .map(|payload| RabbitMQMessage {
+            exchange: String::new(),
+            routing_key: queue.name.clone(),
+            payload: payload.as_bytes().to_vec(),
+            properties: None,
+        })
+        .collect();
+
+    // The queue only accepts one message and rejects the rest of the batch.
+    match client.publish_batch(&messages).await {
+        Err(RabbitMQError::BatchNacked { nacked, total }) => {
+            assert_eq!((nacked, total), (vec![1, 2], 3))
+        }
+        other => panic!("Expected BatchNacked, got {other:?}"),
+    }
+    assert_eq!(client.queue_message_count(&queue.name).await.unwrap(), 1);
+
+    client.purge_queue(&queue.name).await.unwrap();
+    client.publish_batch(&messages[..1]).await.unwrap();
+
+    client
+        .delete_queue(&queue.name, false, false)
+        .await
+        .unwrap();
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_unroutable_mandatory_publish_is_returned() {
+    use rabbitmq_config::*;
+    use std::sync::{Arc, Mutex};
+
+    let message = RabbitMQMessage {
+        exchange: "amq.direct".to_string(),
+        routing_key: "rabbitmq_config.nowhere".to_string(),
+        payload: b"lost".to_vec(),
+        properties: None,
+    };
+    let publisher = PublisherConfig {
+        mandatory: true,
+        ..Default::default()
+    };
+
+    // Without a return handler the publish fails.
+    let mut client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    match client.publish_with_config(&message, &publisher).await {
+        Err(RabbitMQError::Unroutable {
+            exchange,
+            routing_key,
+            ..
+        }) => {
+            assert_eq!(
+                (exchange.as_str(), routing_key.as_str()),
+                ("amq.direct", "rabbitmq_config.nowhere")
+            )
+        }
+        other => panic!("Expected Unroutable, got {other:?}"),
+    }
+
+    // With one, the handler receives the message and the publish succeeds.
+    let returned = Arc::new(Mutex::new(Vec::new()));
+    let recorded = returned.clone();
+    client.set_return_handler(move |message| recorded.lock().unwrap().push(message.payload));
+    client
+        .publish_with_config(&message, &publisher)
+        .await
+        .unwrap();
+    assert_eq!(*returned.lock().unwrap(), vec![b"lost".to_vec()]);
+
+    // `immediate` is rejected before anything is published.
+    let immediate = PublisherConfig {
+        immediate: true,
+        ..publisher
+    };
+    assert!(matches!(
+        client.publish_with_config(&message, &immediate).await,
+        Err(RabbitMQError::ConfigError(_))
+    ));
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_replay_stops_with_partial_report_when_channel_closes() {
+    use lapin::types::{AMQPValue, FieldTable};
+    use rabbitmq_config::*;
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let dead_letters = QueueInfo {
+        name: "rabbitmq_config.replay_dlq".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments: FieldTable::default(),
+    };
+    let destination = QueueInfo {
+        name: "rabbitmq_config.replay_destination".to_string(),
+        ..dead_letters.clone()
+    };
+    for queue in [&dead_letters, &destination] {
+        client.declare_queue(queue).await.unwrap();
+        client.purge_queue(&queue.name).await.unwrap();
+    }
+
+    // The second message points at an exchange that does not exist, so publishing it
+    // makes the broker close the channel.
+    for (payload, exchange, routing_key) in [
+        ("one", "", destination.name.as_str()),
+        ("two", "rabbitmq_config.missing_exchange", "anything"),
+        ("three", "", destination.name.as_str()),
+    ] {
+        let mut headers = FieldTable::default();
+        headers.insert(
+            ORIGINAL_EXCHANGE_HEADER.into(),
+            AMQPValue::LongString(exchange.into()),
+        );
+        headers.insert(
+            ORIGINAL_ROUTING_KEY_HEADER.into(),
+            AMQPValue::LongString(routing_key.into()),
+        );
+        let message = RabbitMQMessage {
+            exchange: String::new(),
+            routing_key: dead_letters.name.clone(),
+            payload: payload.as_bytes().to_vec(),
+            properties: Some(MessageProperties {
+                headers: Some(headers),
+                ..Default::default()
+            }),
+        };
+        client.publish_message(&message).await.unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let report = client
+        .replay_dead_letters(&dead_letters.name, &ReplayOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(report.scanned, 2);
+    assert_eq!(report.results.len(), 2);
+    assert_eq!(report.results[0].status, ReplayStatus::Replayed);
+    assert!(matches!(report.results[1].status, ReplayStatus::Failed(_)));
+    assert!(report.stopped.is_some());
+
+    // The replayed message is gone; the failed one and the one never reached are kept.
+    assert_eq!(
+        client
+            .queue_message_count(&dead_letters.name)
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        client.queue_message_count(&destination.name).await.unwrap(),
+        1
+    );
+
+    client
+        .delete_queue(&dead_letters.name, false, false)
+        .await
+        .unwrap();
+    client
+        .delete_queue(&destination.name, false, false)
+        .await
+        .unwrap();
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // This test is ignored as it requires a real RabbitMQ server
+async fn test_apply_plan_refuses_non_empty_queue_before_unbinding() {
+    use lapin::types::FieldTable;
+    use rabbitmq_config::*;
+
+    let client = RabbitMQClient::new(RabbitMQConfig::default())
+        .await
+        .unwrap();
+    let queue = QueueInfo {
+        name: "rabbitmq_config.plan_guard".to_string(),
+        durable: false,
+        exclusive: false,
+        auto_delete: false,
+        arguments: FieldTable::default(),
+    };
+    client.declare_queue(&queue).await.unwrap();
+    client.purge_queue(&queue.name).await.unwrap();
+    client
+        .bind_queue(&queue.name, "amq.direct", "plan")
+        .await
+        .unwrap();
+
+    let message = RabbitMQMessage {
+        exchange: "amq.direct".to_string(),
+        routing_key: "plan".to_string(),
+        payload: b"keep".to_vec(),
+        properties: None,
+    };
+    client.publish_message(&message).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let binding = BindingConfig {
+        exchange: "amq.direct".to_string(),
+        queue: queue.name.clone(),
+        routing_key: "plan".to_string(),
+        arguments: Default::default(),
+    };
+    let plan = TopologyPlan {
+        operations: vec![
+            PlannedOperation {
+                operation: TopologyOperation::Unbind(binding),
+                destructive: false,
+                reason: "extra".to_string(),
+            },
+            PlannedOperation {
+                operation: TopologyOperation::DeleteQueue {
+                    name: queue.name.clone(),
+                },
+                destructive: true,
+                reason: "extra".to_string(),
+            },
+        ],
+    };
+    let refused = client.apply_plan(&plan, true).await;
+    assert!(
+        matches!(
+            refused,
+            Err(RabbitMQError::QueueNotEmpty { messages: 1, .. })
+        ),
+        "{refused:?}"
+    );
+
+    // The binding was left in place, so the queue still receives messages.
+    client.publish_message(&message).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert_eq!(client.queue_message_count(&queue.name).await.unwrap(), 2);
+
+    client
+        .delete_queue(&queue.name, false, false)
+        .await
+        .unwrap();
+    client.close().await.unwrap();
+}
@@ -10,6 +10,7 @@ fn test_rabbitmq_config_to_uri() {
         username: "guest".to_string(),
         password: "guest".to_string(),
         vhost: "/".to_string(),
+        ..Default::default()
     };
 
     let uri = config.to_uri();
@@ -23,6 +24,7 @@ fn test_rabbitmq_config_to_uri() {
         username: "test-user".to_string(),
         password: "test-pass".to_string(),
         vhost: "test-vhost".to_string(),
+        ..Default::default()
     };
 
     let uri2 = config2.to_uri();
@@ -40,3 +42,94 @@ fn test_rabbitmq_error_debug() {
     assert!(debug_str.contains("ConfigError"));
     assert!(debug_str.contains("Test error"));
 }
+
+#[test]
+fn test_topology_error_display() {
+    let err = RabbitMQError::PreconditionFailed {
+        object: TopologyObjectKind::Queue,
+        name: "orders".to_string(),
+        reason: "PRECONDITION_FAILED - queue 'orders' in vhost '/' not empty".to_string(),
+    };
+    assert_eq!(
+        err.to_string(),
+        "Precondition failed for queue 'orders': PRECONDITION_FAILED - queue 'orders' in vhost '/' not empty"
+    );
+
+    let err = RabbitMQError::NotFound {
+        object: TopologyObjectKind::Exchange,
+        name: "events".to_string(),
+        reason: "NOT_FOUND - no exchange 'events' in vhost '/'".to_string(),
+    };
+    assert!(err.to_string().starts_with("exchange 'events' not found"));
+}
+
+#[test]
+fn test_message_properties_round_trip() {
+    use lapin::types::{AMQPValue, FieldTable, LongString};
+
+    let mut headers = FieldTable::default();
+    headers.insert(
+        "x-tenant".into(),
+        AMQPValue::LongString(LongString::from("acme")),
+    );
+    headers.insert("x-attempt".into(), AMQPValue::LongInt(3));
+
+    let properties = MessageProperties {
+        content_type: Some("application/json".to_string()),
+        content_encoding: Some("utf-8".to_string()),
+        headers: Some(headers),
+        delivery_mode: Some(2),
+        priority: Some(5),
+        correlation_id: Some("corr-1".to_string()),
+        reply_to: Some("replies".to_string()),
+        expiration: Some("60000".to_string()),
+        message_id: Some("msg-1".to_string()),
+        timestamp: Some(1_700_000_000),
+        kind: Some("order.placed".to_string()),
+        user_id: Some("guest".to_string()),
+        app_id: Some("orders-service".to_string()),
+        cluster_id: Some("cluster-a".to_string()),
+    };
+
+    let basic = properties.to_basic_properties();
+    assert_eq!(MessageProperties::from(&basic), properties);
+}
+
+#[test]
+fn test_message_properties_from_publisher_config() {
+    let publisher = PublisherConfig {
+        content_type: "text/plain".to_string(),
+        persistence: true,
+        ..Default::default()
+    };
+
+    let properties = MessageProperties::from(&publisher).with_defaults(&MessageProperties {
+        content_type: Some("application/json".to_string()),
+        app_id: Some("fallback".to_string()),
+        ..Default::default()
+    });
+
+    assert_eq!(properties.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(properties.delivery_mode, Some(2));
+    assert_eq!(properties.app_id.as_deref(), Some("fallback"));
+}
+
+#[test]
+fn test_confirmation_result() {
+    use lapin::publisher_confirm::Confirmation;
+
+    assert!(confirmation_result("orders", "order.placed", Confirmation::Ack(None)).is_ok());
+    assert!(confirmation_result("orders", "order.placed", Confirmation::NotRequested).is_ok());
+    match confirmation_result("orders", "order.placed", Confirmation::Nack(None)) {
+        Err(RabbitMQError::PublishNacked {
+            exchange,
+            routing_key,
+        }) => {
+            assert_eq!(
+                (exchange.as_str(), routing_key.as_str()),
+                ("orders", "order.placed")
+            )
+        }
+        other => panic!("Expected PublishNacked, got {other:?}"),
+    }
+}
@@ -12,6 +12,7 @@ pub fn create_test_config() -> RabbitMQConfig {
         username: "guest".to_string(),
         password: "guest".to_string(),
         vhost: "%2F".to_string(), // URL-encoded form of "/"
+        ..Default::default()
     }
 }
 
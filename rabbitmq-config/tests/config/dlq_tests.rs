@@ -0,0 +1,174 @@
+use lapin::types::{AMQPValue, FieldArray, FieldTable, LongString};
+use rabbitmq_config::*;
+
+fn death(
+    reason: &str,
+    queue: &str,
+    exchange: &str,
+    routing_key: &str,
+    count: i64,
+    time: u64,
+) -> AMQPValue {
+    let mut table = FieldTable::default();
+    table.insert("reason".into(), AMQPValue::LongString(reason.into()));
+    table.insert("queue".into(), AMQPValue::LongString(queue.into()));
+    table.insert("exchange".into(), AMQPValue::LongString(exchange.into()));
+    table.insert(
+        "routing-keys".into(),
+        AMQPValue::FieldArray(FieldArray::from(vec![AMQPValue::LongString(
+            LongString::from(routing_key),
+        )])),
+    );
+    table.insert("count".into(), AMQPValue::LongLongInt(count));
+    table.insert("time".into(), AMQPValue::Timestamp(time));
+    AMQPValue::FieldTable(table)
+}
+
+fn dead_letter(position: usize, headers: FieldTable) -> DeadLetter {
+    DeadLetter::new(
+        position,
+        RabbitMQMessage {
+            exchange: "orders.dlx".to_string(),
+            routing_key: "order.placed".to_string(),
+            payload: b"{}".to_vec(),
+            properties: Some(MessageProperties {
+                headers: Some(headers),
+                ..Default::default()
+            }),
+        },
+    )
+}
+
+/// Rejected from `orders` after expiring from `orders.delay`, most recent first.
+fn twice_dead() -> DeadLetter {
+    let mut headers = FieldTable::default();
+    headers.insert(
+        X_DEATH_HEADER.into(),
+        AMQPValue::FieldArray(FieldArray::from(vec![
+            death("rejected", "orders", "", "orders", 3, 1_700_000_600),
+            death(
+                "expired",
+                "orders.delay",
+                "shop",
+                "order.placed",
+                1,
+                1_700_000_000,
+            ),
+        ])),
+    );
+    dead_letter(0, headers)
+}
+
+#[test]
+fn test_x_death_is_decoded() {
+    let dead_letter = twice_dead();
+    assert_eq!(dead_letter.deaths.len(), 2);
+
+    let last = dead_letter.last_death().unwrap();
+    assert_eq!(last.reason, "rejected");
+    assert_eq!(last.queue, "orders");
+    assert_eq!(last.count, 3);
+    assert_eq!(last.time, Some(1_700_000_600));
+    assert_eq!(dead_letter.deaths[1].routing_keys, vec!["order.placed"]);
+
+    // The oldest death records where the message was first published.
+    assert_eq!(
+        dead_letter.original_target(),
+        Some(("shop".to_string(), "order.placed".to_string()))
+    );
+}
+
+#[test]
+fn test_original_target_from_consumer_headers() {
+    let mut headers = FieldTable::default();
+    headers.insert(
+        "x-original-exchange".into(),
+        AMQPValue::LongString("shop".into()),
+    );
+    headers.insert(
+        "x-original-routing-key".into(),
+        AMQPValue::LongString("order.paid".into()),
+    );
+    let dead_letter = dead_letter(4, headers);
+
+    assert!(dead_letter.deaths.is_empty());
+    assert_eq!(
+        dead_letter.original_target(),
+        Some(("shop".to_string(), "order.paid".to_string()))
+    );
+    assert_eq!(
+        self::dead_letter(5, FieldTable::default()).original_target(),
+        None
+    );
+}
+
+#[test]
+fn test_original_target_prefers_consumer_headers_over_x_death() {
+    // A message retried through a delay queue and then dead-lettered by the broker:
+    // its oldest death is the delay queue, not where it was first published.
+    let mut headers = FieldTable::default();
+    headers.insert(
+        X_DEATH_HEADER.into(),
+        AMQPValue::FieldArray(FieldArray::from(vec![
+            death("rejected", "orders", "", "orders", 1, 1_700_000_600),
+            death(
+                "expired",
+                "orders.retry.1000ms",
+                "",
+                "orders.retry.1000ms",
+                1,
+                1_700_000_000,
+            ),
+        ])),
+    );
+    headers.insert(
+        "x-original-exchange".into(),
+        AMQPValue::LongString("shop".into()),
+    );
+    headers.insert(
+        "x-original-routing-key".into(),
+        AMQPValue::LongString("order.placed".into()),
+    );
+    let dead_letter = dead_letter(6, headers);
+
+    assert_eq!(dead_letter.deaths.len(), 2);
+    assert_eq!(
+        dead_letter.original_target(),
+        Some(("shop".to_string(), "order.placed".to_string()))
+    );
+}
+
+#[test]
+fn test_filters_match_a_single_death() {
+    let dead_letter = twice_dead();
+    assert!(DeadLetterFilter::default().matches(&dead_letter));
+
+    let rejected_from_orders = DeadLetterFilter {
+        reason: Some("rejected".to_string()),
+        queue: Some("orders".to_string()),
+        min_count: Some(3),
+        ..Default::default()
+    };
+    assert!(rejected_from_orders.matches(&dead_letter));
+
+    // Both criteria hold, but for different deaths.
+    let expired_from_orders = DeadLetterFilter {
+        reason: Some("expired".to_string()),
+        queue: Some("orders".to_string()),
+        ..Default::default()
+    };
+    assert!(!expired_from_orders.matches(&dead_letter));
+
+    let window = DeadLetterFilter {
+        since: Some(1_700_000_100),
+        until: Some(1_700_000_700),
+        ..Default::default()
+    };
+    assert!(window.matches(&dead_letter));
+    let too_late = DeadLetterFilter {
+        since: Some(1_700_001_000),
+        ..Default::default()
+    };
+    assert!(!too_late.matches(&dead_letter));
+    assert!(!too_late.matches(&self::dead_letter(1, FieldTable::default())));
+}
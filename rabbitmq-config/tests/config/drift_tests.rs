@@ -0,0 +1,193 @@
+use rabbitmq_config::*;
+use serde_json::json;
+
+fn desired() -> Topology {
+    serde_json::from_value(json!({
+        "exchanges": [
+            { "name": "events", "type": "topic", "durable": true }
+        ],
+        "queues": [
+            { "name": "order.placed", "durable": true, "arguments": { "x-max-priority": 5 } },
+            { "name": "user.lookup" }
+        ],
+        "bindings": [
+            { "exchange": "events", "queue": "order.placed", "routing_key": "order.placed" }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_identical_topologies_have_no_drift() {
+    let report = diff_topology(&desired(), &desired());
+    assert!(report.is_clean());
+    assert_eq!(report.to_table(), "No topology drift detected.\n");
+}
+
+#[test]
+fn test_diff_reports_missing_extra_and_mismatched() {
+    let live: Topology = serde_json::from_value(json!({
+        "exchanges": [
+            { "name": "events", "type": "topic", "durable": true },
+            { "name": "legacy", "type": "fanout" },
+            { "name": "amq.topic", "type": "topic", "durable": true }
+        ],
+        "queues": [
+            { "name": "order.placed", "durable": false, "arguments": { "x-max-priority": 10 } },
+            { "name": "amq.gen-abc123", "exclusive": true }
+        ],
+        "bindings": [
+            { "exchange": "", "queue": "order.placed", "routing_key": "order.placed" }
+        ]
+    }))
+    .unwrap();
+
+    let report = diff_topology(&desired(), &live);
+    let summary: Vec<_> = report
+        .drifts
+        .iter()
+        .map(|drift| (drift.object, drift.name.as_str(), drift.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (TopologyObjectKind::Exchange, "legacy", DriftKind::Extra),
+            (
+                TopologyObjectKind::Queue,
+                "order.placed",
+                DriftKind::Mismatched
+            ),
+            (TopologyObjectKind::Queue, "user.lookup", DriftKind::Missing),
+            (
+                TopologyObjectKind::Binding,
+                "events -> order.placed (order.placed)",
+                DriftKind::Missing
+            ),
+        ]
+    );
+
+    let fields = &report.drifts[1].fields;
+    assert_eq!(
+        fields,
+        &vec![
+            FieldDrift {
+                field: "durable".to_string(),
+                desired: json!(true),
+                live: json!(false)
+            },
+            FieldDrift {
+                field: "arguments.x-max-priority".to_string(),
+                desired: json!(5),
+                live: json!(10)
+            },
+        ]
+    );
+
+    let table = report.to_table();
+    assert!(table.starts_with("KIND"));
+    assert!(table.contains("arguments.x-max-priority: desired 5, live 10"));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["drifts"][0]["kind"], "extra");
+    assert_eq!(json["drifts"][1]["object"], "queue");
+}
+
+#[test]
+fn test_whole_float_arguments_match_integers() {
+    let desired: Topology = serde_json::from_value(json!({
+        "queues": [
+            { "name": "orders", "arguments": { "x-message-ttl": 60000.0, "x-max-length": 1.5 } }
+        ],
+        "bindings": [
+            { "exchange": "events", "queue": "orders", "routing_key": "order.*", "arguments": { "x-priority": 1.0 } }
+        ]
+    }))
+    .unwrap();
+    let live: Topology = serde_json::from_value(json!({
+        "queues": [
+            { "name": "orders", "arguments": { "x-message-ttl": 60000, "x-max-length": 1 } }
+        ],
+        "bindings": [
+            { "exchange": "events", "queue": "orders", "routing_key": "order.*", "arguments": { "x-priority": 1 } }
+        ]
+    }))
+    .unwrap();
+
+    // Only the fractional value differs; the binding still matches.
+    let report = diff_topology(&desired, &live);
+    assert_eq!(report.drifts.len(), 1);
+    assert_eq!(
+        report.drifts[0].fields,
+        vec![FieldDrift {
+            field: "arguments.x-max-length".to_string(),
+            desired: json!(1.5),
+            live: json!(1)
+        }]
+    );
+}
+
+#[test]
+fn test_message_type_catalog_topology() {
+    let catalog: MessageTypeCatalog = serde_json::from_value(json!({
+        "message_types": [
+            {
+                "category": "events",
+                "types": [
+                    { "name": "order.placed", "priority": 5, "durable": true },
+                    { "name": "inventory.updated", "durable": false }
+                ]
+            }
+        ]
+    }))
+    .unwrap();
+
+    let topology = Topology::from(&catalog);
+    assert_eq!(topology.exchanges.len(), 1);
+    assert_eq!(topology.exchanges[0].exchange_type, "topic");
+    assert_eq!(
+        topology.queues[0].arguments.get("x-max-priority"),
+        Some(&json!(5))
+    );
+    assert!(topology.queues[1].arguments.is_empty());
+    assert_eq!(topology.bindings[1].routing_key, "inventory.updated");
+}
+
+#[test]
+fn test_topology_from_definitions_export() {
+    let definitions: RabbitMQServerDefinition = serde_json::from_value(json!({
+        "rabbitmq_version": "3.13.0",
+        "users": [
+            { "name": "guest", "password_hash": "x", "hashing_algorithm": "rabbit_password_hashing_sha256", "tags": ["administrator"] }
+        ],
+        "vhosts": [{ "name": "/" }, { "name": "staging" }],
+        "permissions": [],
+        "topic_permissions": [],
+        "parameters": [],
+        "policies": [],
+        "queues": [
+            { "name": "order.placed", "vhost": "/", "durable": true, "auto_delete": false, "arguments": { "x-max-priority": 5 } },
+            { "name": "order.placed", "vhost": "staging", "durable": true, "auto_delete": false, "arguments": {} }
+        ],
+        "exchanges": [
+            { "name": "events", "vhost": "/", "type": "topic", "durable": true, "auto_delete": false, "internal": false, "arguments": {} }
+        ],
+        "bindings": [
+            { "source": "events", "vhost": "/", "destination": "order.placed", "destination_type": "queue", "routing_key": "order.placed", "arguments": {} },
+            { "source": "events", "vhost": "/", "destination": "audit", "destination_type": "exchange", "routing_key": "#", "arguments": {} }
+        ]
+    }))
+    .expect("Failed to parse definitions");
+    assert_eq!(definitions.users[0].tags, "administrator");
+
+    let live = Topology::from_definitions(&definitions, "/");
+    assert_eq!(live.queues.len(), 1);
+    assert_eq!(live.bindings.len(), 1);
+
+    let desired: Topology = serde_json::from_value(json!({
+        "exchanges": [{ "name": "events", "type": "topic", "durable": true }],
+        "queues": [{ "name": "order.placed", "durable": true, "arguments": { "x-max-priority": 5 } }],
+        "bindings": [{ "exchange": "events", "queue": "order.placed", "routing_key": "order.placed" }]
+    }))
+    .unwrap();
+    assert!(diff_topology(&desired, &live).is_clean());
+}
@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::PathBuf;
+
+use rabbitmq_config::*;
+
+const TOML_CONFIG: &str = r#"
+[connection]
+host = "localhost"
+amqp_port = 5672
+management_port = 15672
+vhost = "/"
+username = "app"
+connection_name = "orders-service"
+
+[channel]
+default_prefetch_count = 10
+
+[profiles.prod.connection]
+host = "rabbit.prod.example.com"
+amqp_endpoints = ["rabbit1:5671", "rabbit2:5671"]
+use_tls = true
+
+[profiles.dev.connection]
+vhost = "dev"
+"#;
+
+/// Writes `contents` to a file that is unique to the test.
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("rabbitmq-config-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn no_env() -> Vec<(String, String)> {
+    Vec::new()
+}
+
+#[test]
+fn test_layers_and_their_sources() {
+    let path = write_config("layers.toml", TOML_CONFIG);
+    let loaded = ConfigLoader::new()
+        .file(&path)
+        .profile("prod")
+        .env_vars([
+            ("RABBITMQ_USERNAME", "ops"),
+            ("RABBITMQ_HEARTBEAT_INTERVAL_SEC", "30"),
+            ("RABBITMQ_NODENAME", "rabbit@x"),
+        ])
+        .set("connection.username", "admin")
+        .set("channel.confirm_deliveries", "true")
+        .load()
+        .unwrap();
+
+    let connection = &loaded.config.connection;
+    assert_eq!(connection.host, "rabbit.prod.example.com");
+    assert_eq!(connection.connection_name, "orders-service");
+    assert_eq!(connection.username, "admin");
+    assert_eq!(connection.heartbeat_interval_sec, 30);
+    assert_eq!(connection.connection_timeout_ms, 10000);
+    assert!(connection.use_tls);
+    assert_eq!(connection.amqp_endpoints.len(), 2);
+    assert_eq!(loaded.config.channel.default_prefetch_count, 10);
+    assert!(loaded.config.channel.confirm_deliveries);
+    assert_eq!(loaded.profile.as_deref(), Some("prod"));
+
+    let file = ConfigSource::File {
+        path: path.clone(),
+        profile: None,
+    };
+    let prod = ConfigSource::File {
+        path: path.clone(),
+        profile: Some("prod".to_string()),
+    };
+    assert_eq!(loaded.source("connection.host"), Some(&prod));
+    assert_eq!(loaded.source("connection.connection_name"), Some(&file));
+    assert_eq!(
+        loaded.source("connection.heartbeat_interval_sec"),
+        Some(&ConfigSource::Env(
+            "RABBITMQ_HEARTBEAT_INTERVAL_SEC".to_string()
+        ))
+    );
+    assert_eq!(
+        loaded.source("connection.username"),
+        Some(&ConfigSource::Cli("connection.username".to_string()))
+    );
+    assert_eq!(
+        loaded.source("connection.connection_timeout_ms"),
+        Some(&ConfigSource::Default)
+    );
+    assert_eq!(loaded.source("connection.amqp_endpoints"), Some(&prod));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_profile_from_environment() {
+    let path = write_config("env-profile.toml", TOML_CONFIG);
+    let loaded = ConfigLoader::new()
+        .env_vars([
+            ("RABBITMQ_CONFIG", path.to_str().unwrap()),
+            ("RABBITMQ_PROFILE", "dev"),
+        ])
+        .load()
+        .unwrap();
+    assert_eq!(loaded.file.as_ref(), Some(&path));
+    assert_eq!(loaded.config.connection.vhost, "dev");
+    assert_eq!(loaded.config.connection.host, "localhost");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_json_and_yaml_files() {
+    let json = write_config(
+        "config.json",
+        r#"{ "connection": { "host": "json-host", "amqp_port": 5673 } }"#,
+    );
+    let loaded = ConfigLoader::new()
+        .file(&json)
+        .env_vars(no_env())
+        .load()
+        .unwrap();
+    assert_eq!(loaded.config.connection.host, "json-host");
+    assert_eq!(loaded.config.connection.amqp_port, 5673);
+    assert_eq!(loaded.config.connection.username, "guest");
+
+    let yaml = write_config(
+        "config.yaml",
+        "connection:\n  host: yaml-host\n  tls_options:\n    ca_cert_path: /etc/ca.pem\nprofiles:\n  staging:\n    connection:\n      host: staging-host\n",
+    );
+    let loaded = ConfigLoader::new()
+        .file(&yaml)
+        .profile("staging")
+        .env_vars(no_env())
+        .load()
+        .unwrap();
+    assert_eq!(loaded.config.connection.host, "staging-host");
+    assert_eq!(
+        loaded.config.connection.tls_options.unwrap().ca_cert_path,
+        "/etc/ca.pem"
+    );
+
+    fs::remove_file(json).unwrap();
+    fs::remove_file(yaml).unwrap();
+}
+
+#[test]
+fn test_environment_value_types() {
+    let path = write_config("env-types.toml", "");
+    let loaded = ConfigLoader::new()
+        .file(&path)
+        .env_vars([
+            ("RABBITMQ_PASSWORD", "12345"),
+            ("RABBITMQ_USE_TLS", "yes"),
+            ("RABBITMQ_AMQP_ENDPOINTS", "rabbit1:5672, rabbit2:5672"),
+            ("RABBITMQ_ENDPOINT_ORDER", "shuffled"),
+            ("RABBITMQ_TLS_OPTIONS__CA_CERT_PATH", "/etc/ca.pem"),
+            ("RABBITMQ_CHANNEL__DEFAULT_PREFETCH_COUNT", "25"),
+        ])
+        .load()
+        .unwrap();
+
+    let connection = &loaded.config.connection;
+    assert_eq!(connection.password.as_deref(), Some("12345"));
+    assert!(connection.use_tls);
+    assert_eq!(
+        connection.amqp_endpoints,
+        vec![
+            Endpoint::new("rabbit1", 5672),
+            Endpoint::new("rabbit2", 5672)
+        ]
+    );
+    assert_eq!(connection.endpoint_order, EndpointOrder::Shuffled);
+    assert_eq!(
+        connection.tls_options.as_ref().unwrap().ca_cert_path,
+        "/etc/ca.pem"
+    );
+    assert_eq!(loaded.config.channel.default_prefetch_count, 25);
+
+    let report = loaded.sources_report();
+    assert!(
+        report.contains("connection.password = ********  (env RABBITMQ_PASSWORD)"),
+        "{report}"
+    );
+    assert!(!report.contains("12345"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_invalid_layers_are_reported() {
+    let path = write_config("invalid.toml", TOML_CONFIG);
+
+    let result = ConfigLoader::new()
+        .file(&path)
+        .profile("qa")
+        .env_vars(no_env())
+        .load();
+    match result {
+        Err(RabbitMQError::ConfigError(message)) => {
+            assert!(message.contains("available: dev, prod"), "{message}")
+        }
+        other => panic!(
+            "expected a config error, got {:?}",
+            other.map(|loaded| loaded.config)
+        ),
+    }
+
+    let result = ConfigLoader::new()
+        .file(&path)
+        .env_vars(no_env())
+        .set("connection.hots", "x")
+        .load();
+    assert!(matches!(result, Err(RabbitMQError::ConfigError(_))));
+
+    let result = ConfigLoader::new()
+        .file(&path)
+        .env_vars([("RABBITMQ_AMQP_PORT", "many")])
+        .load();
+    assert!(matches!(result, Err(RabbitMQError::ConfigError(_))));
+
+    let result = ConfigLoader::new()
+        .file(path.with_extension("missing"))
+        .env_vars(no_env())
+        .load();
+    assert!(matches!(result, Err(RabbitMQError::ConfigError(_))));
+
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,65 @@
+use lapin::types::{AMQPValue, FieldTable, LongString};
+use rabbitmq_config::*;
+
+#[test]
+fn test_stream_offset_argument() {
+    assert_eq!(
+        StreamOffset::default().to_amqp_value(),
+        AMQPValue::LongString(LongString::from("first"))
+    );
+    assert_eq!(
+        StreamOffset::Next.to_amqp_value(),
+        AMQPValue::LongString(LongString::from("next"))
+    );
+    assert_eq!(
+        StreamOffset::Offset(42).to_amqp_value(),
+        AMQPValue::LongLongInt(42)
+    );
+    assert_eq!(
+        StreamOffset::Timestamp(1_700_000_000).to_amqp_value(),
+        AMQPValue::Timestamp(1_700_000_000)
+    );
+}
+
+#[test]
+fn test_peek_options() {
+    let options = PeekOptions::new(10);
+    assert_eq!((options.limit, options.stream_offset), (10, None));
+
+    let options = PeekOptions::stream(5, StreamOffset::Last);
+    assert_eq!(
+        (options.limit, options.stream_offset),
+        (5, Some(StreamOffset::Last))
+    );
+}
+
+#[test]
+fn test_display_fields_list_properties_and_headers() {
+    let mut headers = FieldTable::default();
+    headers.insert("tenant".into(), AMQPValue::LongString("acme".into()));
+    headers.insert("attempt".into(), AMQPValue::LongInt(2));
+    let properties = MessageProperties {
+        content_type: Some("application/json".to_string()),
+        delivery_mode: Some(2),
+        kind: Some("order.placed".to_string()),
+        headers: Some(headers),
+        ..Default::default()
+    };
+
+    let fields = properties.display_fields();
+    let expected = [
+        ("content_type", "application/json"),
+        ("delivery_mode", "2"),
+        ("type", "order.placed"),
+        ("headers.attempt", "2"),
+        ("headers.tenant", "\"acme\""),
+    ];
+    assert_eq!(fields.len(), expected.len());
+    for ((name, value), (expected_name, expected_value)) in fields.iter().zip(expected) {
+        assert_eq!(
+            (name.as_str(), value.as_str()),
+            (expected_name, expected_value)
+        );
+    }
+    assert!(MessageProperties::default().display_fields().is_empty());
+}
@@ -0,0 +1,99 @@
+use rabbitmq_config::*;
+use serde_json::json;
+
+fn topology(value: serde_json::Value) -> Topology {
+    serde_json::from_value(value).unwrap()
+}
+
+fn steps(plan: &TopologyPlan) -> Vec<(String, bool)> {
+    plan.operations
+        .iter()
+        .map(|planned| (planned.operation.to_string(), planned.destructive))
+        .collect()
+}
+
+#[test]
+fn test_plan_declares_missing_objects_in_order() {
+    let desired = topology(json!({
+        "exchanges": [{ "name": "events", "type": "topic", "durable": true }],
+        "queues": [{ "name": "order.placed", "durable": true }],
+        "bindings": [{ "exchange": "events", "queue": "order.placed", "routing_key": "order.placed" }]
+    }));
+
+    let plan = plan_topology(&desired, &Topology::default(), &PlanOptions::default());
+    assert_eq!(
+        steps(&plan),
+        vec![
+            ("declare exchange events".to_string(), false),
+            ("declare queue order.placed".to_string(), false),
+            (
+                "bind events -> order.placed (order.placed)".to_string(),
+                false
+            ),
+        ]
+    );
+    assert!(plan.destructive().next().is_none());
+
+    assert!(plan_topology(&desired, &desired, &PlanOptions::default()).is_empty());
+}
+
+#[test]
+fn test_plan_recreates_mismatched_queue_destructively() {
+    let desired = topology(json!({
+        "exchanges": [{ "name": "events", "type": "topic", "durable": true }],
+        "queues": [{ "name": "order.placed", "durable": true, "arguments": { "x-max-priority": 5 } }],
+        "bindings": [{ "exchange": "events", "queue": "order.placed", "routing_key": "order.placed" }]
+    }));
+    let live = topology(json!({
+        "exchanges": [{ "name": "events", "type": "topic", "durable": true }],
+        "queues": [{ "name": "order.placed", "durable": true, "arguments": { "x-max-priority": 10 } }],
+        "bindings": [{ "exchange": "events", "queue": "order.placed", "routing_key": "order.placed" }]
+    }));
+
+    let plan = plan_topology(&desired, &live, &PlanOptions::default());
+    assert_eq!(
+        steps(&plan),
+        vec![
+            ("delete queue order.placed".to_string(), true),
+            ("declare queue order.placed".to_string(), false),
+            (
+                "bind events -> order.placed (order.placed)".to_string(),
+                false
+            ),
+        ]
+    );
+    assert_eq!(
+        plan.operations[0].reason,
+        "arguments.x-max-priority: 10 -> 5"
+    );
+    assert!(plan.to_text().starts_with("! delete queue order.placed"));
+}
+
+#[test]
+fn test_plan_only_prunes_extras_when_asked() {
+    let desired = topology(json!({ "queues": [{ "name": "kept" }] }));
+    let live = topology(json!({
+        "exchanges": [{ "name": "legacy", "type": "fanout" }],
+        "queues": [{ "name": "kept" }, { "name": "stale" }],
+        "bindings": [
+            { "exchange": "legacy", "queue": "kept", "routing_key": "" },
+            { "exchange": "", "queue": "kept", "routing_key": "kept" }
+        ]
+    }));
+
+    assert!(plan_topology(&desired, &live, &PlanOptions::default()).is_empty());
+
+    let plan = plan_topology(&desired, &live, &PlanOptions { prune: true });
+    assert_eq!(
+        steps(&plan),
+        vec![
+            ("unbind legacy -> kept ()".to_string(), false),
+            ("delete queue stale".to_string(), true),
+            ("delete exchange legacy".to_string(), true),
+        ]
+    );
+
+    let json = serde_json::to_value(&plan).unwrap();
+    assert_eq!(json["operations"][1]["action"], "delete_queue");
+    assert_eq!(json["operations"][1]["name"], "stale");
+}
@@ -0,0 +1,36 @@
+use rabbitmq_config::RetryConfig;
+use std::time::Duration;
+
+#[test]
+fn test_backoff_grows_and_is_capped() {
+    let retry = RetryConfig {
+        max_retries: 5,
+        initial_interval_ms: 100,
+        multiplier: 2.0,
+        max_interval_ms: 500,
+        randomization_factor: 0.0,
+    };
+
+    assert_eq!(retry.backoff(0), Duration::from_millis(100));
+    assert_eq!(retry.backoff(1), Duration::from_millis(200));
+    assert_eq!(retry.backoff(2), Duration::from_millis(400));
+    assert_eq!(retry.backoff(3), Duration::from_millis(500));
+    assert_eq!(retry.backoff(30), Duration::from_millis(500));
+}
+
+#[test]
+fn test_backoff_randomization_stays_in_range() {
+    let retry = RetryConfig {
+        max_retries: 3,
+        initial_interval_ms: 1000,
+        multiplier: 1.0,
+        max_interval_ms: 0,
+        randomization_factor: 0.5,
+    };
+    assert_eq!(retry.interval(0), Duration::from_millis(1000));
+
+    for _ in 0..100 {
+        let delay = retry.backoff(0);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+    }
+}
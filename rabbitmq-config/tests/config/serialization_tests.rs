@@ -10,6 +10,7 @@ fn test_basic_config_serialization() {
         username: "test-user".to_string(),
         password: "test-password".to_string(),
         vhost: "test-vhost".to_string(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string_pretty(&config).expect("Failed to serialize");
@@ -22,3 +23,56 @@ fn test_basic_config_serialization() {
     assert_eq!(config.password, deserialized.password);
     assert_eq!(config.vhost, deserialized.vhost);
 }
+
+#[test]
+fn test_channel_config_confirm_settings() {
+    let toml_str = r#"
+        default_prefetch_count = 10
+        confirm_deliveries = true
+        max_outstanding_confirms = 64
+    "#;
+    let channel: ChannelConfig = toml::from_str(toml_str).expect("Failed to deserialize");
+    assert!(channel.confirm_deliveries);
+    assert_eq!(channel.max_outstanding_confirms, 64);
+
+    // Older configs without the new key still parse, with no limit on outstanding confirms.
+    let channel: ChannelConfig =
+        toml::from_str("confirm_deliveries = true").expect("Failed to deserialize");
+    assert_eq!(channel.max_outstanding_confirms, 0);
+}
+
+#[test]
+fn test_connection_config_settings() {
+    let toml_str = r#"
+        host = "rabbit.example.com"
+        amqp_port = 5672
+        management_port = 15672
+        vhost = "/"
+        username = "svc"
+        password = "secret"
+
+        [client_properties]
+        team = "payments"
+    "#;
+    let connection: ConnectionConfig = toml::from_str(toml_str).expect("Failed to deserialize");
+
+    // Missing keys fall back to the same values as `ConnectionConfig::default()`.
+    assert_eq!(connection.connection_timeout_ms, 10000);
+    assert_eq!(connection.heartbeat_interval_sec, 60);
+    assert_eq!(connection.connection_name, "rabbitmq_app");
+    assert_eq!(
+        connection.connection_timeout(),
+        Some(std::time::Duration::from_secs(10))
+    );
+
+    let properties = connection.connection_properties();
+    let client_properties = field_table_to_arguments(&properties.client_properties);
+    assert_eq!(client_properties["connection_name"], "rabbitmq_app");
+    assert_eq!(client_properties["team"], "payments");
+
+    // A password in the file is used as is, without prompting.
+    let config = connection.to_rabbitmq_config().expect("Failed to convert");
+    assert_eq!(config.host, "rabbit.example.com");
+    assert_eq!(config.username, "svc");
+    assert_eq!(config.password, "secret");
+}
@@ -0,0 +1,76 @@
+use lapin::types::{AMQPValue, FieldArray, LongString};
+use rabbitmq_config::*;
+use serde_json::json;
+use std::collections::HashMap;
+
+#[test]
+fn test_arguments_convert_to_amqp_types() {
+    let arguments: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+        "x-message-ttl": 60000,
+        "x-max-length-bytes": 10_000_000_000_i64,
+        "x-queue-type": "quorum",
+        "x-single-active-consumer": true,
+        "x-ratio": 0.5,
+        "x-tags": ["a", 1],
+        "x-none": null,
+    }))
+    .unwrap();
+
+    let table = arguments_to_field_table(&arguments);
+    let value = |key: &str| table.inner().get(key).cloned();
+
+    assert_eq!(value("x-message-ttl"), Some(AMQPValue::LongInt(60000)));
+    assert_eq!(
+        value("x-max-length-bytes"),
+        Some(AMQPValue::LongLongInt(10_000_000_000))
+    );
+    assert_eq!(
+        value("x-queue-type"),
+        Some(AMQPValue::LongString(LongString::from("quorum")))
+    );
+    assert_eq!(
+        value("x-single-active-consumer"),
+        Some(AMQPValue::Boolean(true))
+    );
+    assert_eq!(value("x-ratio"), Some(AMQPValue::Double(0.5)));
+    assert_eq!(
+        value("x-tags"),
+        Some(AMQPValue::FieldArray(FieldArray::from(vec![
+            AMQPValue::LongString(LongString::from("a")),
+            AMQPValue::LongInt(1),
+        ])))
+    );
+    assert_eq!(value("x-none"), Some(AMQPValue::Void));
+}
+
+#[test]
+fn test_queue_config_converts_to_queue_info() {
+    let config: RabbitMQFullConfig = toml::from_str(
+        r#"
+        [connection]
+        host = "localhost"
+        amqp_port = 5672
+        management_port = 15672
+        vhost = "/"
+        username = "guest"
+
+        [[queues]]
+        name = "orders"
+        durable = true
+        arguments = { "x-max-priority" = 10, "x-dead-letter-exchange" = "orders.dlx" }
+        "#,
+    )
+    .expect("Failed to deserialize");
+
+    let queue = QueueInfo::from(&config.queues[0]);
+    assert_eq!(queue.name, "orders");
+    assert!(queue.durable);
+    assert_eq!(
+        queue.arguments.inner().get("x-max-priority"),
+        Some(&AMQPValue::LongInt(10))
+    );
+    assert_eq!(
+        queue.arguments.inner().get("x-dead-letter-exchange"),
+        Some(&AMQPValue::LongString(LongString::from("orders.dlx")))
+    );
+}
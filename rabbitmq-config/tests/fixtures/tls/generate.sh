@@ -0,0 +1,22 @@
+#!/bin/sh
+# Regenerates the test certificates: a CA, a server certificate for localhost and a
+# client certificate, all valid for 100 years. Keys are written in PKCS#8 form.
+# They are shared by the TLS tests of rabbitmq-config and rabbitmq-info.
+# The CA key is only needed for signing and is deleted afterwards, so it is never committed.
+set -e
+cd "$(dirname "$0")"
+
+openssl req -x509 -newkey rsa:2048 -nodes -days 36500 -subj "/CN=rabbitmq-test-ca" \
+    -keyout ca-key.pem -out ca.pem
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=localhost" -keyout server-key.pem -out server.csr
+printf "subjectAltName=DNS:localhost,IP:127.0.0.1\n" > server.ext
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial -days 36500 \
+    -extfile server.ext -out server.pem
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=rabbitmq-test-client" -keyout client-key.pem -out client.csr
+printf "extendedKeyUsage=clientAuth\n" > client.ext
+openssl x509 -req -in client.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial -days 36500 \
+    -extfile client.ext -out client.pem
+
+rm -f server.csr server.ext client.csr client.ext ca.srl ca-key.pem
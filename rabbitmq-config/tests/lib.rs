@@ -2,15 +2,25 @@
 
 // Define the client test modules
 pub mod client {
+    pub mod connection_tests;
+    pub mod failover_tests;
     pub mod integration_tests;
     pub mod mocked_tests;
+    pub mod tls_tests;
     pub mod unit_tests;
 }
 
 // Define the config test modules
 pub mod config {
+    pub mod dlq_tests;
+    pub mod drift_tests;
+    pub mod loader_tests;
     pub mod manipulation_tests;
+    pub mod peek_tests;
+    pub mod plan_tests;
+    pub mod retry_tests;
     pub mod serialization_tests;
+    pub mod topology_tests;
     pub mod validation_tests;
 }
 
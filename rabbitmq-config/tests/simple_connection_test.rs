@@ -11,6 +11,7 @@ fn test_simple_rabbitmq_connection() {
         username: "guest".to_string(),
         password: "guest".to_string(),
         vhost: "/".to_string(),
+        ..Default::default()
     };
 
     // Create a tokio runtime
@@ -1,10 +1,20 @@
 // rabbitmq-info/src/api.rs
 
-use reqwest::Client;
-use serde_json::Value;
+use futures_util::stream::{self, Stream, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use thiserror::Error;
 
-use rabbitmq_config::RabbitMQConfig;
+use rabbitmq_config::{
+    field_table_to_arguments, tls_client_config, BindingDefinition, Endpoint, EndpointPool,
+    ExchangeDefinition, PermissionDefinition, QueueDefinition, RabbitMQConfig, TlsOptions,
+    UserDefinition,
+};
+
+use crate::types::{Binding, Channel, Connection, Consumer, Exchange, Node, Overview, Queue};
 
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -13,11 +23,110 @@ pub enum ApiError {
 
     #[error("HTTP error: {0}")]
     HttpError(String),
+
+    #[error("{method} {path} failed with status {status}: {reason}")]
+    StatusError {
+        method: Method,
+        path: String,
+        status: u16,
+        reason: String,
+    },
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("No management endpoint reachable: {0}")]
+    Unreachable(String),
+}
+
+/// The list endpoints that support pagination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListResource {
+    Queues,
+    Exchanges,
+    Connections,
+    Channels,
+}
+
+impl ListResource {
+    fn path(&self) -> &'static str {
+        match self {
+            ListResource::Queues => "/api/queues",
+            ListResource::Exchanges => "/api/exchanges",
+            ListResource::Connections => "/api/connections",
+            ListResource::Channels => "/api/channels",
+        }
+    }
+}
+
+/// Options for paginated list calls such as `list_queues`.
+#[derive(Debug, Clone)]
+pub struct ListQuery {
+    /// Only list objects in this vhost, using the per-vhost endpoint.
+    pub vhost: Option<String>,
+    /// The first page to fetch, starting at 1.
+    pub page: u32,
+    pub page_size: u32,
+    /// Only list objects whose name contains this text, or matches it as a regex
+    /// when `use_regex` is set.
+    pub name: Option<String>,
+    pub use_regex: bool,
+    /// Only return these fields, e.g. `name` and `messages`. Empty means all fields.
+    pub columns: Vec<String>,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        Self {
+            vhost: None,
+            page: 1,
+            page_size: 500,
+            name: None,
+            use_regex: false,
+            columns: Vec::new(),
+        }
+    }
 }
 
+/// One page of a paginated list response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page<T = Value> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub page_count: u32,
+    pub page_size: u32,
+    /// Objects matching the name filter.
+    pub filtered_count: u64,
+    /// Objects on this page.
+    pub item_count: u64,
+    /// Objects before filtering.
+    pub total_count: u64,
+}
+
+/// A policy as accepted by `PUT /api/policies/{vhost}/{name}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicySettings {
+    pub pattern: String,
+    pub definition: Value,
+    pub priority: i32,
+    /// `queues`, `exchanges` or `all`.
+    #[serde(rename = "apply-to")]
+    pub apply_to: String,
+}
+
+/// A management API client.
+///
+/// Requests go to the node in use, starting with the first of the config's
+/// `management_endpoints`. A node that cannot be reached is remembered as failed
+/// and the request is retried on the next one.
 pub struct RabbitMQApiClient {
     client: Client,
     config: RabbitMQConfig,
+    scheme: &'static str,
+    endpoints: EndpointPool,
 }
 
 impl RabbitMQApiClient {
@@ -25,27 +134,118 @@ impl RabbitMQApiClient {
         Ok(Self {
             client: Client::new(),
             config: config.clone(),
+            scheme: "http",
+            endpoints: config.management_pool(),
         })
     }
 
-    fn build_url(&self, path: &str) -> String {
-        format!(
-            "http://{}:{}{}",
-            self.config.host, self.config.management_port, path
-        )
+    /// Creates a client that talks to the management API over HTTPS.
+    ///
+    /// Certificates are checked with the same rustls configuration as AMQPS
+    /// connections, see `rabbitmq_config::connect_amqps`: without `tls` against the
+    /// system roots, otherwise also against `ca_cert_path`, with hostname checks
+    /// following `verify_hostname`. `client_cert_path` / `client_key_path` are
+    /// presented when the broker asks for a client certificate. All files are PEM.
+    pub fn with_tls(config: &RabbitMQConfig, tls: Option<&TlsOptions>) -> Result<Self, ApiError> {
+        let tls_config = tls_client_config(tls).map_err(|e| ApiError::TlsError(e.to_string()))?;
+        let client = Client::builder()
+            .https_only(true)
+            .use_preconfigured_tls(tls_config)
+            .build()
+            .map_err(|e| ApiError::TlsError(format!("Failed to build HTTPS client: {}", e)))?;
+        Ok(Self {
+            client,
+            config: config.clone(),
+            scheme: "https",
+            endpoints: config.management_pool(),
+        })
     }
 
-    async fn get_list(&self, path: &str) -> Result<Vec<Value>, ApiError> {
-        let url = self.build_url(path);
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.config.username, Some(&self.config.password))
-            .send()
-            .await?;
+    /// Creates a client over HTTPS when `use_tls` is set, and over plain HTTP otherwise.
+    pub fn from_settings(
+        config: &RabbitMQConfig,
+        use_tls: bool,
+        tls: Option<&TlsOptions>,
+    ) -> Result<Self, ApiError> {
+        if use_tls {
+            Self::with_tls(config, tls)
+        } else {
+            Self::new(config)
+        }
+    }
+
+    /// The address of the management API node in use, e.g. `https://rabbit.example.com:15671`.
+    pub fn base_url(&self) -> String {
+        let endpoint = self
+            .endpoints
+            .current()
+            .or_else(|| self.endpoints.candidates().into_iter().next())
+            .unwrap_or_else(|| {
+                Endpoint::new(self.config.host.clone(), self.config.management_port)
+            });
+        self.endpoint_url(&endpoint)
+    }
+
+    /// The node that answered the last request.
+    pub fn current_endpoint(&self) -> Option<Endpoint> {
+        self.endpoints.current()
+    }
+
+    /// Nodes that could not be reached, which are tried last.
+    pub fn failed_endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints.failed()
+    }
+
+    fn endpoint_url(&self, endpoint: &Endpoint) -> String {
+        format!("{}://{}", self.scheme, endpoint)
+    }
+
+    /// Sends a request to the first reachable node.
+    ///
+    /// Only failures to connect move on to the next node, so a request is never
+    /// sent twice. With several nodes and none reachable, all failures are reported
+    /// as `Unreachable`. `build` adds the query and body to the request for `path`.
+    async fn dispatch(
+        &self,
+        method: Method,
+        path: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response, ApiError> {
+        let mut failures = Vec::new();
+        for endpoint in self.endpoints.candidates() {
+            let url = format!("{}{}", self.endpoint_url(&endpoint), path);
+            let request = self
+                .client
+                .request(method.clone(), &url)
+                .basic_auth(&self.config.username, Some(&self.config.password));
+            match build(request).send().await {
+                Ok(response) => {
+                    self.endpoints.mark_connected(&endpoint);
+                    return Ok(response);
+                }
+                Err(e) if e.is_connect() => {
+                    self.endpoints.mark_failed(&endpoint);
+                    failures.push((endpoint, e));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        // A single node reports its own error, as before clusters were supported.
+        if failures.len() == 1 {
+            return Err(failures.remove(0).1.into());
+        }
+        let details: Vec<String> = failures
+            .iter()
+            .map(|(endpoint, e)| format!("{} ({})", endpoint, e))
+            .collect();
+        Err(ApiError::Unreachable(details.join("; ")))
+    }
+
+    async fn get_list<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>, ApiError> {
+        let response = self.dispatch(Method::GET, path, |request| request).await?;
 
         if response.status().is_success() {
-            let items: Vec<Value> = response.json().await?;
+            let items: Vec<T> = response.json().await?;
             Ok(items)
         } else {
             Err(ApiError::HttpError(format!(
@@ -56,17 +256,11 @@ impl RabbitMQApiClient {
         }
     }
 
-    async fn get_value(&self, path: &str) -> Result<Value, ApiError> {
-        let url = self.build_url(path);
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.config.username, Some(&self.config.password))
-            .send()
-            .await?;
+    async fn get_value<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        let response = self.dispatch(Method::GET, path, |request| request).await?;
 
         if response.status().is_success() {
-            let value: Value = response.json().await?;
+            let value: T = response.json().await?;
             Ok(value)
         } else {
             Err(ApiError::HttpError(format!(
@@ -78,33 +272,45 @@ impl RabbitMQApiClient {
     }
 
     pub async fn is_alive(&self) -> Result<bool, ApiError> {
-        let url = self.build_url("/api/aliveness-test/%2F");
         let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.config.username, Some(&self.config.password))
-            .send()
+            .dispatch(Method::GET, "/api/aliveness-test/%2F", |request| request)
             .await?;
 
         Ok(response.status().is_success())
     }
 
-    pub async fn get_overview(&self) -> Result<Value, ApiError> {
+    pub async fn get_overview(&self) -> Result<Overview, ApiError> {
         self.get_value("/api/overview").await
     }
 
-    pub async fn get_queues(&self) -> Result<Vec<Value>, ApiError> {
+    pub async fn get_queues(&self) -> Result<Vec<Queue>, ApiError> {
         self.get_list("/api/queues").await
     }
 
-    pub async fn get_exchanges(&self) -> Result<Vec<Value>, ApiError> {
+    pub async fn get_exchanges(&self) -> Result<Vec<Exchange>, ApiError> {
         self.get_list("/api/exchanges").await
     }
 
-    pub async fn get_bindings(&self) -> Result<Vec<Value>, ApiError> {
+    pub async fn get_bindings(&self) -> Result<Vec<Binding>, ApiError> {
         self.get_list("/api/bindings").await
     }
 
+    pub async fn get_connections(&self) -> Result<Vec<Connection>, ApiError> {
+        self.get_list("/api/connections").await
+    }
+
+    pub async fn get_channels(&self) -> Result<Vec<Channel>, ApiError> {
+        self.get_list("/api/channels").await
+    }
+
+    pub async fn get_consumers(&self) -> Result<Vec<Consumer>, ApiError> {
+        self.get_list("/api/consumers").await
+    }
+
+    pub async fn get_nodes(&self) -> Result<Vec<Node>, ApiError> {
+        self.get_list("/api/nodes").await
+    }
+
     pub async fn get_vhosts(&self) -> Result<Vec<Value>, ApiError> {
         self.get_list("/api/vhosts").await
     }
@@ -112,4 +318,354 @@ impl RabbitMQApiClient {
     pub async fn get_definitions(&self) -> Result<Value, ApiError> {
         self.get_value("/api/definitions").await
     }
+
+    /// Fetches a single page of queues, exchanges, connections or channels.
+    pub async fn get_page<T: DeserializeOwned>(
+        &self,
+        resource: ListResource,
+        query: &ListQuery,
+    ) -> Result<Page<T>, ApiError> {
+        let path = match &query.vhost {
+            Some(vhost) => format!("{}/{}", resource.path(), encode(vhost)),
+            None => resource.path().to_string(),
+        };
+        let mut params = vec![
+            ("page", query.page.to_string()),
+            ("page_size", query.page_size.to_string()),
+        ];
+        if let Some(name) = &query.name {
+            params.push(("name", name.clone()));
+            params.push(("use_regex", query.use_regex.to_string()));
+        }
+        if !query.columns.is_empty() {
+            params.push(("columns", query.columns.join(",")));
+        }
+
+        let response = self
+            .dispatch(Method::GET, &path, |request| request.query(&params))
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            Err(ApiError::HttpError(format!(
+                "Failed to get page {} from {}: {}",
+                query.page,
+                path,
+                response.status()
+            )))
+        }
+    }
+
+    /// Walks all pages from `query.page` onwards, fetching each page when it is polled.
+    pub fn pages<'a, T: DeserializeOwned + 'a>(
+        &'a self,
+        resource: ListResource,
+        query: &'a ListQuery,
+    ) -> impl Stream<Item = Result<Page<T>, ApiError>> + 'a {
+        stream::try_unfold(Some(query.page), move |next| async move {
+            let Some(page) = next else {
+                return Ok(None);
+            };
+            let query = ListQuery {
+                page,
+                ..query.clone()
+            };
+            let result: Page<T> = self.get_page(resource, &query).await?;
+            let next = (result.page < result.page_count).then_some(page + 1);
+            Ok(Some((result, next)))
+        })
+    }
+
+    /// Lists all matching objects, fetching `query.page_size` objects per request.
+    pub async fn list<T: DeserializeOwned>(
+        &self,
+        resource: ListResource,
+        query: &ListQuery,
+    ) -> Result<Vec<T>, ApiError> {
+        self.pages(resource, query)
+            .try_fold(Vec::new(), |mut items, page| async move {
+                items.extend(page.items);
+                Ok(items)
+            })
+            .await
+    }
+
+    pub async fn list_queues(&self, query: &ListQuery) -> Result<Vec<Queue>, ApiError> {
+        self.list(ListResource::Queues, query).await
+    }
+
+    pub async fn list_exchanges(&self, query: &ListQuery) -> Result<Vec<Exchange>, ApiError> {
+        self.list(ListResource::Exchanges, query).await
+    }
+
+    /// Lists the bindings of one vhost, or of all vhosts. Bindings are not paginated.
+    pub async fn list_bindings(&self, vhost: Option<&str>) -> Result<Vec<Binding>, ApiError> {
+        match vhost {
+            Some(vhost) => {
+                self.get_list(&format!("/api/bindings/{}", encode(vhost)))
+                    .await
+            }
+            None => self.get_bindings().await,
+        }
+    }
+
+    pub async fn put_vhost(&self, name: &str) -> Result<(), ApiError> {
+        self.send(
+            Method::PUT,
+            &format!("/api/vhosts/{}", encode(name)),
+            Some(&json!({})),
+        )
+        .await
+    }
+
+    pub async fn delete_vhost(&self, name: &str) -> Result<(), ApiError> {
+        self.send(
+            Method::DELETE,
+            &format!("/api/vhosts/{}", encode(name)),
+            None,
+        )
+        .await
+    }
+
+    /// Creates or updates a user. `tags` is a comma-separated list such as `administrator`.
+    pub async fn put_user(&self, name: &str, password: &str, tags: &str) -> Result<(), ApiError> {
+        let body = json!({ "password": password, "tags": tags });
+        self.send(
+            Method::PUT,
+            &format!("/api/users/{}", encode(name)),
+            Some(&body),
+        )
+        .await
+    }
+
+    /// Creates or updates a user from a definitions export, keeping its password hash.
+    pub async fn put_user_definition(&self, user: &UserDefinition) -> Result<(), ApiError> {
+        let body = json!({
+            "password_hash": user.password_hash,
+            "hashing_algorithm": user.hashing_algorithm,
+            "tags": user.tags,
+        });
+        self.send(
+            Method::PUT,
+            &format!("/api/users/{}", encode(&user.name)),
+            Some(&body),
+        )
+        .await
+    }
+
+    pub async fn delete_user(&self, name: &str) -> Result<(), ApiError> {
+        self.send(
+            Method::DELETE,
+            &format!("/api/users/{}", encode(name)),
+            None,
+        )
+        .await
+    }
+
+    pub async fn put_permissions(
+        &self,
+        permissions: &PermissionDefinition,
+    ) -> Result<(), ApiError> {
+        let path = format!(
+            "/api/permissions/{}/{}",
+            encode(&permissions.vhost),
+            encode(&permissions.user)
+        );
+        let body = json!({
+            "configure": permissions.configure,
+            "write": permissions.write,
+            "read": permissions.read,
+        });
+        self.send(Method::PUT, &path, Some(&body)).await
+    }
+
+    pub async fn delete_permissions(&self, vhost: &str, user: &str) -> Result<(), ApiError> {
+        let path = format!("/api/permissions/{}/{}", encode(vhost), encode(user));
+        self.send(Method::DELETE, &path, None).await
+    }
+
+    pub async fn put_policy(
+        &self,
+        vhost: &str,
+        name: &str,
+        policy: &PolicySettings,
+    ) -> Result<(), ApiError> {
+        let path = format!("/api/policies/{}/{}", encode(vhost), encode(name));
+        self.send(Method::PUT, &path, Some(&json!(policy))).await
+    }
+
+    pub async fn delete_policy(&self, vhost: &str, name: &str) -> Result<(), ApiError> {
+        let path = format!("/api/policies/{}/{}", encode(vhost), encode(name));
+        self.send(Method::DELETE, &path, None).await
+    }
+
+    pub async fn put_queue(&self, queue: &QueueDefinition) -> Result<(), ApiError> {
+        let path = format!(
+            "/api/queues/{}/{}",
+            encode(&queue.vhost),
+            encode(&queue.name)
+        );
+        let body = json!({
+            "durable": queue.durable,
+            "auto_delete": queue.auto_delete,
+            "arguments": field_table_to_arguments(&queue.arguments),
+        });
+        self.send(Method::PUT, &path, Some(&body)).await
+    }
+
+    /// Deletes a queue. With `if_unused` or `if_empty` set, the broker refuses to
+    /// delete a queue that has consumers or messages.
+    pub async fn delete_queue(
+        &self,
+        vhost: &str,
+        name: &str,
+        if_unused: bool,
+        if_empty: bool,
+    ) -> Result<(), ApiError> {
+        let mut path = format!("/api/queues/{}/{}", encode(vhost), encode(name));
+        let conditions: Vec<&str> = [(if_unused, "if-unused=true"), (if_empty, "if-empty=true")]
+            .into_iter()
+            .filter_map(|(set, condition)| set.then_some(condition))
+            .collect();
+        if !conditions.is_empty() {
+            path = format!("{}?{}", path, conditions.join("&"));
+        }
+        self.send(Method::DELETE, &path, None).await
+    }
+
+    pub async fn put_exchange(&self, exchange: &ExchangeDefinition) -> Result<(), ApiError> {
+        let path = format!(
+            "/api/exchanges/{}/{}",
+            encode(&exchange.vhost),
+            encode(&exchange.name)
+        );
+        let body = json!({
+            "type": exchange.r#type,
+            "durable": exchange.durable,
+            "auto_delete": exchange.auto_delete,
+            "internal": exchange.internal,
+            "arguments": field_table_to_arguments(&exchange.arguments),
+        });
+        self.send(Method::PUT, &path, Some(&body)).await
+    }
+
+    pub async fn delete_exchange(
+        &self,
+        vhost: &str,
+        name: &str,
+        if_unused: bool,
+    ) -> Result<(), ApiError> {
+        let mut path = format!("/api/exchanges/{}/{}", encode(vhost), encode(name));
+        if if_unused {
+            path.push_str("?if-unused=true");
+        }
+        self.send(Method::DELETE, &path, None).await
+    }
+
+    /// Binds a queue or exchange, depending on `destination_type`, to the source exchange.
+    pub async fn create_binding(&self, binding: &BindingDefinition) -> Result<(), ApiError> {
+        let body = json!({
+            "routing_key": binding.routing_key,
+            "arguments": field_table_to_arguments(&binding.arguments),
+        });
+        self.send(Method::POST, &binding_path(binding)?, Some(&body))
+            .await
+    }
+
+    /// Deletes a binding. The management API identifies bindings by a properties key,
+    /// so the matching binding is looked up first.
+    pub async fn delete_binding(&self, binding: &BindingDefinition) -> Result<(), ApiError> {
+        let path = binding_path(binding)?;
+        let arguments: Map<String, Value> = field_table_to_arguments(&binding.arguments)
+            .into_iter()
+            .collect();
+        let existing: Vec<Binding> = self.get_list(&path).await?;
+        let properties_key = existing
+            .iter()
+            .find(|candidate| {
+                candidate.routing_key == binding.routing_key && candidate.arguments == arguments
+            })
+            .and_then(|candidate| candidate.properties_key.as_deref())
+            .ok_or_else(|| {
+                ApiError::NotFound(format!(
+                    "binding {} -> {} ({})",
+                    binding.source, binding.destination, binding.routing_key
+                ))
+            })?;
+        self.send(
+            Method::DELETE,
+            &format!("{}/{}", path, encode(properties_key)),
+            None,
+        )
+        .await
+    }
+
+    /// Imports a definitions export, as produced by `get_definitions`, into the broker.
+    pub async fn upload_definitions(&self, definitions: &Value) -> Result<(), ApiError> {
+        self.send(Method::POST, "/api/definitions", Some(definitions))
+            .await
+    }
+
+    /// Imports definitions into a single vhost. Vhost fields in `definitions` are ignored.
+    pub async fn upload_vhost_definitions(
+        &self,
+        vhost: &str,
+        definitions: &Value,
+    ) -> Result<(), ApiError> {
+        let path = format!("/api/definitions/{}", encode(vhost));
+        self.send(Method::POST, &path, Some(definitions)).await
+    }
+
+    /// Sends a request that changes the broker and expects no response body.
+    async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<(), ApiError> {
+        let response = self
+            .dispatch(method.clone(), path, |request| match body {
+                Some(body) => request.json(body),
+                None => request,
+            })
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        // Errors come back as `{"error": "...", "reason": "..."}`.
+        let text = response.text().await.unwrap_or_default();
+        let reason = serde_json::from_str::<Value>(&text)
+            .ok()
+            .and_then(|error| error["reason"].as_str().map(str::to_string))
+            .unwrap_or(text);
+        Err(ApiError::StatusError {
+            method,
+            path: path.to_string(),
+            status: status.as_u16(),
+            reason,
+        })
+    }
+}
+
+/// Encodes a single path segment, e.g. the default vhost `/` as `%2F`.
+fn encode(segment: &str) -> String {
+    utf8_percent_encode(segment, NON_ALPHANUMERIC).to_string()
+}
+
+fn binding_path(binding: &BindingDefinition) -> Result<String, ApiError> {
+    let destination_type = match binding.destination_type.as_str() {
+        "queue" => "q",
+        "exchange" => "e",
+        other => {
+            return Err(ApiError::HttpError(format!(
+                "Unknown binding destination type: {}",
+                other
+            )))
+        }
+    };
+    Ok(format!(
+        "/api/bindings/{}/e/{}/{}/{}",
+        encode(&binding.vhost),
+        encode(&binding.source),
+        destination_type,
+        encode(&binding.destination)
+    ))
 }
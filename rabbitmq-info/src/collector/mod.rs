@@ -1,9 +1,12 @@
 // rabbitmq-info/src/collector/mod.rs
 
-use crate::api::{ApiError, RabbitMQApiClient};
+use crate::api::{ApiError, ListQuery, RabbitMQApiClient};
+use crate::types::{Binding, Exchange, Overview, Queue};
 use crate::{BindingInfo, ExchangeInfo, QueueInfo, ServerInfo};
+use rabbitmq_config::{BindingConfig, ExchangeConfig, QueueConfig, Topology};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 
@@ -34,6 +37,52 @@ impl RabbitMQInfo {
             vhosts,
         }
     }
+
+    /// Extracts the exchanges, queues and queue bindings of `vhost`, for drift detection.
+    pub fn topology(&self, vhost: &str) -> Topology {
+        let arguments = |value: &Value| -> HashMap<String, Value> {
+            serde_json::from_value(value.clone()).unwrap_or_default()
+        };
+
+        Topology {
+            exchanges: self
+                .exchanges
+                .iter()
+                .filter(|exchange| exchange.vhost == vhost)
+                .map(|exchange| ExchangeConfig {
+                    name: exchange.name.clone(),
+                    exchange_type: exchange.exchange_type.clone(),
+                    durable: exchange.durable,
+                    auto_delete: exchange.auto_delete,
+                    internal: exchange.internal,
+                    arguments: arguments(&exchange.arguments),
+                })
+                .collect(),
+            queues: self
+                .queues
+                .iter()
+                .filter(|queue| queue.vhost == vhost)
+                .map(|queue| QueueConfig {
+                    name: queue.name.clone(),
+                    durable: queue.durable,
+                    exclusive: queue.exclusive,
+                    auto_delete: queue.auto_delete,
+                    arguments: arguments(&queue.arguments),
+                })
+                .collect(),
+            bindings: self
+                .bindings
+                .iter()
+                .filter(|binding| binding.vhost == vhost && binding.destination_type == "queue")
+                .map(|binding| BindingConfig {
+                    exchange: binding.source.clone(),
+                    queue: binding.destination.clone(),
+                    routing_key: binding.routing_key.clone(),
+                    arguments: arguments(&binding.arguments),
+                })
+                .collect(),
+        }
+    }
 }
 
 pub struct RabbitMQInfoCollector {
@@ -46,12 +95,21 @@ impl RabbitMQInfoCollector {
     }
 
     pub async fn collect_all(&self) -> Result<RabbitMQInfo, ApiError> {
+        // Queues and exchanges are fetched page by page to keep responses small on large brokers
+        let query = ListQuery::default();
+
         // Define the futures with explicit types
-        let overview_future: Pin<Box<dyn Future<Output = Result<Value, ApiError>> + Send>> = Box::pin(self.client.get_overview());
-        let exchanges_future: Pin<Box<dyn Future<Output = Result<Vec<Value>, ApiError>> + Send>> = Box::pin(self.client.get_exchanges());
-        let queues_future: Pin<Box<dyn Future<Output = Result<Vec<Value>, ApiError>> + Send>> = Box::pin(self.client.get_queues());
-        let bindings_future: Pin<Box<dyn Future<Output = Result<Vec<Value>, ApiError>> + Send>> = Box::pin(self.client.get_bindings());
-        let vhosts_future: Pin<Box<dyn Future<Output = Result<Vec<Value>, ApiError>> + Send>> = Box::pin(self.client.get_vhosts());
+        let overview_future: Pin<Box<dyn Future<Output = Result<Overview, ApiError>> + Send>> =
+            Box::pin(self.client.get_overview());
+        let exchanges_future: Pin<
+            Box<dyn Future<Output = Result<Vec<Exchange>, ApiError>> + Send>,
+        > = Box::pin(self.client.list_exchanges(&query));
+        let queues_future: Pin<Box<dyn Future<Output = Result<Vec<Queue>, ApiError>> + Send>> =
+            Box::pin(self.client.list_queues(&query));
+        let bindings_future: Pin<Box<dyn Future<Output = Result<Vec<Binding>, ApiError>> + Send>> =
+            Box::pin(self.client.get_bindings());
+        let vhosts_future: Pin<Box<dyn Future<Output = Result<Vec<Value>, ApiError>> + Send>> =
+            Box::pin(self.client.get_vhosts());
 
         // Await all futures
         let (overview, exchanges, queues, bindings, vhosts) = tokio::join!(
@@ -75,101 +133,79 @@ impl RabbitMQInfoCollector {
     }
 
     // Process the overview response from the API
-    fn process_overview(&self, overview: serde_json::Value) -> ServerInfo {
+    fn process_overview(&self, overview: Overview) -> ServerInfo {
         ServerInfo {
-            version: self.extract_string(&overview, "rabbitmq_version"),
-            erlang_version: self.extract_string(&overview, "erlang_version"),
-            cluster_name: self.extract_string(&overview, "cluster_name"),
-            management_version: self.extract_string(&overview, "management_version"),
-            uptime: self.extract_number(&overview, "uptime").unwrap_or(0),
-            node_name: self.extract_string(&overview, "node"),
+            version: overview.rabbitmq_version,
+            erlang_version: overview.erlang_version,
+            cluster_name: overview.cluster_name,
+            management_version: overview.management_version,
+            uptime: overview
+                .extra
+                .get("uptime")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+            node_name: overview.node,
         }
     }
 
     // Process exchanges response from the API
-    fn process_exchanges(&self, exchanges: Vec<serde_json::Value>) -> Vec<ExchangeInfo> {
+    fn process_exchanges(&self, exchanges: Vec<Exchange>) -> Vec<ExchangeInfo> {
         exchanges
             .into_iter()
             .map(|exchange| ExchangeInfo {
-                name: self.extract_string(&exchange, "name"),
-                vhost: self.extract_string(&exchange, "vhost"),
-                exchange_type: self.extract_string(&exchange, "type"),
-                durable: self.extract_bool(&exchange, "durable").unwrap_or(false),
-                auto_delete: self.extract_bool(&exchange, "auto_delete").unwrap_or(false),
-                internal: self.extract_bool(&exchange, "internal").unwrap_or(false),
-                arguments: self
-                    .extract_value(&exchange, "arguments")
-                    .unwrap_or_else(|| serde_json::json!({})),
+                name: exchange.name,
+                vhost: exchange.vhost,
+                exchange_type: exchange.exchange_type,
+                durable: exchange.durable,
+                auto_delete: exchange.auto_delete,
+                internal: exchange.internal,
+                arguments: Value::Object(exchange.arguments),
             })
             .collect()
     }
 
     // Process queues response from the API
-    fn process_queues(&self, queues: Vec<serde_json::Value>) -> Vec<QueueInfo> {
+    fn process_queues(&self, queues: Vec<Queue>) -> Vec<QueueInfo> {
         queues
             .into_iter()
             .map(|queue| QueueInfo {
-                name: self.extract_string(&queue, "name"),
-                vhost: self.extract_string(&queue, "vhost"),
-                durable: self.extract_bool(&queue, "durable").unwrap_or(false),
-                auto_delete: self.extract_bool(&queue, "auto_delete").unwrap_or(false),
-                exclusive: self.extract_bool(&queue, "exclusive").unwrap_or(false),
-                arguments: self
-                    .extract_value(&queue, "arguments")
-                    .unwrap_or_else(|| serde_json::json!({})),
-                messages: self.extract_number(&queue, "messages"),
-                messages_ready: self.extract_number(&queue, "messages_ready"),
-                messages_unacknowledged: self.extract_number(&queue, "messages_unacknowledged"),
+                name: queue.name,
+                vhost: queue.vhost,
+                durable: queue.durable,
+                auto_delete: queue.auto_delete,
+                exclusive: queue.exclusive,
+                arguments: Value::Object(queue.arguments),
+                messages: queue.messages,
+                messages_ready: queue.messages_ready,
+                messages_unacknowledged: queue.messages_unacknowledged,
             })
             .collect()
     }
 
     // Process bindings response from the API
-    fn process_bindings(&self, bindings: Vec<serde_json::Value>) -> Vec<BindingInfo> {
+    fn process_bindings(&self, bindings: Vec<Binding>) -> Vec<BindingInfo> {
         bindings
             .into_iter()
             .map(|binding| BindingInfo {
-                source: self.extract_string(&binding, "source"),
-                destination: self.extract_string(&binding, "destination"),
-                destination_type: self.extract_string(&binding, "destination_type"),
-                routing_key: self.extract_string(&binding, "routing_key"),
-                arguments: self
-                    .extract_value(&binding, "arguments")
-                    .unwrap_or_else(|| serde_json::json!({})),
-                vhost: self.extract_string(&binding, "vhost"),
+                source: binding.source,
+                destination: binding.destination,
+                destination_type: binding.destination_type,
+                routing_key: binding.routing_key,
+                arguments: Value::Object(binding.arguments),
+                vhost: binding.vhost,
             })
             .collect()
     }
 
     // Process vhosts response from the API
-    fn process_vhosts(&self, vhosts: Vec<serde_json::Value>) -> Vec<String> {
+    fn process_vhosts(&self, vhosts: Vec<Value>) -> Vec<String> {
         vhosts
             .into_iter()
             .filter_map(|vhost| {
-                self.extract_value(&vhost, "name")
+                vhost
+                    .get("name")
                     .and_then(|v| v.as_str().map(|s| s.to_string()))
             })
             .collect()
     }
-
-    // Helper methods to extract values from JSON
-    fn extract_string(&self, value: &serde_json::Value, key: &str) -> String {
-        value
-            .get(key)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string()
-    }
-
-    fn extract_bool(&self, value: &serde_json::Value, key: &str) -> Option<bool> {
-        value.get(key).and_then(|v| v.as_bool())
-    }
-
-    fn extract_number(&self, value: &serde_json::Value, key: &str) -> Option<u64> {
-        value.get(key).and_then(|v| v.as_u64())
-    }
-
-    fn extract_value(&self, value: &serde_json::Value, key: &str) -> Option<serde_json::Value> {
-        value.get(key).cloned()
-    }
 }
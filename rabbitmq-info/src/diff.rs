@@ -0,0 +1,302 @@
+// rabbitmq-info/src/diff.rs
+
+use rabbitmq_config::RabbitMQServerDefinition;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::restore::DefinitionKind;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// How an object differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// A single setting that differs. Nested settings such as arguments and policy
+/// definitions are reported as `arguments.<name>`, with `null` for an absent value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// One added, removed or changed object.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DefinitionChange {
+    pub kind: DefinitionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vhost: Option<String>,
+    pub name: String,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// The result of `diff_definitions`, ordered by object type, vhost and name.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DefinitionsDiff {
+    pub changes: Vec<DefinitionChange>,
+}
+
+impl DefinitionsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn count(&self, change: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.change == change).count()
+    }
+
+    /// Renders the diff with `+`, `-` and `~` markers, optionally in ANSI colors.
+    pub fn to_text(&self, color: bool) -> String {
+        if self.is_empty() {
+            return "No differences.\n".to_string();
+        }
+
+        let paint = |code: &str, text: String| {
+            if color {
+                format!("{}{}{}", code, text, RESET)
+            } else {
+                text
+            }
+        };
+        let mut text = String::new();
+        for change in &self.changes {
+            let (marker, code) = match change.change {
+                ChangeKind::Added => ('+', GREEN),
+                ChangeKind::Removed => ('-', RED),
+                ChangeKind::Changed => ('~', YELLOW),
+            };
+            let mut line = format!("{} {} {}", marker, change.kind, change.name);
+            if let Some(vhost) = &change.vhost {
+                line.push_str(&format!(" (vhost {})", vhost));
+            }
+            text.push_str(&paint(code, line));
+            text.push('\n');
+            for field in &change.fields {
+                text.push_str(&format!(
+                    "    {}: {} -> {}\n",
+                    field.field, field.before, field.after
+                ));
+            }
+        }
+        text.push_str(&format!(
+            "{} added, {} removed, {} changed.\n",
+            self.count(ChangeKind::Added),
+            self.count(ChangeKind::Removed),
+            self.count(ChangeKind::Changed)
+        ));
+        text
+    }
+}
+
+/// Compares two definitions snapshots, e.g. two `dump-state` files taken on different dates.
+///
+/// Password hashes are compared but never printed.
+pub fn diff_definitions(
+    before: &RabbitMQServerDefinition,
+    after: &RabbitMQServerDefinition,
+) -> DefinitionsDiff {
+    let before = entries(before);
+    let after = entries(after);
+    let mut diff = DefinitionsDiff::default();
+
+    for (key, entry) in &after {
+        let change = match before.get(key) {
+            None => Some((ChangeKind::Added, Vec::new())),
+            Some(old) => {
+                let mut fields = Vec::new();
+                compare_values("", &old.value, &entry.value, &mut fields);
+                (!fields.is_empty()).then_some((ChangeKind::Changed, fields))
+            }
+        };
+        if let Some((change, fields)) = change {
+            diff.changes.push(entry.to_change(change, fields));
+        }
+    }
+    for (key, entry) in &before {
+        if !after.contains_key(key) {
+            diff.changes
+                .push(entry.to_change(ChangeKind::Removed, Vec::new()));
+        }
+    }
+
+    diff.changes.sort_by(|a, b| {
+        let order = |change: &DefinitionChange| {
+            DefinitionKind::ALL
+                .iter()
+                .position(|kind| *kind == change.kind)
+        };
+        order(a)
+            .cmp(&order(b))
+            .then_with(|| a.vhost.cmp(&b.vhost))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    diff
+}
+
+struct Entry {
+    kind: DefinitionKind,
+    vhost: Option<String>,
+    name: String,
+    value: Value,
+}
+
+impl Entry {
+    fn to_change(&self, change: ChangeKind, fields: Vec<FieldChange>) -> DefinitionChange {
+        DefinitionChange {
+            kind: self.kind,
+            vhost: self.vhost.clone(),
+            name: self.name.clone(),
+            change,
+            fields,
+        }
+    }
+}
+
+/// Indexes every object of a snapshot by its identity.
+fn entries(definitions: &RabbitMQServerDefinition) -> BTreeMap<String, Entry> {
+    let mut entries = BTreeMap::new();
+    let mut insert = |kind: DefinitionKind, vhost: Option<&str>, name: String, value: Value| {
+        let key = format!("{}|{}|{}", kind, vhost.unwrap_or_default(), name);
+        entries.insert(
+            key,
+            Entry {
+                kind,
+                vhost: vhost.map(str::to_string),
+                name,
+                value,
+            },
+        );
+    };
+    for vhost in &definitions.vhosts {
+        insert(
+            DefinitionKind::Vhost,
+            None,
+            vhost.name.clone(),
+            value(vhost),
+        );
+    }
+    for user in &definitions.users {
+        insert(DefinitionKind::User, None, user.name.clone(), value(user));
+    }
+    for permission in &definitions.permissions {
+        insert(
+            DefinitionKind::Permission,
+            Some(&permission.vhost),
+            permission.user.clone(),
+            value(permission),
+        );
+    }
+    for policy in &definitions.policies {
+        let vhost = policy["vhost"].as_str().unwrap_or_default();
+        let name = policy["name"].as_str().unwrap_or_default().to_string();
+        insert(DefinitionKind::Policy, Some(vhost), name, policy.clone());
+    }
+    for exchange in &definitions.exchanges {
+        insert(
+            DefinitionKind::Exchange,
+            Some(&exchange.vhost),
+            exchange.name.clone(),
+            value(exchange),
+        );
+    }
+    for queue in &definitions.queues {
+        insert(
+            DefinitionKind::Queue,
+            Some(&queue.vhost),
+            queue.name.clone(),
+            value(queue),
+        );
+    }
+    for binding in &definitions.bindings {
+        // Bindings have no settings of their own; arguments are part of their identity.
+        let mut name = format!(
+            "{} -> {} {} ({})",
+            binding.source, binding.destination_type, binding.destination, binding.routing_key
+        );
+        let binding_value = value(binding);
+        if binding_value["arguments"]
+            .as_object()
+            .is_some_and(|arguments| !arguments.is_empty())
+        {
+            name.push_str(&format!(" {}", binding_value["arguments"]));
+        }
+        insert(
+            DefinitionKind::Binding,
+            Some(&binding.vhost),
+            name,
+            Value::Null,
+        );
+    }
+    entries
+}
+
+/// Collects the differing leaves of two JSON values as dotted paths.
+fn compare_values(prefix: &str, before: &Value, after: &Value, fields: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                // Identity fields are part of the object name already.
+                if prefix.is_empty() && matches!(key.as_str(), "name" | "vhost" | "user") {
+                    continue;
+                }
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                compare_values(
+                    &path,
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    fields,
+                );
+            }
+        }
+        _ if before != after => {
+            let hidden = prefix == "password_hash";
+            let mask = |value: &Value| {
+                if hidden && !value.is_null() {
+                    Value::from("<hidden>")
+                } else {
+                    value.clone()
+                }
+            };
+            fields.push(FieldChange {
+                field: prefix.to_string(),
+                before: mask(before),
+                after: mask(after),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn value<T: Serialize>(object: &T) -> Value {
+    serde_json::to_value(object).unwrap_or(Value::Null)
+}
@@ -8,7 +8,10 @@ use thiserror::Error;
 // Define the modules
 pub mod api;
 pub mod collector;
+pub mod diff;
 pub mod export;
+pub mod restore;
+pub mod types;
 
 // Define the data structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,403 @@
+// rabbitmq-info/src/restore.rs
+
+use rabbitmq_config::{
+    field_table_to_arguments, BindingDefinition, ExchangeDefinition, PermissionDefinition,
+    QueueDefinition, RabbitMQServerDefinition, UserDefinition, VhostDefinition,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::api::{ApiError, PolicySettings, RabbitMQApiClient};
+use crate::InfoError;
+
+/// The object types a definitions snapshot can restore, in the order they are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefinitionKind {
+    Vhost,
+    User,
+    Permission,
+    Policy,
+    Exchange,
+    Queue,
+    Binding,
+}
+
+impl DefinitionKind {
+    pub const ALL: [DefinitionKind; 7] = [
+        DefinitionKind::Vhost,
+        DefinitionKind::User,
+        DefinitionKind::Permission,
+        DefinitionKind::Policy,
+        DefinitionKind::Exchange,
+        DefinitionKind::Queue,
+        DefinitionKind::Binding,
+    ];
+}
+
+impl fmt::Display for DefinitionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DefinitionKind::Vhost => "vhost",
+            DefinitionKind::User => "user",
+            DefinitionKind::Permission => "permission",
+            DefinitionKind::Policy => "policy",
+            DefinitionKind::Exchange => "exchange",
+            DefinitionKind::Queue => "queue",
+            DefinitionKind::Binding => "binding",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for DefinitionKind {
+    type Err = String;
+
+    /// Accepts singular and plural names, e.g. `queue` and `queues`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let singular = match s.to_lowercase().as_str() {
+            "policies" => "policy".to_string(),
+            other => other.trim_end_matches('s').to_string(),
+        };
+        DefinitionKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == singular)
+            .ok_or_else(|| format!("Unknown object type: {}", s))
+    }
+}
+
+/// Selects which parts of a snapshot are restored.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreFilter {
+    /// Only restore objects in these vhosts. Empty means all vhosts.
+    pub vhosts: Vec<String>,
+    /// Only restore these object types. Empty means all types.
+    pub kinds: HashSet<DefinitionKind>,
+}
+
+impl RestoreFilter {
+    fn includes(&self, kind: DefinitionKind, vhost: Option<&str>) -> bool {
+        let kind_selected = self.kinds.is_empty() || self.kinds.contains(&kind);
+        // Users are not scoped to a vhost, so a vhost filter does not exclude them.
+        let vhost_selected = match vhost {
+            Some(vhost) => self.vhosts.is_empty() || self.vhosts.iter().any(|v| v == vhost),
+            None => true,
+        };
+        kind_selected && vhost_selected
+    }
+}
+
+/// How restoring an object changes the broker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreChange {
+    /// The object does not exist on the broker.
+    Create,
+    /// The object exists with different settings and is overwritten.
+    Update,
+    /// The object already matches the snapshot and is skipped.
+    Unchanged,
+}
+
+impl fmt::Display for RestoreChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreChange::Create => write!(f, "create"),
+            RestoreChange::Update => write!(f, "update"),
+            RestoreChange::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/// A snapshot object and what restoring it would do.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreAction {
+    pub kind: DefinitionKind,
+    pub vhost: Option<String>,
+    pub name: String,
+    pub change: RestoreChange,
+    #[serde(skip)]
+    object: RestoreObject,
+}
+
+#[derive(Debug, Clone)]
+enum RestoreObject {
+    Vhost(VhostDefinition),
+    User(UserDefinition),
+    Permission(PermissionDefinition),
+    Policy {
+        vhost: String,
+        name: String,
+        settings: PolicySettings,
+    },
+    Exchange(ExchangeDefinition),
+    Queue(QueueDefinition),
+    Binding(BindingDefinition),
+}
+
+/// The actions needed to restore a snapshot, in the order they are applied.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestorePlan {
+    pub actions: Vec<RestoreAction>,
+}
+
+impl RestorePlan {
+    /// The actions that change the broker.
+    pub fn changes(&self) -> impl Iterator<Item = &RestoreAction> {
+        self.actions
+            .iter()
+            .filter(|action| action.change != RestoreChange::Unchanged)
+    }
+
+    /// Renders the changes one per line, e.g. `create queue orders (vhost /)`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for action in self.changes() {
+            text.push_str(&format!(
+                "{} {} {}",
+                action.change, action.kind, action.name
+            ));
+            if let Some(vhost) = &action.vhost {
+                text.push_str(&format!(" (vhost {})", vhost));
+            }
+            text.push('\n');
+        }
+        let unchanged = self.actions.len() - self.changes().count();
+        text.push_str(&format!(
+            "{} change(s), {} object(s) unchanged.\n",
+            self.changes().count(),
+            unchanged
+        ));
+        text
+    }
+}
+
+/// Reads a snapshot written by `dump-state`.
+pub fn load_snapshot(path: &Path) -> Result<RabbitMQServerDefinition, InfoError> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Compares a snapshot with the broker's current definitions.
+///
+/// Restoring only creates and overwrites objects; objects missing from the snapshot are
+/// left alone. Policies that cannot be read from the snapshot are skipped.
+pub fn plan_restore(
+    snapshot: &RabbitMQServerDefinition,
+    live: &RabbitMQServerDefinition,
+    filter: &RestoreFilter,
+) -> RestorePlan {
+    let mut plan = RestorePlan::default();
+    let mut push = |kind: DefinitionKind,
+                    vhost: Option<&str>,
+                    name: String,
+                    change: RestoreChange,
+                    object: RestoreObject| {
+        if filter.includes(kind, vhost) {
+            plan.actions.push(RestoreAction {
+                kind,
+                vhost: vhost.map(str::to_string),
+                name,
+                change,
+                object,
+            });
+        }
+    };
+
+    for vhost in &snapshot.vhosts {
+        let exists = live.vhosts.iter().any(|live| live.name == vhost.name);
+        let change = if exists {
+            RestoreChange::Unchanged
+        } else {
+            RestoreChange::Create
+        };
+        push(
+            DefinitionKind::Vhost,
+            Some(&vhost.name),
+            vhost.name.clone(),
+            change,
+            RestoreObject::Vhost(vhost.clone()),
+        );
+    }
+    for user in &snapshot.users {
+        let live_user = live.users.iter().find(|live| live.name == user.name);
+        let change = compare(live_user.map(|live| {
+            live.password_hash == user.password_hash
+                && live.hashing_algorithm == user.hashing_algorithm
+                && same_tags(&live.tags, &user.tags)
+        }));
+        push(
+            DefinitionKind::User,
+            None,
+            user.name.clone(),
+            change,
+            RestoreObject::User(user.clone()),
+        );
+    }
+    for permission in &snapshot.permissions {
+        let live_permission = live
+            .permissions
+            .iter()
+            .find(|live| live.user == permission.user && live.vhost == permission.vhost);
+        let change = compare(live_permission.map(|live| {
+            live.configure == permission.configure
+                && live.write == permission.write
+                && live.read == permission.read
+        }));
+        push(
+            DefinitionKind::Permission,
+            Some(&permission.vhost),
+            permission.user.clone(),
+            change,
+            RestoreObject::Permission(permission.clone()),
+        );
+    }
+    for policy in &snapshot.policies {
+        let Some((vhost, name, settings)) = read_policy(policy) else {
+            continue;
+        };
+        let live_policy = live
+            .policies
+            .iter()
+            .filter_map(read_policy)
+            .find(|(live_vhost, live_name, _)| *live_vhost == vhost && *live_name == name);
+        let change = compare(live_policy.map(|(_, _, live)| {
+            serde_json::to_value(live).ok() == serde_json::to_value(&settings).ok()
+        }));
+        push(
+            DefinitionKind::Policy,
+            Some(&vhost),
+            name.clone(),
+            change,
+            RestoreObject::Policy {
+                vhost: vhost.clone(),
+                name,
+                settings,
+            },
+        );
+    }
+    for exchange in &snapshot.exchanges {
+        let live_exchange = live
+            .exchanges
+            .iter()
+            .find(|live| live.vhost == exchange.vhost && live.name == exchange.name);
+        let change = compare(live_exchange.map(|live| {
+            live.r#type == exchange.r#type
+                && live.durable == exchange.durable
+                && live.auto_delete == exchange.auto_delete
+                && live.internal == exchange.internal
+                && field_table_to_arguments(&live.arguments)
+                    == field_table_to_arguments(&exchange.arguments)
+        }));
+        push(
+            DefinitionKind::Exchange,
+            Some(&exchange.vhost),
+            exchange.name.clone(),
+            change,
+            RestoreObject::Exchange(exchange.clone()),
+        );
+    }
+    for queue in &snapshot.queues {
+        let live_queue = live
+            .queues
+            .iter()
+            .find(|live| live.vhost == queue.vhost && live.name == queue.name);
+        let change = compare(live_queue.map(|live| {
+            live.durable == queue.durable
+                && live.auto_delete == queue.auto_delete
+                && field_table_to_arguments(&live.arguments)
+                    == field_table_to_arguments(&queue.arguments)
+        }));
+        push(
+            DefinitionKind::Queue,
+            Some(&queue.vhost),
+            queue.name.clone(),
+            change,
+            RestoreObject::Queue(queue.clone()),
+        );
+    }
+    for binding in &snapshot.bindings {
+        let exists = live.bindings.iter().any(|live| {
+            live.vhost == binding.vhost
+                && live.source == binding.source
+                && live.destination == binding.destination
+                && live.destination_type == binding.destination_type
+                && live.routing_key == binding.routing_key
+                && field_table_to_arguments(&live.arguments)
+                    == field_table_to_arguments(&binding.arguments)
+        });
+        let change = if exists {
+            RestoreChange::Unchanged
+        } else {
+            RestoreChange::Create
+        };
+        push(
+            DefinitionKind::Binding,
+            Some(&binding.vhost),
+            format!(
+                "{} -> {} ({})",
+                binding.source, binding.destination, binding.routing_key
+            ),
+            change,
+            RestoreObject::Binding(binding.clone()),
+        );
+    }
+
+    plan
+}
+
+/// Applies the changes of a plan through the management API, stopping at the first
+/// failure. Returns the number of objects created or updated.
+pub async fn apply_restore(
+    client: &RabbitMQApiClient,
+    plan: &RestorePlan,
+) -> Result<usize, ApiError> {
+    let mut applied = 0;
+    for action in plan.changes() {
+        match &action.object {
+            RestoreObject::Vhost(vhost) => client.put_vhost(&vhost.name).await?,
+            RestoreObject::User(user) => client.put_user_definition(user).await?,
+            RestoreObject::Permission(permission) => client.put_permissions(permission).await?,
+            RestoreObject::Policy {
+                vhost,
+                name,
+                settings,
+            } => client.put_policy(vhost, name, settings).await?,
+            RestoreObject::Exchange(exchange) => client.put_exchange(exchange).await?,
+            RestoreObject::Queue(queue) => client.put_queue(queue).await?,
+            RestoreObject::Binding(binding) => client.create_binding(binding).await?,
+        }
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+fn compare(matches_live: Option<bool>) -> RestoreChange {
+    match matches_live {
+        None => RestoreChange::Create,
+        Some(true) => RestoreChange::Unchanged,
+        Some(false) => RestoreChange::Update,
+    }
+}
+
+fn same_tags(a: &str, b: &str) -> bool {
+    let tags = |tags: &str| -> HashSet<String> {
+        tags.split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    };
+    tags(a) == tags(b)
+}
+
+fn read_policy(policy: &Value) -> Option<(String, String, PolicySettings)> {
+    let vhost = policy["vhost"].as_str()?.to_string();
+    let name = policy["name"].as_str()?.to_string();
+    let settings = serde_json::from_value(policy.clone()).ok()?;
+    Some((vhost, name, settings))
+}
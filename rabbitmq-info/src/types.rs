@@ -0,0 +1,315 @@
+// rabbitmq-info/src/types.rs
+
+//! Typed responses of the management HTTP API.
+//!
+//! Every type keeps the fields it does not model in `extra`, so nothing the broker
+//! returns is lost. Fields that are missing when statistics are disabled or when a
+//! `columns` selection leaves them out are `Option`s or fall back to their defaults.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+/// A message rate, e.g. the `publish_details` of `message_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateDetails {
+    /// Messages per second.
+    pub rate: f64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Message counters and rates of a queue, exchange, channel or the whole broker.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageStats {
+    pub publish: Option<u64>,
+    pub publish_details: Option<RateDetails>,
+    pub publish_in: Option<u64>,
+    pub publish_in_details: Option<RateDetails>,
+    pub publish_out: Option<u64>,
+    pub publish_out_details: Option<RateDetails>,
+    pub confirm: Option<u64>,
+    pub confirm_details: Option<RateDetails>,
+    pub deliver: Option<u64>,
+    pub deliver_details: Option<RateDetails>,
+    pub deliver_get: Option<u64>,
+    pub deliver_get_details: Option<RateDetails>,
+    pub deliver_no_ack: Option<u64>,
+    pub get: Option<u64>,
+    pub get_no_ack: Option<u64>,
+    pub redeliver: Option<u64>,
+    pub redeliver_details: Option<RateDetails>,
+    pub ack: Option<u64>,
+    pub ack_details: Option<RateDetails>,
+    pub return_unroutable: Option<u64>,
+    pub return_unroutable_details: Option<RateDetails>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl MessageStats {
+    /// Published messages per second, or 0 when unknown.
+    pub fn publish_rate(&self) -> f64 {
+        rate(&self.publish_details)
+    }
+
+    /// Delivered and fetched messages per second, or 0 when unknown.
+    pub fn deliver_rate(&self) -> f64 {
+        rate(&self.deliver_get_details)
+    }
+
+    /// Acknowledged messages per second, or 0 when unknown.
+    pub fn ack_rate(&self) -> f64 {
+        rate(&self.ack_details)
+    }
+
+    /// Redelivered messages per second, or 0 when unknown.
+    pub fn redeliver_rate(&self) -> f64 {
+        rate(&self.redeliver_details)
+    }
+}
+
+/// Direct (in-node) connections report their peer port as `"unknown"`.
+fn port_or_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u16>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    Ok(value.as_u64().and_then(|port| u16::try_from(port).ok()))
+}
+
+fn rate(details: &Option<RateDetails>) -> f64 {
+    details.as_ref().map_or(0.0, |details| details.rate)
+}
+
+/// `GET /api/overview`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overview {
+    pub management_version: String,
+    pub rabbitmq_version: String,
+    pub erlang_version: String,
+    pub cluster_name: String,
+    pub node: String,
+    pub message_stats: Option<MessageStats>,
+    pub queue_totals: Option<QueueTotals>,
+    pub object_totals: Option<ObjectTotals>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Message counts over all queues.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueTotals {
+    pub messages: u64,
+    pub messages_ready: u64,
+    pub messages_unacknowledged: u64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Object counts over the whole broker.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectTotals {
+    pub connections: u64,
+    pub channels: u64,
+    pub exchanges: u64,
+    pub queues: u64,
+    pub consumers: u64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/queues`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Queue {
+    pub name: String,
+    pub vhost: String,
+    /// `classic`, `quorum` or `stream`.
+    #[serde(rename = "type")]
+    pub queue_type: Option<String>,
+    pub durable: bool,
+    pub auto_delete: bool,
+    pub exclusive: bool,
+    pub arguments: Map<String, Value>,
+    pub node: Option<String>,
+    /// E.g. `running` or `idle`.
+    pub state: Option<String>,
+    pub policy: Option<String>,
+    pub messages: Option<u64>,
+    pub messages_ready: Option<u64>,
+    pub messages_unacknowledged: Option<u64>,
+    pub consumers: Option<u64>,
+    pub consumer_utilisation: Option<f64>,
+    /// Bytes of memory used by the queue process.
+    pub memory: Option<u64>,
+    pub idle_since: Option<String>,
+    pub message_stats: Option<MessageStats>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/exchanges`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Exchange {
+    pub name: String,
+    pub vhost: String,
+    #[serde(rename = "type")]
+    pub exchange_type: String,
+    pub durable: bool,
+    pub auto_delete: bool,
+    pub internal: bool,
+    pub arguments: Map<String, Value>,
+    pub policy: Option<String>,
+    pub message_stats: Option<MessageStats>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/bindings`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Binding {
+    pub source: String,
+    pub vhost: String,
+    pub destination: String,
+    /// `queue` or `exchange`.
+    pub destination_type: String,
+    pub routing_key: String,
+    pub arguments: Map<String, Value>,
+    /// Identifies the binding in `DELETE /api/bindings/...` requests.
+    pub properties_key: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/connections`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Connection {
+    pub name: String,
+    pub vhost: String,
+    pub user: String,
+    pub node: Option<String>,
+    pub state: Option<String>,
+    pub protocol: Option<String>,
+    pub peer_host: Option<String>,
+    #[serde(deserialize_with = "port_or_none")]
+    pub peer_port: Option<u16>,
+    pub ssl: Option<bool>,
+    pub channels: Option<u64>,
+    /// Milliseconds since the epoch.
+    pub connected_at: Option<u64>,
+    pub recv_oct: Option<u64>,
+    pub send_oct: Option<u64>,
+    pub client_properties: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/channels`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Channel {
+    pub name: String,
+    pub number: u64,
+    pub vhost: String,
+    pub user: String,
+    pub node: Option<String>,
+    pub state: Option<String>,
+    pub consumer_count: Option<u64>,
+    pub messages_unacknowledged: Option<u64>,
+    pub messages_unconfirmed: Option<u64>,
+    pub prefetch_count: Option<u64>,
+    pub confirm: Option<bool>,
+    pub transactional: Option<bool>,
+    pub connection_details: Option<ConnectionDetails>,
+    pub message_stats: Option<MessageStats>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The connection a channel belongs to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionDetails {
+    pub name: String,
+    pub peer_host: Option<String>,
+    #[serde(deserialize_with = "port_or_none")]
+    pub peer_port: Option<u16>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/consumers`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Consumer {
+    pub consumer_tag: String,
+    pub queue: QueueReference,
+    pub channel_details: ChannelDetails,
+    pub ack_required: bool,
+    pub exclusive: bool,
+    pub prefetch_count: Option<u64>,
+    pub active: Option<bool>,
+    pub arguments: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The queue a consumer reads from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueReference {
+    pub name: String,
+    pub vhost: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The channel a consumer was registered on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelDetails {
+    pub name: String,
+    pub number: u64,
+    pub connection_name: String,
+    pub user: Option<String>,
+    pub node: Option<String>,
+    pub peer_host: Option<String>,
+    #[serde(deserialize_with = "port_or_none")]
+    pub peer_port: Option<u16>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item of `GET /api/nodes`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Node {
+    pub name: String,
+    /// `disc` or `ram`.
+    #[serde(rename = "type")]
+    pub node_type: Option<String>,
+    pub running: bool,
+    /// Milliseconds since the node started.
+    pub uptime: Option<u64>,
+    pub mem_used: Option<u64>,
+    pub mem_limit: Option<u64>,
+    pub mem_alarm: Option<bool>,
+    pub disk_free: Option<u64>,
+    pub disk_free_limit: Option<u64>,
+    pub disk_free_alarm: Option<bool>,
+    pub fd_used: Option<u64>,
+    pub fd_total: Option<u64>,
+    pub sockets_used: Option<u64>,
+    pub sockets_total: Option<u64>,
+    pub proc_used: Option<u64>,
+    pub proc_total: Option<u64>,
+    /// Nodes this node cannot reach, i.e. a network partition.
+    pub partitions: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,244 @@
+use futures_util::TryStreamExt;
+use rabbitmq_config::{BindingDefinition, QueueDefinition, RabbitMQConfig};
+use rabbitmq_info::api::{
+    ApiError, ListQuery, ListResource, Page, PolicySettings, RabbitMQApiClient,
+};
+use rabbitmq_info::types::Exchange;
+use serde_json::json;
+use wiremock::matchers::{basic_auth, body_json, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client_for(server: &MockServer) -> RabbitMQApiClient {
+    let config = RabbitMQConfig {
+        host: server.address().ip().to_string(),
+        management_port: server.address().port(),
+        username: "admin".to_string(),
+        password: "secret".to_string(),
+        ..Default::default()
+    };
+    RabbitMQApiClient::new(&config).unwrap()
+}
+
+#[tokio::test]
+async fn test_put_and_delete_vhost_and_user() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/api/vhosts/orders%2Dv2"))
+        .and(basic_auth("admin", "secret"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/api/users/svc"))
+        .and(body_json(json!({ "password": "pw", "tags": "monitoring" })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/vhosts/orders%2Dv2"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    client.put_vhost("orders-v2").await.unwrap();
+    client.put_user("svc", "pw", "monitoring").await.unwrap();
+    client.delete_vhost("orders-v2").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_put_policy_and_queue_in_default_vhost() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/api/policies/%2F/ha"))
+        .and(body_json(json!({
+            "pattern": "^orders\\.",
+            "definition": { "max-length": 1000 },
+            "priority": 1,
+            "apply-to": "queues"
+        })))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/api/queues/%2F/orders"))
+        .and(body_json(json!({
+            "durable": true,
+            "auto_delete": false,
+            "arguments": { "x-max-priority": 5 }
+        })))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/queues/%2F/orders"))
+        .and(query_param("if-empty", "true"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let policy = PolicySettings {
+        pattern: "^orders\\.".to_string(),
+        definition: json!({ "max-length": 1000 }),
+        priority: 1,
+        apply_to: "queues".to_string(),
+    };
+    client.put_policy("/", "ha", &policy).await.unwrap();
+
+    let queue: QueueDefinition = serde_json::from_value(json!({
+        "name": "orders",
+        "vhost": "/",
+        "durable": true,
+        "auto_delete": false,
+        "arguments": { "x-max-priority": 5 }
+    }))
+    .unwrap();
+    client.put_queue(&queue).await.unwrap();
+    client
+        .delete_queue("/", "orders", false, true)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_binding_looks_up_properties_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/bindings/%2F/e/events/q/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "routing_key": "order.placed", "arguments": {}, "properties_key": "order.placed" },
+            { "routing_key": "order.#", "arguments": {}, "properties_key": "order.%23" }
+        ])))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/bindings/%2F/e/events/q/orders/order%2E%2523"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let binding: BindingDefinition = serde_json::from_value(json!({
+        "source": "events",
+        "vhost": "/",
+        "destination": "orders",
+        "destination_type": "queue",
+        "routing_key": "order.#",
+        "arguments": {}
+    }))
+    .unwrap();
+    let client = client_for(&server);
+    client.delete_binding(&binding).await.unwrap();
+
+    let missing = BindingDefinition {
+        routing_key: "order.cancelled".to_string(),
+        ..binding
+    };
+    assert!(matches!(
+        client.delete_binding(&missing).await,
+        Err(ApiError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_error_reason_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/definitions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "bad_request",
+            "reason": "Validation failed"
+        })))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    match client.upload_definitions(&json!({ "queues": [] })).await {
+        Err(ApiError::StatusError { status, reason, .. }) => {
+            assert_eq!(status, 400);
+            assert_eq!(reason, "Validation failed");
+        }
+        other => panic!("expected a status error, got {:?}", other),
+    }
+}
+
+fn page(items: serde_json::Value, page: u32, page_count: u32) -> ResponseTemplate {
+    let item_count = items.as_array().unwrap().len();
+    ResponseTemplate::new(200).set_body_json(json!({
+        "items": items,
+        "page": page,
+        "page_count": page_count,
+        "page_size": 2,
+        "filtered_count": 3,
+        "item_count": item_count,
+        "total_count": 10
+    }))
+}
+
+#[tokio::test]
+async fn test_list_queues_walks_all_pages() {
+    let server = MockServer::start().await;
+    for (number, items) in [
+        (1, json!([{ "name": "orders.a" }, { "name": "orders.b" }])),
+        (2, json!([{ "name": "orders.c" }])),
+    ] {
+        Mock::given(method("GET"))
+            .and(path("/api/queues/%2F"))
+            .and(query_param("page", number.to_string()))
+            .and(query_param("page_size", "2"))
+            .and(query_param("name", "^orders\\."))
+            .and(query_param("use_regex", "true"))
+            .and(query_param("columns", "name,messages"))
+            .respond_with(page(items, number, 2))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let query = ListQuery {
+        vhost: Some("/".to_string()),
+        page_size: 2,
+        name: Some("^orders\\.".to_string()),
+        use_regex: true,
+        columns: vec!["name".to_string(), "messages".to_string()],
+        ..Default::default()
+    };
+    let client = client_for(&server);
+    let queues = client.list_queues(&query).await.unwrap();
+    let names: Vec<&str> = queues.iter().map(|q| q.name.as_str()).collect();
+    assert_eq!(names, vec!["orders.a", "orders.b", "orders.c"]);
+}
+
+#[tokio::test]
+async fn test_pages_stream_starts_at_requested_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/exchanges"))
+        .and(query_param("page", "2"))
+        .respond_with(page(json!([{ "name": "events" }]), 2, 2))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let query = ListQuery {
+        page: 2,
+        page_size: 2,
+        ..Default::default()
+    };
+    let client = client_for(&server);
+    let pages: Vec<Page<Exchange>> = client
+        .pages(ListResource::Exchanges, &query)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].filtered_count, 3);
+    assert_eq!(pages[0].items[0].name, "events");
+}
@@ -0,0 +1,101 @@
+use rabbitmq_config::RabbitMQServerDefinition;
+use rabbitmq_info::diff::{diff_definitions, ChangeKind, FieldChange};
+use rabbitmq_info::restore::DefinitionKind;
+use serde_json::json;
+
+fn definitions(
+    users: serde_json::Value,
+    queues: serde_json::Value,
+    bindings: serde_json::Value,
+) -> RabbitMQServerDefinition {
+    serde_json::from_value(json!({
+        "rabbitmq_version": "3.13.0",
+        "users": users,
+        "vhosts": [{ "name": "/" }],
+        "permissions": [],
+        "topic_permissions": [],
+        "parameters": [],
+        "policies": [],
+        "queues": queues,
+        "exchanges": [],
+        "bindings": bindings
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_identical_snapshots_have_no_differences() {
+    let snapshot = definitions(json!([]), json!([]), json!([]));
+    let diff = diff_definitions(&snapshot, &snapshot);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_text(true), "No differences.\n");
+}
+
+#[test]
+fn test_diff_reports_added_removed_and_changed_objects() {
+    let before = definitions(
+        json!([{ "name": "svc", "password_hash": "old", "hashing_algorithm": "rabbit_password_hashing_sha256", "tags": "" }]),
+        json!([
+            { "name": "orders", "vhost": "/", "durable": true, "auto_delete": false, "arguments": { "x-max-priority": 5 } },
+            { "name": "legacy", "vhost": "/", "durable": true, "auto_delete": false, "arguments": {} }
+        ]),
+        json!([]),
+    );
+    let after = definitions(
+        json!([{ "name": "svc", "password_hash": "new", "hashing_algorithm": "rabbit_password_hashing_sha256", "tags": "" }]),
+        json!([
+            { "name": "orders", "vhost": "/", "durable": true, "auto_delete": false, "arguments": { "x-max-priority": 10, "x-queue-type": "classic" } }
+        ]),
+        json!([{ "source": "events", "vhost": "/", "destination": "orders", "destination_type": "queue", "routing_key": "order.#", "arguments": {} }]),
+    );
+
+    let diff = diff_definitions(&before, &after);
+    let summary: Vec<(DefinitionKind, &str, ChangeKind)> = diff
+        .changes
+        .iter()
+        .map(|change| (change.kind, change.name.as_str(), change.change))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (DefinitionKind::User, "svc", ChangeKind::Changed),
+            (DefinitionKind::Queue, "legacy", ChangeKind::Removed),
+            (DefinitionKind::Queue, "orders", ChangeKind::Changed),
+            (
+                DefinitionKind::Binding,
+                "events -> queue orders (order.#)",
+                ChangeKind::Added
+            ),
+        ]
+    );
+
+    // Password hashes are never shown.
+    assert_eq!(diff.changes[0].fields[0].before, json!("<hidden>"));
+    assert_eq!(
+        diff.changes[2].fields,
+        vec![
+            FieldChange {
+                field: "arguments.x-max-priority".to_string(),
+                before: json!(5),
+                after: json!(10),
+            },
+            FieldChange {
+                field: "arguments.x-queue-type".to_string(),
+                before: json!(null),
+                after: json!("classic"),
+            },
+        ]
+    );
+
+    let text = diff.to_text(false);
+    assert!(text.contains("- queue legacy (vhost /)\n"));
+    assert!(text.contains("    arguments.x-max-priority: 5 -> 10\n"));
+    assert!(text.ends_with("1 added, 1 removed, 2 changed.\n"));
+    assert!(diff
+        .to_text(true)
+        .contains("\x1b[31m- queue legacy (vhost /)\x1b[0m"));
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["changes"][1]["change"], "removed");
+    assert_eq!(json["changes"][1]["kind"], "queue");
+}
@@ -0,0 +1,71 @@
+use std::net::TcpListener;
+
+use rabbitmq_config::{Endpoint, RabbitMQConfig};
+use rabbitmq_info::api::{ApiError, RabbitMQApiClient};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A local endpoint with nothing listening on it.
+fn dead_endpoint() -> Endpoint {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    Endpoint::new("127.0.0.1", port)
+}
+
+fn live_endpoint(server: &MockServer) -> Endpoint {
+    Endpoint::new(server.address().ip().to_string(), server.address().port())
+}
+
+#[tokio::test]
+async fn test_requests_fail_over_to_the_next_node() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/overview"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "node": "rabbit@node2" })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let dead = dead_endpoint();
+    let live = live_endpoint(&server);
+    let config = RabbitMQConfig {
+        management_endpoints: vec![dead.clone(), live.clone()],
+        ..Default::default()
+    };
+    let client = RabbitMQApiClient::new(&config).unwrap();
+    assert_eq!(client.base_url(), format!("http://{}", dead));
+
+    let overview = client.get_overview().await.unwrap();
+    assert_eq!(overview.node, "rabbit@node2");
+    assert_eq!(client.current_endpoint(), Some(live.clone()));
+    assert_eq!(client.failed_endpoints(), vec![dead.clone()]);
+    assert_eq!(client.base_url(), format!("http://{}", live));
+
+    // The failed node is now tried last, so the next request goes straight to the live one.
+    client.get_overview().await.unwrap();
+    assert_eq!(client.failed_endpoints(), vec![dead]);
+}
+
+#[tokio::test]
+async fn test_all_nodes_unreachable() {
+    let (first, second) = (dead_endpoint(), dead_endpoint());
+    let config = RabbitMQConfig {
+        management_endpoints: vec![first.clone(), second.clone()],
+        ..Default::default()
+    };
+    let client = RabbitMQApiClient::new(&config).unwrap();
+
+    match client.get_overview().await {
+        Err(ApiError::Unreachable(message)) => {
+            assert!(message.contains(&first.to_string()), "{}", message);
+            assert!(message.contains(&second.to_string()), "{}", message);
+        }
+        other => panic!("expected Unreachable, got {:?}", other),
+    }
+    assert_eq!(client.current_endpoint(), None);
+    assert_eq!(client.failed_endpoints(), vec![first, second]);
+}
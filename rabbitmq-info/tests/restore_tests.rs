@@ -0,0 +1,128 @@
+use rabbitmq_config::{RabbitMQConfig, RabbitMQServerDefinition};
+use rabbitmq_info::api::RabbitMQApiClient;
+use rabbitmq_info::restore::{
+    apply_restore, plan_restore, DefinitionKind, RestoreChange, RestoreFilter,
+};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn definitions(queues: serde_json::Value) -> RabbitMQServerDefinition {
+    serde_json::from_value(json!({
+        "rabbitmq_version": "3.13.0",
+        "users": [{ "name": "svc", "password_hash": "abc", "hashing_algorithm": "rabbit_password_hashing_sha256", "tags": ["monitoring"] }],
+        "vhosts": [{ "name": "/" }, { "name": "orders" }],
+        "permissions": [],
+        "topic_permissions": [],
+        "parameters": [],
+        "policies": [{ "vhost": "orders", "name": "ttl", "pattern": ".*", "apply-to": "queues", "definition": { "message-ttl": 60000 }, "priority": 0 }],
+        "queues": queues,
+        "exchanges": [],
+        "bindings": []
+    }))
+    .unwrap()
+}
+
+fn snapshot() -> RabbitMQServerDefinition {
+    definitions(json!([
+        { "name": "jobs", "vhost": "/", "durable": true, "auto_delete": false, "arguments": {} },
+        { "name": "placed", "vhost": "orders", "durable": true, "auto_delete": false, "arguments": { "x-max-priority": 5 } },
+        { "name": "cancelled", "vhost": "orders", "durable": true, "auto_delete": false, "arguments": {} }
+    ]))
+}
+
+fn live() -> RabbitMQServerDefinition {
+    let mut live = definitions(json!([
+        { "name": "jobs", "vhost": "/", "durable": true, "auto_delete": false, "arguments": {} },
+        { "name": "placed", "vhost": "orders", "durable": true, "auto_delete": false, "arguments": { "x-max-priority": 10 } }
+    ]));
+    live.vhosts.retain(|vhost| vhost.name == "/");
+    live.policies.clear();
+    live
+}
+
+#[test]
+fn test_plan_restore_reports_creates_and_updates() {
+    let plan = plan_restore(&snapshot(), &live(), &RestoreFilter::default());
+    let changes: Vec<(DefinitionKind, &str, RestoreChange)> = plan
+        .changes()
+        .map(|action| (action.kind, action.name.as_str(), action.change))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (DefinitionKind::Vhost, "orders", RestoreChange::Create),
+            (DefinitionKind::Policy, "ttl", RestoreChange::Create),
+            (DefinitionKind::Queue, "placed", RestoreChange::Update),
+            (DefinitionKind::Queue, "cancelled", RestoreChange::Create),
+        ]
+    );
+    assert!(plan
+        .to_text()
+        .ends_with("4 change(s), 3 object(s) unchanged.\n"));
+}
+
+#[test]
+fn test_plan_restore_filters_by_vhost_and_kind() {
+    let filter = RestoreFilter {
+        vhosts: vec!["/".to_string()],
+        kinds: Default::default(),
+    };
+    let plan = plan_restore(&snapshot(), &live(), &filter);
+    assert!(plan
+        .actions
+        .iter()
+        .all(|action| action.vhost.as_deref() != Some("orders")));
+    assert_eq!(plan.changes().count(), 0);
+
+    let filter = RestoreFilter {
+        vhosts: Vec::new(),
+        kinds: ["queues".parse::<DefinitionKind>().unwrap()]
+            .into_iter()
+            .collect(),
+    };
+    let plan = plan_restore(&snapshot(), &live(), &filter);
+    assert!(plan
+        .actions
+        .iter()
+        .all(|action| action.kind == DefinitionKind::Queue));
+    assert_eq!(plan.actions.len(), 3);
+
+    assert!("widgets".parse::<DefinitionKind>().is_err());
+}
+
+#[tokio::test]
+async fn test_apply_restore_only_sends_changes() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/api/queues/orders/cancelled"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/api/queues/%2F/jobs"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let config = RabbitMQConfig {
+        host: server.address().ip().to_string(),
+        management_port: server.address().port(),
+        ..Default::default()
+    };
+    let client = RabbitMQApiClient::new(&config).unwrap();
+
+    let mut current = live();
+    current.queues.retain(|queue| queue.name != "placed");
+    let mut snapshot = snapshot();
+    snapshot.queues.retain(|queue| queue.name != "placed");
+    let filter = RestoreFilter {
+        vhosts: Vec::new(),
+        kinds: [DefinitionKind::Queue].into_iter().collect(),
+    };
+
+    let plan = plan_restore(&snapshot, &current, &filter);
+    assert_eq!(apply_restore(&client, &plan).await.unwrap(), 1);
+}
@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use rabbitmq_config::{RabbitMQConfig, TlsOptions};
+use rabbitmq_info::api::{ApiError, RabbitMQApiClient};
+use rustls::crypto::ring;
+use rustls::ServerConfig;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// Shared with the AMQPS tests of rabbitmq-config, so both use the same certificates.
+const FIXTURES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../rabbitmq-config/tests/fixtures/tls"
+);
+
+fn fixture(name: &str) -> String {
+    format!("{}/{}", FIXTURES, name)
+}
+
+/// Serves a single HTTPS request with the test server certificate and answers `200 OK`.
+async fn start_https_server() -> u16 {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(
+        File::open(fixture("server.pem")).unwrap(),
+    ))
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+    let key = rustls_pemfile::private_key(&mut BufReader::new(
+        File::open(fixture("server-key.pem")).unwrap(),
+    ))
+    .unwrap()
+    .unwrap();
+    let server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let Ok(mut stream) = acceptor.accept(socket).await else {
+            return; // The client refused the certificate.
+        };
+        let mut buffer = [0u8; 4096];
+        let _ = stream.read(&mut buffer).await.unwrap();
+        let body = r#"{"status":"ok"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        stream.shutdown().await.unwrap();
+    });
+    port
+}
+
+fn config(port: u16) -> RabbitMQConfig {
+    RabbitMQConfig {
+        host: "localhost".to_string(),
+        management_port: port,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_https_with_custom_ca_and_client_certificate() {
+    let port = start_https_server().await;
+    let tls = TlsOptions {
+        verify_hostname: true,
+        ca_cert_path: fixture("ca.pem"),
+        client_cert_path: Some(fixture("client.pem")),
+        client_key_path: Some(fixture("client-key.pem")),
+    };
+
+    let client = RabbitMQApiClient::with_tls(&config(port), Some(&tls)).unwrap();
+    assert_eq!(client.base_url(), format!("https://localhost:{}", port));
+    assert!(client.is_alive().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_https_rejects_unknown_ca() {
+    let port = start_https_server().await;
+    let client = RabbitMQApiClient::with_tls(&config(port), None).unwrap();
+    assert!(matches!(
+        client.is_alive().await,
+        Err(ApiError::RequestError(_))
+    ));
+}
+
+#[test]
+fn test_invalid_tls_options_are_reported() {
+    let missing_ca = TlsOptions {
+        ca_cert_path: fixture("missing.pem"),
+        ..Default::default()
+    };
+    assert!(matches!(
+        RabbitMQApiClient::with_tls(&config(15671), Some(&missing_ca)),
+        Err(ApiError::TlsError(_))
+    ));
+
+    let cert_without_key = TlsOptions {
+        ca_cert_path: fixture("ca.pem"),
+        client_cert_path: Some(fixture("client.pem")),
+        ..Default::default()
+    };
+    assert!(matches!(
+        RabbitMQApiClient::with_tls(&config(15671), Some(&cert_without_key)),
+        Err(ApiError::TlsError(_))
+    ));
+}
+
+#[test]
+fn test_plain_http_unless_tls_is_enabled() {
+    let client = RabbitMQApiClient::from_settings(&config(15672), false, None).unwrap();
+    assert_eq!(client.base_url(), "http://localhost:15672");
+}
@@ -0,0 +1,118 @@
+use rabbitmq_info::types::{Connection, Consumer, Node, Overview, Queue};
+use serde_json::{json, Value};
+
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[test]
+fn test_queue_keeps_unknown_fields_and_reads_rates() {
+    let value = json!({
+        "name": "orders",
+        "vhost": "/",
+        "type": "quorum",
+        "durable": true,
+        "auto_delete": false,
+        "exclusive": false,
+        "arguments": { "x-queue-type": "quorum" },
+        "state": "running",
+        "messages": 12,
+        "messages_ready": 10,
+        "messages_unacknowledged": 2,
+        "consumers": 3,
+        "consumer_utilisation": null,
+        "memory": 55000,
+        "message_stats": {
+            "publish": 100,
+            "publish_details": { "rate": 2.5 },
+            "deliver_get_details": { "rate": 1.5 },
+            "ack_details": { "rate": 1.0 },
+            "drop_unroutable": 0
+        },
+        "leader": "rabbit@node1",
+        "members": ["rabbit@node1", "rabbit@node2"]
+    });
+
+    let queue: Queue = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(queue.queue_type.as_deref(), Some("quorum"));
+    assert_eq!(queue.messages, Some(12));
+    assert_eq!(queue.consumer_utilisation, None);
+
+    let stats = queue.message_stats.as_ref().unwrap();
+    assert_eq!(stats.publish_rate(), 2.5);
+    assert_eq!(stats.deliver_rate(), 1.5);
+    assert_eq!(stats.redeliver_rate(), 0.0);
+    assert_eq!(stats.extra["drop_unroutable"], 0);
+    assert_eq!(queue.extra["leader"], "rabbit@node1");
+
+    // Serializing gives back everything that was read; unset options come back as nulls.
+    let round_trip = without_nulls(serde_json::to_value(&queue).unwrap());
+    assert_eq!(round_trip, without_nulls(value));
+}
+
+#[test]
+fn test_partial_column_selection_deserializes() {
+    let queue: Queue = serde_json::from_value(json!({ "name": "orders", "messages": 4 })).unwrap();
+    assert_eq!(queue.name, "orders");
+    assert_eq!(queue.consumers, None);
+    assert!(queue.arguments.is_empty());
+}
+
+#[test]
+fn test_overview_connections_consumers_and_nodes() {
+    let overview: Overview = serde_json::from_value(json!({
+        "rabbitmq_version": "3.13.0",
+        "node": "rabbit@node1",
+        "queue_totals": { "messages": 5, "messages_ready": 4, "messages_unacknowledged": 1 },
+        "object_totals": { "queues": 2, "consumers": 1 },
+        "listeners": []
+    }))
+    .unwrap();
+    assert_eq!(overview.queue_totals.unwrap().messages_ready, 4);
+    assert_eq!(overview.object_totals.unwrap().queues, 2);
+    assert!(overview.extra.contains_key("listeners"));
+
+    let connection: Connection = serde_json::from_value(json!({
+        "name": "<rabbit@node1.1.2.3>",
+        "vhost": "/",
+        "user": "guest",
+        "peer_host": "unknown",
+        "peer_port": "unknown",
+        "client_properties": { "connection_name": "worker-1" }
+    }))
+    .unwrap();
+    assert_eq!(connection.peer_port, None);
+    assert_eq!(connection.client_properties["connection_name"], "worker-1");
+
+    let consumer: Consumer = serde_json::from_value(json!({
+        "consumer_tag": "ctag-1",
+        "queue": { "name": "orders", "vhost": "/" },
+        "channel_details": { "name": "127.0.0.1:5000 -> 127.0.0.1:5672 (1)", "number": 1, "connection_name": "127.0.0.1:5000 -> 127.0.0.1:5672", "peer_port": 5000 },
+        "ack_required": true,
+        "exclusive": false,
+        "prefetch_count": 10
+    }))
+    .unwrap();
+    assert_eq!(consumer.queue.name, "orders");
+    assert_eq!(consumer.channel_details.peer_port, Some(5000));
+
+    let node: Node = serde_json::from_value(json!({
+        "name": "rabbit@node1",
+        "type": "disc",
+        "running": true,
+        "mem_used": 100,
+        "mem_alarm": false,
+        "partitions": []
+    }))
+    .unwrap();
+    assert!(node.running);
+    assert_eq!(node.node_type.as_deref(), Some("disc"));
+}
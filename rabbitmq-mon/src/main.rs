@@ -1,11 +1,15 @@
 use chrono::Local;
+use clap::Parser;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rabbitmq_config::{get_password, load_config_file, RabbitMQConfig};
-use rabbitmq_info::api::RabbitMQApiClient;
+use rabbitmq_config::{
+    ConfigArgs, PeekOptions, PeekedMessage, RabbitMQClient, RabbitMQFullConfig, StreamOffset,
+};
+use rabbitmq_info::api::{ListQuery, RabbitMQApiClient};
+use rabbitmq_info::types::Queue;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -14,12 +18,51 @@ use ratatui::{
     widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, TableState},
     Terminal,
 };
-use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::io;
 use std::time::{Duration, Instant};
 
+/// Terminal monitor for RabbitMQ queues.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Only show queues in this vhost.
+    #[arg(long)]
+    vhost: Option<String>,
+
+    /// Only show queues whose name matches this regex.
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Number of queues fetched per request.
+    #[arg(long, default_value_t = 500)]
+    page_size: u32,
+
+    /// Number of messages shown when peeking into a queue.
+    #[arg(long, default_value_t = 20)]
+    peek_limit: usize,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+/// The queue fields shown in the list and detail views.
+const QUEUE_COLUMNS: [&str; 11] = [
+    "name",
+    "vhost",
+    "type",
+    "durable",
+    "auto_delete",
+    "messages",
+    "messages_ready",
+    "consumers",
+    "memory",
+    "state",
+    "message_stats",
+];
+
 // Represents the different views or states of the application
 #[derive(Clone, Debug)]
 enum AppView {
@@ -27,10 +70,23 @@ enum AppView {
     QueueDetail { queue_name: String },
 }
 
+/// Messages read from a queue without removing them.
+#[derive(Debug)]
+struct QueuePeek {
+    queue_name: String,
+    messages: Vec<PeekedMessage>,
+}
+
 /// App holds the state of the application
 pub struct App {
     client: RabbitMQApiClient,
-    queues: Vec<Value>,
+    query: ListQuery,
+    file_config: RabbitMQFullConfig,
+    /// AMQP connections used for peeking, opened on first use and keyed by vhost.
+    amqp_clients: HashMap<String, RabbitMQClient>,
+    peek_limit: usize,
+    peek: Option<QueuePeek>,
+    queues: Vec<Queue>,
     should_quit: bool,
     status: String,
     queue_list_state: TableState,
@@ -41,22 +97,34 @@ pub struct App {
 impl fmt::Debug for App {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("App")
+            .field("query", &self.query)
             .field("queues", &self.queues)
             .field("should_quit", &self.should_quit)
             .field("status", &self.status)
             .field("queue_list_state", &self.queue_list_state)
             .field("view_stack", &self.view_stack)
-            .finish_non_exhaustive() // Use this to signify the client is omitted
+            .field("peek", &self.peek)
+            .finish_non_exhaustive() // Use this to signify the clients are omitted
     }
 }
 
 impl App {
-    fn new(client: RabbitMQApiClient) -> Self {
+    fn new(
+        client: RabbitMQApiClient,
+        query: ListQuery,
+        file_config: RabbitMQFullConfig,
+        peek_limit: usize,
+    ) -> Self {
         let mut queue_list_state = TableState::default();
         queue_list_state.select(Some(0));
 
         Self {
             client,
+            query,
+            file_config,
+            amqp_clients: HashMap::new(),
+            peek_limit,
+            peek: None,
             queues: Vec::new(),
             should_quit: false,
             status: "Fetching data...".to_string(),
@@ -86,6 +154,58 @@ impl App {
         // Don't pop the last view
         if self.view_stack.len() > 1 {
             self.view_stack.pop();
+            self.peek = None;
+        }
+    }
+
+    /// Reads the first messages of a queue without consuming them, from the
+    /// beginning of the stream for stream queues.
+    async fn peek_queue(&mut self, queue_name: &str) {
+        let Some(queue) = self.queues.iter().find(|q| q.name == queue_name) else {
+            return;
+        };
+        let vhost = queue.vhost.clone();
+        let options = match queue.queue_type.as_deref() {
+            Some("stream") => PeekOptions::stream(self.peek_limit, StreamOffset::First),
+            _ => PeekOptions::new(self.peek_limit),
+        };
+
+        if !self.amqp_clients.contains_key(&vhost) {
+            let mut config = self.file_config.clone();
+            config.connection.vhost = vhost.clone();
+            match RabbitMQClient::from_config(&config).await {
+                Ok(client) => {
+                    self.amqp_clients.insert(vhost.clone(), client);
+                }
+                Err(e) => {
+                    self.status = format!("Error connecting to vhost '{}': {}", vhost, e);
+                    return;
+                }
+            }
+        }
+        let client = &self.amqp_clients[&vhost];
+        match client.peek_messages(queue_name, &options).await {
+            Ok(messages) => {
+                self.status = format!(
+                    "Peeked {} message(s) from '{}' at {}",
+                    messages.len(),
+                    queue_name,
+                    Local::now().format("%H:%M:%S")
+                );
+                self.peek = Some(QueuePeek {
+                    queue_name: queue_name.to_string(),
+                    messages,
+                });
+            }
+            Err(e) => {
+                self.status = format!("Error peeking into '{}': {}", queue_name, e);
+            }
+        }
+    }
+
+    async fn close_amqp_clients(&mut self) {
+        for (_, client) in self.amqp_clients.drain() {
+            let _ = client.close().await;
         }
     }
 
@@ -95,7 +215,11 @@ impl App {
         }
         let i = match self.queue_list_state.selected() {
             Some(i) => {
-                if i >= self.queues.len() - 1 { 0 } else { i + 1 }
+                if i >= self.queues.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
             }
             None => 0,
         };
@@ -108,7 +232,11 @@ impl App {
         }
         let i = match self.queue_list_state.selected() {
             Some(i) => {
-                if i == 0 { self.queues.len() - 1 } else { i - 1 }
+                if i == 0 {
+                    self.queues.len() - 1
+                } else {
+                    i - 1
+                }
             }
             None => 0,
         };
@@ -117,7 +245,7 @@ impl App {
 
     /// Fetches queue data from the RabbitMQ API and updates the app state.
     async fn on_tick(&mut self) {
-        match self.client.get_queues().await {
+        match self.client.list_queues(&self.query).await {
             Ok(queues) => {
                 if queues.is_empty() {
                     self.queue_list_state.select(None);
@@ -137,19 +265,18 @@ impl App {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let file_config = load_config_file()?;
-    let conn_info = file_config.connection;
+    let cli = Cli::parse();
+    let mut file_config = cli.config.load()?.config;
+    let conn_info = file_config.connection.clone();
     println!("Connecting as user: '{}'", conn_info.username);
-    let password = get_password()?;
-    let config = RabbitMQConfig {
-        host: conn_info.host,
-        amqp_port: conn_info.amqp_port,
-        management_port: conn_info.management_port,
-        username: conn_info.username,
-        password,
-        vhost: conn_info.vhost,
-    };
-    let client = RabbitMQApiClient::new(&config)?;
+    let config = conn_info.to_rabbitmq_config()?;
+    // Keep the password resolved now, so that peeking never prompts for it once the TUI is in raw mode.
+    file_config.connection.password = Some(config.password.clone());
+    let client = RabbitMQApiClient::from_settings(
+        &config,
+        conn_info.use_tls,
+        conn_info.tls_options.as_ref(),
+    )?;
     println!("Checking RabbitMQ connection...");
     if let Err(e) = client.is_alive().await {
         eprintln!("Failed to connect to RabbitMQ: {}", e);
@@ -158,7 +285,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("RabbitMQ is alive. Launching monitor...");
     tokio::time::sleep(Duration::from_secs(1)).await;
 
-    let mut app = App::new(client);
+    let query = ListQuery {
+        vhost: cli.vhost,
+        page_size: cli.page_size,
+        use_regex: cli.filter.is_some(),
+        name: cli.filter,
+        columns: QUEUE_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect(),
+        ..Default::default()
+    };
+    let mut app = App::new(client, query, file_config, cli.peek_limit);
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -166,9 +304,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     let res = run_app(&mut terminal, &mut app).await;
+    app.close_amqp_clients().await;
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
     if let Err(err) = res {
         eprintln!("Error: {:?}", err);
@@ -176,7 +319,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> io::Result<()> {
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_secs(5);
     app.on_tick().await;
@@ -187,7 +333,9 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
         }
         terminal.draw(|f| ui(f, app))?;
 
-        let timeout = tick_rate.checked_sub(last_tick.elapsed()).unwrap_or_else(|| Duration::from_secs(0));
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
         if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 // Global key bindings
@@ -207,15 +355,16 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
                         KeyCode::Enter => {
                             if let Some(selected) = app.queue_list_state.selected() {
                                 if let Some(queue) = app.queues.get(selected) {
-                                    let queue_name = queue["name"].as_str().unwrap_or("").to_string();
+                                    let queue_name = queue.name.clone();
                                     app.push_view(AppView::QueueDetail { queue_name });
                                 }
                             }
                         }
                         _ => {}
                     },
-                    AppView::QueueDetail { .. } => match key.code {
+                    AppView::QueueDetail { queue_name } => match key.code {
                         KeyCode::Char('q') | KeyCode::Esc | KeyCode::Backspace => app.pop_view(),
+                        KeyCode::Char('p') => app.peek_queue(&queue_name).await,
                         _ => {}
                     },
                 }
@@ -233,16 +382,33 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
         .split(f.area());
 
-    let breadcrumbs = app.view_stack.iter().map(|view| match view {
-        AppView::QueueList => "Queues",
-        AppView::QueueDetail { queue_name } => queue_name.as_str(),
-    }).collect::<Vec<&str>>().join(" > ");
-    
+    let breadcrumbs = app
+        .view_stack
+        .iter()
+        .map(|view| match view {
+            AppView::QueueList => "Queues",
+            AppView::QueueDetail { queue_name } => queue_name.as_str(),
+        })
+        .collect::<Vec<&str>>()
+        .join(" > ");
+
     let header_paragraph = Paragraph::new(Line::from(vec![
-        Span::styled("RabbitMQ Monitor", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::styled(
+            "RabbitMQ Monitor",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
         Span::raw(" | "),
         Span::styled(breadcrumbs, Style::default().fg(Color::Yellow)),
     ]))
@@ -252,25 +418,58 @@ fn ui(f: &mut ratatui::Frame<'_>, app: &mut App) {
     let current_view = app.current_view().clone();
     match current_view {
         AppView::QueueList => draw_queue_list(f, &app.queues, &mut app.queue_list_state, chunks[1]),
-        AppView::QueueDetail { queue_name } => draw_queue_details(f, &app.queues, chunks[1], &queue_name),
+        AppView::QueueDetail { queue_name } => {
+            let detail_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(15), Constraint::Min(0)].as_ref())
+                .split(chunks[1]);
+            draw_queue_details(f, &app.queues, detail_chunks[0], &queue_name);
+            let messages = app
+                .peek
+                .as_ref()
+                .filter(|peek| peek.queue_name == queue_name);
+            draw_peeked_messages(
+                f,
+                messages.map(|peek| peek.messages.as_slice()),
+                detail_chunks[1],
+            );
+        }
     }
 
-    let footer_paragraph = Paragraph::new(Line::from(format!("Status: {} | Press 'q' to quit", app.status)))
+    let keys = match app.current_view() {
+        AppView::QueueList => "Press 'q' to quit",
+        AppView::QueueDetail { .. } => "Press 'p' to peek messages, 'q' to go back",
+    };
+    let footer_paragraph = Paragraph::new(Line::from(format!("Status: {} | {}", app.status, keys)))
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(footer_paragraph, chunks[2]);
 }
 
-fn draw_queue_list(f: &mut ratatui::Frame<'_>, queues: &[Value], state: &mut TableState, area: Rect) {
-    let header_cells = ["Queue", "Messages", "Consumers"]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
-    let header = Row::new(header_cells).style(Style::default().bg(Color::DarkGray)).height(1);
+fn draw_queue_list(
+    f: &mut ratatui::Frame<'_>,
+    queues: &[Queue],
+    state: &mut TableState,
+    area: Rect,
+) {
+    let header_cells = ["Queue", "Messages", "Consumers"].iter().map(|h| {
+        Cell::from(*h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells)
+        .style(Style::default().bg(Color::DarkGray))
+        .height(1);
 
     let rows = queues.iter().map(|q| {
-        let name = q["name"].as_str().unwrap_or("N/A");
-        let messages = q["messages"].as_i64().unwrap_or(0).to_string();
-        let consumers = q["consumers"].as_i64().unwrap_or(0).to_string();
-        Row::new(vec![Cell::from(name), Cell::from(messages), Cell::from(consumers)])
+        let messages = q.messages.unwrap_or(0).to_string();
+        let consumers = q.consumers.unwrap_or(0).to_string();
+        Row::new(vec![
+            Cell::from(q.name.as_str()),
+            Cell::from(messages),
+            Cell::from(consumers),
+        ])
     });
 
     let table = Table::new(
@@ -285,38 +484,136 @@ fn draw_queue_list(f: &mut ratatui::Frame<'_>, queues: &[Value], state: &mut Tab
     .block(Block::default().borders(Borders::ALL).title("Queues"))
     .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
     .highlight_symbol(">> ");
-    
+
     f.render_stateful_widget(table, area, state);
 }
 
-fn draw_queue_details(f: &mut ratatui::Frame<'_>, queues: &[Value], area: Rect, queue_name: &str) {
-    let items: Vec<ListItem> = if let Some(queue) = queues.iter().find(|q| q["name"].as_str() == Some(queue_name)) {
+/// Formats an optional statistic, which the broker omits when it is not collected.
+fn or_na<T: fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "N/A".to_string(), |v| v.to_string())
+}
+
+fn draw_queue_details(f: &mut ratatui::Frame<'_>, queues: &[Queue], area: Rect, queue_name: &str) {
+    let items: Vec<ListItem> = if let Some(queue) = queues.iter().find(|q| q.name == queue_name) {
         // A helper to create a styled list item
-        let create_item = |key: &str, value: &Value| {
+        let create_item = |key: &str, value: String| {
             let content = Line::from(vec![
                 Span::styled(format!("{:<20}", key), Style::default().fg(Color::Cyan)),
-                Span::raw(value.to_string().trim_matches('"').to_string()),
+                Span::raw(value),
             ]);
             ListItem::new(content)
         };
+        let stats = queue.message_stats.clone().unwrap_or_default();
 
         vec![
-            create_item("Name", &queue["name"]),
-            create_item("Vhost", &queue["vhost"]),
-            create_item("Durable", &queue["durable"]),
-            create_item("Auto Delete", &queue["auto_delete"]),
-            create_item("Messages", &queue["messages"]),
-            create_item("Messages Ready", &queue["messages_ready"]),
-            create_item("Consumers", &queue["consumers"]),
-            create_item("Memory", &queue["memory"]),
-            create_item("State", &queue["state"]),
+            create_item("Name", queue.name.clone()),
+            create_item("Vhost", queue.vhost.clone()),
+            create_item("Type", or_na(&queue.queue_type)),
+            create_item("Durable", queue.durable.to_string()),
+            create_item("Auto Delete", queue.auto_delete.to_string()),
+            create_item("Messages", or_na(&queue.messages)),
+            create_item("Messages Ready", or_na(&queue.messages_ready)),
+            create_item("Consumers", or_na(&queue.consumers)),
+            create_item("Memory", or_na(&queue.memory)),
+            create_item("State", or_na(&queue.state)),
+            create_item("Publish Rate", format!("{:.1}/s", stats.publish_rate())),
+            create_item("Deliver Rate", format!("{:.1}/s", stats.deliver_rate())),
+            create_item("Ack Rate", format!("{:.1}/s", stats.ack_rate())),
         ]
     } else {
-        vec![ListItem::new(Line::from(format!("Details for queue '{}' not found.", queue_name)))]
+        vec![ListItem::new(Line::from(format!(
+            "Details for queue '{}' not found.",
+            queue_name
+        )))]
     };
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(format!("Details for {}", queue_name)));
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Details for {}", queue_name)),
+    );
+
+    f.render_widget(list, area);
+}
+
+/// Longest payload excerpt shown for a peeked message.
+const PAYLOAD_PREVIEW_CHARS: usize = 200;
+
+fn draw_peeked_messages(
+    f: &mut ratatui::Frame<'_>,
+    messages: Option<&[PeekedMessage]>,
+    area: Rect,
+) {
+    let items: Vec<ListItem> = match messages {
+        None => vec![ListItem::new(Line::from(
+            "Press 'p' to show the first messages without consuming them.",
+        ))],
+        Some([]) => vec![ListItem::new(Line::from(
+            "The queue has no ready messages.",
+        ))],
+        Some(messages) => messages
+            .iter()
+            .flat_map(peeked_message_lines)
+            .map(ListItem::new)
+            .collect(),
+    };
 
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Messages (peek)"),
+    );
     f.render_widget(list, area);
 }
+
+/// A summary line for a peeked message, followed by its properties, headers and payload.
+fn peeked_message_lines(peeked: &PeekedMessage) -> Vec<Line<'static>> {
+    let message = &peeked.message;
+    let mut summary = vec![
+        Span::styled(
+            format!("#{:<4}", peeked.position),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(format!(
+            "exchange '{}' routing key '{}', {} bytes",
+            message.exchange,
+            message.routing_key,
+            message.payload.len()
+        )),
+    ];
+    if let Some(offset) = peeked.stream_offset {
+        summary.push(Span::raw(format!(", offset {}", offset)));
+    }
+    if peeked.redelivered {
+        summary.push(Span::styled(
+            " redelivered",
+            Style::default().fg(Color::Red),
+        ));
+    }
+
+    let mut lines = vec![Line::from(summary)];
+    for (name, value) in peeked.properties().display_fields() {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("      {:<24}", name),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(value),
+        ]));
+    }
+    let payload: String = String::from_utf8_lossy(&message.payload)
+        .chars()
+        .take(PAYLOAD_PREVIEW_CHARS)
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("      {:<24}", "payload"),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(payload),
+    ]));
+    lines
+}
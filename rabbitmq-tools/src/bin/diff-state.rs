@@ -0,0 +1,63 @@
+use clap::{Parser, ValueEnum};
+use rabbitmq_info::diff::diff_definitions;
+use rabbitmq_info::restore::load_snapshot;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Compares two `dump-state` snapshots and shows what changed on the broker in between.
+///
+/// Exits with 0 when the snapshots match, 1 when they differ and 2 on errors.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// The older snapshot.
+    before: PathBuf,
+
+    /// The newer snapshot.
+    after: PathBuf,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Disable colors. Colors are only used when writing to a terminal.
+    #[arg(long)]
+    no_color: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the snapshots match.
+fn run(cli: &Cli) -> Result<bool, Box<dyn std::error::Error>> {
+    let before = load_snapshot(&cli.before)?;
+    let after = load_snapshot(&cli.after)?;
+
+    let diff = diff_definitions(&before, &after);
+    match cli.format {
+        Format::Text => {
+            let color = !cli.no_color && std::io::stdout().is_terminal();
+            print!("{}", diff.to_text(color));
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    Ok(diff.is_empty())
+}
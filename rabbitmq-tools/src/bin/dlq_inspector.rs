@@ -0,0 +1,290 @@
+use chrono::{DateTime, TimeZone, Utc};
+use clap::{Args, Parser, Subcommand};
+use rabbitmq_config::{
+    ConfigArgs, DeadLetter, DeadLetterFilter, RabbitMQClient, ReplayOptions, ReplayStatus,
+    ReplayTarget,
+};
+
+/// Browses dead letter queues and replays their messages.
+///
+/// Browsing never removes messages: they are fetched unacknowledged and returned to
+/// the queue in their original order.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List messages with their decoded `x-death` headers.
+    List {
+        /// The dead letter queue.
+        queue: String,
+
+        /// Read at most this many messages from the head of the queue.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Also print each payload.
+        #[arg(long)]
+        payload: bool,
+
+        /// Print the messages as JSON.
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Republish messages to their original exchange and routing key, or to --exchange.
+    Replay {
+        /// The dead letter queue.
+        queue: String,
+
+        /// Replay the message at this position, as shown by `list`. May be given more than once.
+        #[arg(long = "position", required_unless_present = "all")]
+        positions: Vec<usize>,
+
+        /// Replay every message matching the filters.
+        #[arg(long, conflicts_with = "positions")]
+        all: bool,
+
+        /// Publish to this exchange instead of the original one.
+        #[arg(long)]
+        exchange: Option<String>,
+
+        /// Publish with this routing key instead of the original one. Requires --exchange.
+        #[arg(long, requires = "exchange")]
+        routing_key: Option<String>,
+
+        /// Replay at most this many messages per second.
+        #[arg(long)]
+        rate: Option<f64>,
+
+        /// Show what would be replayed without publishing or removing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+/// Criteria matched against each `x-death` entry.
+#[derive(Args)]
+struct FilterArgs {
+    /// Only messages dead-lettered for this reason: rejected, expired, maxlen or delivery_limit.
+    #[arg(long)]
+    reason: Option<String>,
+
+    /// Only messages dead-lettered from this queue.
+    #[arg(long = "from-queue")]
+    from_queue: Option<String>,
+
+    /// Only messages dead-lettered at least this many times.
+    #[arg(long)]
+    min_count: Option<u64>,
+
+    /// Only messages dead-lettered at or after this RFC 3339 time.
+    #[arg(long, value_parser = parse_time)]
+    since: Option<u64>,
+
+    /// Only messages dead-lettered at or before this RFC 3339 time.
+    #[arg(long, value_parser = parse_time)]
+    until: Option<u64>,
+}
+
+impl From<FilterArgs> for DeadLetterFilter {
+    fn from(args: FilterArgs) -> Self {
+        Self {
+            reason: args.reason,
+            queue: args.from_queue,
+            min_count: args.min_count,
+            since: args.since,
+            until: args.until,
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<u64, String> {
+    let time = DateTime::parse_from_rfc3339(value)
+        .map_err(|e| format!("expected an RFC 3339 time: {e}"))?;
+    u64::try_from(time.timestamp()).map_err(|_| "time is before the Unix epoch".to_string())
+}
+
+fn format_time(seconds: Option<u64>) -> String {
+    seconds
+        .and_then(|seconds| i64::try_from(seconds).ok())
+        .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    // --- Connect to RabbitMQ ---
+    let file_config = cli.config.load()?.config;
+    eprintln!(
+        "Connecting to RabbitMQ as user: '{}'",
+        file_config.connection.username
+    );
+    let client = RabbitMQClient::from_config(&file_config).await?;
+
+    let result = match cli.command {
+        Command::List {
+            queue,
+            limit,
+            payload,
+            json,
+            filter,
+        } => list(&client, &queue, limit, payload, json, filter.into()).await,
+        Command::Replay {
+            queue,
+            positions,
+            all,
+            exchange,
+            routing_key,
+            rate,
+            dry_run,
+            filter,
+        } => {
+            let options = ReplayOptions {
+                filter: filter.into(),
+                positions: (!all).then_some(positions),
+                target: exchange.map(|exchange| ReplayTarget {
+                    exchange,
+                    routing_key,
+                }),
+                rate_limit: rate,
+                dry_run,
+            };
+            replay(&client, &queue, &options).await
+        }
+    };
+    client.close().await?;
+    result
+}
+
+async fn list(
+    client: &RabbitMQClient,
+    queue: &str,
+    limit: usize,
+    show_payload: bool,
+    json: bool,
+    filter: DeadLetterFilter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dead_letters: Vec<DeadLetter> = client
+        .browse_dead_letters(queue, limit)
+        .await?
+        .into_iter()
+        .filter(|dead_letter| filter.matches(dead_letter))
+        .collect();
+
+    if json {
+        let entries: Vec<serde_json::Value> = dead_letters
+            .iter()
+            .map(|dead_letter| {
+                let original = dead_letter.original_target();
+                serde_json::json!({
+                    "position": dead_letter.position,
+                    "original_exchange": original.as_ref().map(|(exchange, _)| exchange),
+                    "original_routing_key": original.as_ref().map(|(_, routing_key)| routing_key),
+                    "deaths": dead_letter.deaths,
+                    "bytes": dead_letter.message.payload.len(),
+                    "payload": show_payload.then(|| String::from_utf8_lossy(&dead_letter.message.payload).into_owned()),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for dead_letter in &dead_letters {
+        let original = match dead_letter.original_target() {
+            Some((exchange, routing_key)) => format!("'{exchange}' / '{routing_key}'"),
+            None => "unknown".to_string(),
+        };
+        println!(
+            "#{:<4} {} bytes, originally published to {}",
+            dead_letter.position,
+            dead_letter.message.payload.len(),
+            original
+        );
+        for death in &dead_letter.deaths {
+            println!(
+                "       {} from queue '{}' x{} at {}",
+                death.reason,
+                death.queue,
+                death.count,
+                format_time(death.time)
+            );
+        }
+        if show_payload {
+            println!(
+                "       {}",
+                String::from_utf8_lossy(&dead_letter.message.payload)
+            );
+        }
+    }
+    println!("{} message(s) shown.", dead_letters.len());
+    Ok(())
+}
+
+async fn replay(
+    client: &RabbitMQClient,
+    queue: &str,
+    options: &ReplayOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = client.replay_dead_letters(queue, options).await?;
+
+    for result in &report.results {
+        let target = match &result.target {
+            Some((exchange, routing_key)) => format!("'{exchange}' / '{routing_key}'"),
+            None => "-".to_string(),
+        };
+        match &result.status {
+            ReplayStatus::Replayed => println!("#{:<4} replayed to {}", result.position, target),
+            ReplayStatus::DryRun => println!("#{:<4} would replay to {}", result.position, target),
+            ReplayStatus::Failed(reason) => {
+                println!("#{:<4} failed ({}): {}", result.position, target, reason)
+            }
+        }
+    }
+    if options.dry_run {
+        println!(
+            "Dry run: {} of {} scanned message(s) would be replayed.",
+            report.results.len() - report.failed(),
+            report.scanned
+        );
+    } else {
+        println!(
+            "Replayed {} of {} scanned message(s), {} failed.",
+            report.replayed(),
+            report.scanned,
+            report.failed()
+        );
+    }
+    if let Some(reason) = &report.stopped {
+        return Err(format!(
+            "Replay stopped after {} message(s), the rest were left in '{queue}': {reason}",
+            report.scanned
+        )
+        .into());
+    }
+    if report.failed() > 0 {
+        return Err(format!(
+            "{} message(s) could not be replayed and were left in '{queue}'",
+            report.failed()
+        )
+        .into());
+    }
+    Ok(())
+}
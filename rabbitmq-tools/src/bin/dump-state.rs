@@ -1,31 +1,40 @@
 use chrono::Local;
-use rabbitmq_config::{get_password, load_config_file, RabbitMQConfig};
+use clap::Parser;
+use rabbitmq_config::ConfigArgs;
 use rabbitmq_info::api::RabbitMQApiClient;
 use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
+/// Saves the broker's definitions to a timestamped snapshot in `artifacts/`.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+    let cli = Cli::parse();
 
     // --- Connect to RabbitMQ ---
-    let file_config = load_config_file()?;
+    let file_config = cli.config.load()?.config;
     let conn_info = file_config.connection;
 
-    println!("Connecting to RabbitMQ Management API as user: '{}'", conn_info.username);
-    let password = get_password()?;
+    println!(
+        "Connecting to RabbitMQ Management API as user: '{}'",
+        conn_info.username
+    );
 
-    let config = RabbitMQConfig {
-        host: conn_info.host,
-        amqp_port: conn_info.amqp_port,
-        management_port: conn_info.management_port,
-        username: conn_info.username,
-        password,
-        vhost: conn_info.vhost,
-    };
+    let config = conn_info.to_rabbitmq_config()?;
 
-    let client = RabbitMQApiClient::new(&config)?;
+    let client = RabbitMQApiClient::from_settings(
+        &config,
+        conn_info.use_tls,
+        conn_info.tls_options.as_ref(),
+    )?;
 
     // --- Fetch Definitions ---
     println!("Fetching server definitions...");
@@ -35,7 +44,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // --- Save to File ---
     let timestamp = Local::now().format("%Y%m%d_%H%M%S");
     let filename = format!("server_state_{}.json", timestamp);
-    
+
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("../artifacts");
     fs::create_dir_all(&path)?; // Ensure the artifacts directory exists
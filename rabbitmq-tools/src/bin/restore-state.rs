@@ -0,0 +1,99 @@
+use clap::Parser;
+use rabbitmq_config::{ConfigArgs, RabbitMQServerDefinition};
+use rabbitmq_info::api::RabbitMQApiClient;
+use rabbitmq_info::restore::{
+    apply_restore, load_snapshot, plan_restore, DefinitionKind, RestoreFilter,
+};
+use std::fs;
+use std::path::PathBuf;
+
+/// Restores a `dump-state` snapshot through the management API.
+///
+/// Objects are created or overwritten; objects missing from the snapshot are left alone.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Snapshot to restore. Defaults to the newest `artifacts/server_state_*.json`.
+    snapshot: Option<PathBuf>,
+
+    /// Only restore objects in this vhost. May be given more than once.
+    #[arg(long = "vhost")]
+    vhosts: Vec<String>,
+
+    /// Only restore these object types, e.g. `queues,exchanges,bindings`.
+    #[arg(long, value_delimiter = ',')]
+    only: Vec<DefinitionKind>,
+
+    /// Print what would change without changing anything.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    let snapshot_path = match cli.snapshot {
+        Some(path) => path,
+        None => latest_snapshot()?,
+    };
+    println!("Loading snapshot: {:?}", snapshot_path);
+    let snapshot = load_snapshot(&snapshot_path)?;
+
+    // --- Connect to RabbitMQ ---
+    let file_config = cli.config.load()?.config;
+    let conn_info = file_config.connection;
+
+    println!(
+        "Connecting to RabbitMQ Management API as user: '{}'",
+        conn_info.username
+    );
+
+    let config = conn_info.to_rabbitmq_config()?;
+
+    let client = RabbitMQApiClient::from_settings(
+        &config,
+        conn_info.use_tls,
+        conn_info.tls_options.as_ref(),
+    )?;
+    let live: RabbitMQServerDefinition = serde_json::from_value(client.get_definitions().await?)?;
+
+    let filter = RestoreFilter {
+        vhosts: cli.vhosts,
+        kinds: cli.only.into_iter().collect(),
+    };
+    let plan = plan_restore(&snapshot, &live, &filter);
+    print!("{}", plan.to_text());
+
+    if cli.dry_run {
+        println!("Dry run: nothing was changed.");
+        return Ok(());
+    }
+    let applied = apply_restore(&client, &plan).await?;
+    println!("Successfully restored {} object(s).", applied);
+    Ok(())
+}
+
+/// Finds the most recent snapshot written by `dump-state`.
+fn latest_snapshot() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("../artifacts");
+
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("server_state_") && name.ends_with(".json"))
+        })
+        .collect();
+    // Timestamps in the file names sort chronologically.
+    snapshots.sort();
+    snapshots
+        .pop()
+        .ok_or_else(|| format!("No server_state_*.json snapshot found in {:?}", dir).into())
+}
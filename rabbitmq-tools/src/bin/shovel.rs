@@ -0,0 +1,186 @@
+use clap::{ArgGroup, Parser};
+use rabbitmq_config::{
+    parse_override, ConfigArgs, ConfigLoader, RabbitMQClient, RabbitMQFullConfig, ShovelOptions,
+    ShovelProgress, ShovelStop, ShovelTarget,
+};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Moves messages from a queue to another queue or exchange, on the same or another vhost or broker.
+///
+/// Each message is acked on the source only after the destination has confirmed it.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("destination").required(true).args(["to_queue", "to_exchange"])))]
+struct Cli {
+    /// The queue to move messages out of.
+    #[arg(long)]
+    from_queue: String,
+
+    /// Publish into this queue through the default exchange.
+    #[arg(long)]
+    to_queue: Option<String>,
+
+    /// Publish to this exchange.
+    #[arg(long)]
+    to_exchange: Option<String>,
+
+    /// Routing key for --to-exchange. Defaults to each message's own routing key.
+    #[arg(long, requires = "to_exchange")]
+    routing_key: Option<String>,
+
+    /// Configuration file of the destination broker, read on its own: the source
+    /// --config, --profile and --set do not apply to it. Without it the destination
+    /// starts from the source configuration, including those options.
+    #[arg(long)]
+    dest_config: Option<PathBuf>,
+
+    /// Profile of the destination configuration.
+    #[arg(long)]
+    dest_profile: Option<String>,
+
+    /// Destination vhost, if it differs from the source vhost.
+    #[arg(long)]
+    dest_vhost: Option<String>,
+
+    /// Overrides a destination configuration value, e.g. `--dest-set connection.host=rabbit2`.
+    #[arg(long = "dest-set", value_name = "KEY=VALUE", value_parser = parse_override)]
+    dest_overrides: Vec<(String, String)>,
+
+    /// Stop after moving this many messages.
+    #[arg(long)]
+    count: Option<u64>,
+
+    /// Stop before moving more than this many payload bytes.
+    #[arg(long)]
+    bytes: Option<u64>,
+
+    /// Stop once the source queue is empty instead of waiting for new messages.
+    #[arg(long)]
+    until_empty: bool,
+
+    /// Move at most this many messages per second.
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Messages published but not yet confirmed at any time.
+    #[arg(long, default_value_t = 100)]
+    max_in_flight: usize,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+impl Cli {
+    fn has_destination_config(&self) -> bool {
+        self.dest_config.is_some()
+            || self.dest_profile.is_some()
+            || self.dest_vhost.is_some()
+            || !self.dest_overrides.is_empty()
+    }
+
+    /// The destination configuration: `--dest-config` with only the environment
+    /// variables applied, or else the source configuration, with the destination
+    /// profile, vhost and overrides on top.
+    fn destination_config(&self) -> Result<RabbitMQFullConfig, Box<dyn std::error::Error>> {
+        let mut loader = match &self.dest_config {
+            Some(path) => ConfigLoader::new().file(path),
+            None => self.config.loader(),
+        };
+        if let Some(profile) = &self.dest_profile {
+            loader = loader.profile(profile);
+        }
+        if let Some(vhost) = &self.dest_vhost {
+            loader = loader.set("connection.vhost", vhost);
+        }
+        for (key, value) in &self.dest_overrides {
+            loader = loader.set(key, value);
+        }
+        Ok(loader.load()?.config)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    // --- Connect to RabbitMQ ---
+    let source_config = cli.config.load()?.config;
+    eprintln!(
+        "Connecting to source '{}' vhost '{}' as user: '{}'",
+        source_config.connection.host,
+        source_config.connection.vhost,
+        source_config.connection.username
+    );
+    let source = RabbitMQClient::from_config(&source_config).await?;
+
+    let destination = if cli.has_destination_config() {
+        let config = cli.destination_config()?;
+        eprintln!(
+            "Connecting to destination '{}' vhost '{}' as user: '{}'",
+            config.connection.host, config.connection.vhost, config.connection.username
+        );
+        Some(RabbitMQClient::from_config(&config).await?)
+    } else {
+        None
+    };
+
+    let target = match (&cli.to_queue, &cli.to_exchange) {
+        (Some(queue), _) => ShovelTarget::queue(queue),
+        (None, exchange) => ShovelTarget {
+            exchange: exchange.clone().unwrap_or_default(),
+            routing_key: cli.routing_key.clone(),
+        },
+    };
+    let options = ShovelOptions {
+        max_messages: cli.count,
+        max_bytes: cli.bytes,
+        until_empty: cli.until_empty,
+        rate_limit: cli.rate,
+        max_in_flight: cli.max_in_flight,
+    };
+
+    let mut last_report = Instant::now();
+    let result = source
+        .shovel(
+            &cli.from_queue,
+            destination.as_ref().unwrap_or(&source),
+            &target,
+            &options,
+            |progress| {
+                if last_report.elapsed() >= Duration::from_secs(1) {
+                    last_report = Instant::now();
+                    eprintln!("{}", format_progress(progress));
+                }
+            },
+        )
+        .await;
+
+    if let Some(destination) = &destination {
+        destination.close().await?;
+    }
+    source.close().await?;
+
+    let report = result?;
+    let reason = match report.stopped {
+        ShovelStop::MessageLimit => "message limit reached",
+        ShovelStop::ByteLimit => "byte limit reached",
+        ShovelStop::Empty => "source queue empty",
+    };
+    println!("{} ({reason}).", format_progress(&report.progress));
+    Ok(())
+}
+
+fn format_progress(progress: &ShovelProgress) -> String {
+    let seconds = progress.elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 {
+        progress.messages as f64 / seconds
+    } else {
+        0.0
+    };
+    format!(
+        "Moved {} message(s), {} bytes in {:.1}s ({:.1} msg/s), {} left in source",
+        progress.messages, progress.bytes, seconds, rate, progress.remaining
+    )
+}
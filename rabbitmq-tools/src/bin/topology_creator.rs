@@ -1,8 +1,9 @@
-use rabbitmq_config::{get_password, load_config_file, RabbitMQClient, RabbitMQConfig};
+use clap::Parser;
+use lapin::types::{AMQPValue, FieldTable};
+use rabbitmq_config::{ConfigArgs, RabbitMQClient};
 use serde::Deserialize;
 use std::fs;
 use std::path::PathBuf;
-use lapin::types::{AMQPValue, FieldTable};
 
 #[derive(Debug, Deserialize)]
 struct MessageTypes {
@@ -23,27 +24,27 @@ struct MessageType {
     durable: bool,
 }
 
+/// Declares an exchange per category of `artifacts/message_types.json` and a queue per message type.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+    let cli = Cli::parse();
 
     // --- Connect to RabbitMQ ---
-    let file_config = load_config_file()?;
-    let conn_info = file_config.connection;
-
-    println!("Connecting to RabbitMQ as user: '{}'", conn_info.username);
-    let password = get_password()?;
-
-    let config = RabbitMQConfig {
-        host: conn_info.host,
-        amqp_port: conn_info.amqp_port,
-        management_port: conn_info.management_port,
-        username: conn_info.username,
-        password,
-        vhost: conn_info.vhost,
-    };
-
-    let client = RabbitMQClient::new(config).await?;
+    let file_config = cli.config.load()?.config;
+    println!(
+        "Connecting to RabbitMQ as user: '{}'",
+        file_config.connection.username
+    );
+
+    let client = RabbitMQClient::from_config(&file_config).await?;
     println!("Successfully connected to RabbitMQ.");
 
     // --- Load Topology Definition ---
@@ -71,11 +72,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         for msg_type in category.types {
             let queue_name = msg_type.name.clone();
-            
+
             let mut arguments = FieldTable::default();
             if msg_type.priority > 0 {
-                println!("- Declaring priority queue: {} with max-priority={}", queue_name, msg_type.priority);
-                arguments.insert("x-max-priority".into(), AMQPValue::LongLongInt(msg_type.priority as i64));
+                println!(
+                    "- Declaring priority queue: {} with max-priority={}",
+                    queue_name, msg_type.priority
+                );
+                arguments.insert(
+                    "x-max-priority".into(),
+                    AMQPValue::LongLongInt(msg_type.priority as i64),
+                );
             } else {
                 println!("- Declaring queue: {}", queue_name);
             }
@@ -89,8 +96,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             };
             client.declare_queue(&queue_info).await?;
 
-            println!("  - Binding queue {} to exchange {} with routing key {}", queue_name, exchange_name, queue_name);
-            client.bind_queue(&queue_name, &exchange_name, &queue_name).await?;
+            println!(
+                "  - Binding queue {} to exchange {} with routing key {}",
+                queue_name, exchange_name, queue_name
+            );
+            client
+                .bind_queue(&queue_name, &exchange_name, &queue_name)
+                .await?;
         }
     }
 
@@ -0,0 +1,117 @@
+use clap::{Args, Parser, Subcommand};
+use rabbitmq_config::{
+    plan_topology, ConfigArgs, PlanOptions, RabbitMQClient, Topology, TopologyPlan,
+};
+use rabbitmq_info::api::RabbitMQApiClient;
+use rabbitmq_info::collector::RabbitMQInfoCollector;
+use std::path::PathBuf;
+
+/// Plans and applies the changes needed to reach a desired topology.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the operations needed to reach the desired topology.
+    Plan {
+        #[command(flatten)]
+        target: Target,
+
+        /// Print the plan as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Execute the plan against the broker.
+    Apply {
+        #[command(flatten)]
+        target: Target,
+
+        /// Allow deleting and recreating queues and exchanges.
+        #[arg(long)]
+        allow_destructive: bool,
+    },
+}
+
+#[derive(Args)]
+struct Target {
+    /// Desired topology: a config file (TOML or JSON) or `message_types.json`.
+    #[arg(short, long, default_value = "artifacts/message_types.json")]
+    desired: PathBuf,
+
+    /// Also remove exchanges, queues and bindings that are not in the desired topology.
+    #[arg(long)]
+    prune: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    // --- Connect to RabbitMQ ---
+    let file_config = cli.config.load()?.config;
+    let conn_info = file_config.connection;
+
+    eprintln!("Connecting to RabbitMQ as user: '{}'", conn_info.username);
+
+    let config = conn_info.to_rabbitmq_config()?;
+    let api = RabbitMQApiClient::from_settings(
+        &config,
+        conn_info.use_tls,
+        conn_info.tls_options.as_ref(),
+    )?;
+
+    match cli.command {
+        Command::Plan { target, json } => {
+            let plan = build_plan(api, &config.vhost, &target).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plan)?);
+            } else {
+                print!("{}", plan.to_text());
+            }
+        }
+        Command::Apply {
+            target,
+            allow_destructive,
+        } => {
+            let plan = build_plan(api, &config.vhost, &target).await?;
+            print!("{}", plan.to_text());
+            if plan.is_empty() {
+                return Ok(());
+            }
+            if plan.destructive().next().is_some() && !allow_destructive {
+                eprintln!("The plan contains destructive operations (marked with '!'). Re-run with --allow-destructive to apply it.");
+                std::process::exit(1);
+            }
+
+            let client = RabbitMQClient::with_settings(config, conn_info).await?;
+            let applied = client.apply_plan(&plan, allow_destructive).await;
+            client.close().await?;
+            println!("Applied {} operation(s).", applied?);
+        }
+    }
+    Ok(())
+}
+
+/// Compares the desired topology with the live state of the configured vhost.
+async fn build_plan(
+    api: RabbitMQApiClient,
+    vhost: &str,
+    target: &Target,
+) -> Result<TopologyPlan, Box<dyn std::error::Error>> {
+    let desired = Topology::load(&target.desired)?;
+    let collector = RabbitMQInfoCollector::new(api);
+    let live = collector.collect_all().await?.topology(vhost);
+
+    let options = PlanOptions {
+        prune: target.prune,
+    };
+    Ok(plan_topology(&desired, &live, &options))
+}
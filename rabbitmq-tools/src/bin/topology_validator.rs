@@ -0,0 +1,90 @@
+use clap::{Parser, ValueEnum};
+use rabbitmq_config::{diff_topology, ConfigArgs, RabbitMQServerDefinition, Topology};
+use rabbitmq_info::api::RabbitMQApiClient;
+use rabbitmq_info::collector::RabbitMQInfoCollector;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Reports drift between a desired topology and the live broker without changing anything.
+///
+/// Exits with 0 when there is no drift, 1 when drift was found and 2 on errors.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Desired topology: a config file (TOML or JSON) or `message_types.json`.
+    #[arg(short, long, default_value = "artifacts/message_types.json")]
+    desired: PathBuf,
+
+    /// Compare against a saved `/api/definitions` export instead of the live broker.
+    #[arg(long)]
+    definitions: Option<PathBuf>,
+
+    /// Virtual host to compare. Defaults to the vhost of the connection config.
+    #[arg(long)]
+    vhost: Option<String>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    match run(&cli).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the live topology matches the desired one.
+async fn run(cli: &Cli) -> Result<bool, Box<dyn std::error::Error>> {
+    let desired = Topology::load(&cli.desired)?;
+
+    let live = match &cli.definitions {
+        Some(path) => {
+            let definitions: RabbitMQServerDefinition =
+                serde_json::from_str(&fs::read_to_string(path)?)?;
+            Topology::from_definitions(&definitions, cli.vhost.as_deref().unwrap_or("/"))
+        }
+        None => {
+            let conn_info = cli.config.load()?.config.connection;
+            eprintln!(
+                "Connecting to RabbitMQ Management API as user: '{}'",
+                conn_info.username
+            );
+            let vhost = cli.vhost.clone().unwrap_or_else(|| conn_info.vhost.clone());
+
+            let config = conn_info.to_rabbitmq_config()?;
+            let collector = RabbitMQInfoCollector::new(RabbitMQApiClient::from_settings(
+                &config,
+                conn_info.use_tls,
+                conn_info.tls_options.as_ref(),
+            )?);
+            collector.collect_all().await?.topology(&vhost)
+        }
+    };
+
+    let report = diff_topology(&desired, &live);
+    match cli.format {
+        Format::Table => print!("{}", report.to_table()),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(report.is_clean())
+}
@@ -1,9 +1,9 @@
-use std::sync::Arc;
-use tokio::runtime::Runtime;
-use tokio::sync::mpsc;
 use eframe::egui;
 use rabbitmq_config::{RabbitMQClient, RabbitMQConfig};
 use rabbitmq_info::api::RabbitMQApiClient;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
 
 // Enums for communication between UI and async tasks
 #[derive(Debug, Clone)]
@@ -27,7 +27,7 @@ pub struct AppState {
     runtime: Arc<Runtime>,
     request_tx: mpsc::Sender<UiRequest>,
     response_rx: mpsc::Receiver<ServerResponse>,
-    
+
     config: RabbitMQConfig,
     password_input: String,
     connection_status: bool,
@@ -58,7 +58,13 @@ impl AppState {
                                 response_tx.send(ServerResponse::Connected).await.ok();
                             }
                             Err(e) => {
-                                response_tx.send(ServerResponse::Error(format!("Connection failed: {}", e))).await.ok();
+                                response_tx
+                                    .send(ServerResponse::Error(format!(
+                                        "Connection failed: {}",
+                                        e
+                                    )))
+                                    .await
+                                    .ok();
                             }
                         }
                     }
@@ -73,28 +79,46 @@ impl AppState {
                         if let Some(client) = &api_client {
                             match client.get_queues().await {
                                 Ok(queues) => {
-                                    let queue_names = queues.into_iter()
-                                        .filter_map(|q| q.get("name").and_then(|n| n.as_str().map(String::from)))
-                                        .collect();
-                                    response_tx.send(ServerResponse::Queues(queue_names)).await.ok();
+                                    let queue_names = queues.into_iter().map(|q| q.name).collect();
+                                    response_tx
+                                        .send(ServerResponse::Queues(queue_names))
+                                        .await
+                                        .ok();
                                 }
                                 Err(e) => {
-                                    response_tx.send(ServerResponse::Error(format!("Failed to get queues: {}", e))).await.ok();
+                                    response_tx
+                                        .send(ServerResponse::Error(format!(
+                                            "Failed to get queues: {}",
+                                            e
+                                        )))
+                                        .await
+                                        .ok();
                                 }
                             }
                             match client.get_exchanges().await {
                                 Ok(exchanges) => {
-                                    let exchange_names = exchanges.into_iter()
-                                        .filter_map(|e| e.get("name").and_then(|n| n.as_str().map(String::from)))
-                                        .collect();
-                                    response_tx.send(ServerResponse::Exchanges(exchange_names)).await.ok();
+                                    let exchange_names =
+                                        exchanges.into_iter().map(|e| e.name).collect();
+                                    response_tx
+                                        .send(ServerResponse::Exchanges(exchange_names))
+                                        .await
+                                        .ok();
                                 }
                                 Err(e) => {
-                                    response_tx.send(ServerResponse::Error(format!("Failed to get exchanges: {}", e))).await.ok();
+                                    response_tx
+                                        .send(ServerResponse::Error(format!(
+                                            "Failed to get exchanges: {}",
+                                            e
+                                        )))
+                                        .await
+                                        .ok();
                                 }
                             }
                         } else {
-                            response_tx.send(ServerResponse::Error("Not connected".to_string())).await.ok();
+                            response_tx
+                                .send(ServerResponse::Error("Not connected".to_string()))
+                                .await
+                                .ok();
                         }
                     }
                 }
@@ -175,19 +199,28 @@ impl eframe::App for App {
                                 ui.end_row();
 
                                 ui.label("Password:");
-                                ui.add(egui::TextEdit::singleline(&mut self.state.password_input).password(true));
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.state.password_input)
+                                        .password(true),
+                                );
                                 ui.end_row();
                             });
-                        
+
                         if ui.button("Connect").clicked() {
                             let mut config = self.state.config.clone();
                             config.password = self.state.password_input.clone();
-                            self.state.request_tx.blocking_send(UiRequest::Connect(config)).ok();
+                            self.state
+                                .request_tx
+                                .blocking_send(UiRequest::Connect(config))
+                                .ok();
                         }
                     });
                 } else {
                     if ui.button("Disconnect").clicked() {
-                        self.state.request_tx.blocking_send(UiRequest::Disconnect).ok();
+                        self.state
+                            .request_tx
+                            .blocking_send(UiRequest::Disconnect)
+                            .ok();
                     }
                     if ui.button("Refresh").clicked() {
                         self.state.request_tx.blocking_send(UiRequest::Refresh).ok();
@@ -198,23 +231,34 @@ impl eframe::App for App {
 
         egui::SidePanel::left("left_panel").show(ctx, |ui| {
             ui.heading("Queues");
-            egui::ScrollArea::vertical().id_source("queues_scroll").show(ui, |ui| {
-                for queue in &self.state.available_queues {
-                    ui.label(queue);
-                }
-            });
+            egui::ScrollArea::vertical()
+                .id_source("queues_scroll")
+                .show(ui, |ui| {
+                    for queue in &self.state.available_queues {
+                        ui.label(queue);
+                    }
+                });
             ui.separator();
             ui.heading("Exchanges");
-            egui::ScrollArea::vertical().id_source("exchanges_scroll").show(ui, |ui| {
-                for exchange in &self.state.available_exchanges {
-                    ui.label(exchange);
-                }
-            });
+            egui::ScrollArea::vertical()
+                .id_source("exchanges_scroll")
+                .show(ui, |ui| {
+                    for exchange in &self.state.available_exchanges {
+                        ui.label(exchange);
+                    }
+                });
         });
-        
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("RabbitMQ UI");
-            ui.label(format!("Connection Status: {}", if self.state.connection_status { "Connected" } else { "Disconnected" }));
+            ui.label(format!(
+                "Connection Status: {}",
+                if self.state.connection_status {
+                    "Connected"
+                } else {
+                    "Disconnected"
+                }
+            ));
             ui.separator();
             ui.label(&self.state.status_message);
         });